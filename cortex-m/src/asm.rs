@@ -65,6 +65,9 @@ pub fn udf() -> ! {
 }
 
 /// Wait For Event
+///
+/// Sleeps until the event register is set, then clears it. If it is already set, clears it and
+/// returns right away. See [`send_event`] for how to wait for a flag without missing wakeups.
 #[cfg(cortex_m)]
 #[inline(always)]
 pub fn wfe() {
@@ -79,12 +82,70 @@ pub fn wfi() {
 }
 
 /// Send Event
+///
+/// See [`send_event`].
 #[cfg(cortex_m)]
 #[inline(always)]
 pub fn sev() {
     unsafe { asm!("sev", options(nomem, nostack, preserves_flags)) };
 }
 
+/// Yield hint
+///
+/// Emits `YIELD`, which tells a core that runs several hardware threads that this one is in a
+/// spin-wait. On M-profile hardware it executes as a `NOP`; it's meant for simulators and trace
+/// tools that model spin loops from it. To save power while waiting use [`wfe`] instead.
+#[cfg(cortex_m)]
+#[inline(always)]
+pub fn yield_hint() {
+    unsafe { asm!("yield", options(nomem, nostack, preserves_flags)) };
+}
+
+/// Send Event, an alias of [`sev`]
+///
+/// Sets the event register, which wakes up a [`wfe`] that is sleeping, or makes the next one
+/// return immediately.
+///
+/// # Waiting for a flag
+///
+/// The event register is a single latched bit, so the correct way to sleep until an interrupt
+/// handler sets a flag is to check the flag, `WFE`, and check again:
+///
+/// ```no_run
+/// use core::sync::atomic::{AtomicBool, Ordering};
+/// use cortex_m::asm;
+///
+/// static READY: AtomicBool = AtomicBool::new(false);
+///
+/// // sleeper, in thread mode
+/// while !READY.swap(false, Ordering::Acquire) {
+///     asm::wfe();
+/// }
+///
+/// // waker, e.g. another core, or a DMA completion callback run by another core's handler
+/// READY.store(true, Ordering::Release);
+/// asm::send_event();
+/// ```
+///
+/// If the waker runs between the check and the `WFE`, its `SEV` leaves the event register set and
+/// the `WFE` returns right away, so the wakeup isn't lost. A `WFE` that returns for another reason
+/// just leads to another check. Don't try to clear a stale event with a `SEV; WFE` pair before
+/// waiting, the loop above already tolerates it.
+///
+/// When the waker is an interrupt handler on the same core, no `SEV` is needed: exception entry
+/// and exception return both set the event register, so the handler that stores the flag always
+/// wakes the `WFE`, or makes the next one return. `SEV` is for wakers the sleeping core doesn't
+/// take an exception for, e.g. code on another core.
+///
+/// To wake up on interrupts that are disabled or not allowed to preempt, without running their
+/// handler, set `SEVONPEND` with [`SCB::set_sevonpend`](crate::peripheral::SCB::set_sevonpend):
+/// every interrupt that becomes pending then sets the event register.
+#[cfg(cortex_m)]
+#[inline(always)]
+pub fn send_event() {
+    sev()
+}
+
 /// Instruction Synchronization Barrier
 ///
 /// Flushes the pipeline in the processor, so that all instructions following the `ISB` are fetched
@@ -131,6 +192,164 @@ pub fn dmb() {
     compiler_fence(Ordering::SeqCst);
 }
 
+/// Load Register Exclusive
+///
+/// Loads the word at `addr` and marks the address for exclusive access in the local (and, for
+/// shareable memory, the global) exclusive monitor. A following [`strex`] to the same address
+/// only succeeds if no other exclusive store, [`clrex`] or exception entry or return cleared the
+/// monitor in between.
+///
+/// Not available on Armv6-M, which has no exclusive access instructions.
+///
+/// # Safety
+///
+/// `addr` must be valid for reads and 4-byte aligned.
+#[cfg(all(cortex_m, not(armv6m)))]
+#[inline(always)]
+pub unsafe fn ldrex(addr: *const u32) -> u32 {
+    let value;
+    asm!("ldrex {}, [{}]", out(reg) value, in(reg) addr, options(nostack, preserves_flags));
+    value
+}
+
+/// Store Register Exclusive
+///
+/// Stores `value` to `addr` if the exclusive monitors still hold the exclusive access marked by
+/// the previous [`ldrex`]. Returns `0` if the store was performed and `1` if it was not, in
+/// which case the usual response is to retry the whole `ldrex`/`strex` sequence.
+///
+/// Not available on Armv6-M, which has no exclusive access instructions.
+///
+/// # Safety
+///
+/// `addr` must be valid for writes and 4-byte aligned.
+#[cfg(all(cortex_m, not(armv6m)))]
+#[inline(always)]
+pub unsafe fn strex(value: u32, addr: *mut u32) -> u32 {
+    let status;
+    asm!(
+        "strex {}, {}, [{}]",
+        out(reg) status,
+        in(reg) value,
+        in(reg) addr,
+        options(nostack, preserves_flags),
+    );
+    status
+}
+
+/// Clear Exclusive
+///
+/// Clears the local exclusive monitor, so the next [`strex`] fails unless preceded by a new
+/// [`ldrex`].
+///
+/// The processor clears the monitor on exception entry and return, but a context switch
+/// implemented without an exception return (e.g. by a cooperative scheduler) should execute
+/// `CLREX` so that a thread can't complete an exclusive sequence started by another one.
+///
+/// Not available on Armv6-M, which has no exclusive access instructions.
+#[cfg(all(cortex_m, not(armv6m)))]
+#[inline(always)]
+pub fn clrex() {
+    unsafe { asm!("clrex", options(nostack, preserves_flags)) };
+}
+
+/// Preload Data: hints that the cache line containing `addr` will soon be read.
+///
+/// This is only a hint with no architectural effect: it never faults, `addr` doesn't have to be
+/// valid, and the processor is free to ignore it. It only helps on cores with a data cache, such
+/// as the Cortex-M7, when issued far enough ahead of the access, e.g. a few cache lines ahead in
+/// a loop walking a buffer. On cores without a cache it executes as a `NOP`.
+///
+/// Not available on Armv6-M and Armv8-M Baseline.
+#[cfg(all(cortex_m, not(armv6m), not(armv8m_base)))]
+#[inline(always)]
+pub fn pld(addr: *const u8) {
+    unsafe { asm!("pld [{}]", in(reg) addr, options(readonly, nostack, preserves_flags)) };
+}
+
+/// Preload Instruction: hints that code at `addr` will soon be executed.
+///
+/// Like [`pld`], this is only a hint with no architectural effect, and only helps on cores with
+/// an instruction cache. On cores without a cache it executes as a `NOP`.
+///
+/// Not available on Armv6-M and Armv8-M Baseline.
+#[cfg(all(cortex_m, not(armv6m), not(armv8m_base)))]
+#[inline(always)]
+pub fn pli(addr: *const u8) {
+    unsafe { asm!("pli [{}]", in(reg) addr, options(readonly, nostack, preserves_flags)) };
+}
+
+/// Returns the exception number of the running exception, read from IPSR, or `0` in Thread mode.
+///
+/// This is a single `MRS` followed by a mask of the exception number field, meant for the top of
+/// a handler shared by several exceptions that dispatches on the exception number. Exception
+/// numbers `16` and above are external interrupts, IRQ number `n` being exception `16 + n`. See
+/// [`xpsr::read`](crate::register::xpsr::read) to decode the rest of the combined PSR.
+#[cfg(cortex_m)]
+#[inline(always)]
+pub fn current_exception_number() -> u16 {
+    let ipsr: u32;
+    unsafe { asm!("mrs {}, IPSR", out(reg) ipsr, options(nomem, nostack, preserves_flags)) };
+    (ipsr & 0x1FF) as u16
+}
+
+/// Reads the Main Stack Pointer with a single `MRS`.
+///
+/// This is marked `#[inline(always)]`, but that is a hint: inlining is not guaranteed, e.g. in
+/// debug builds, and even when inlined the compiler decides where the read happens relative to
+/// the function prologue. So this can't be relied on to run before the stack frame of the
+/// calling function is set up. Code that needs the stack pointer at exception entry has to read
+/// it in assembly, see [`read_psp`]. See [`msp::read`](crate::register::msp::read) for reading
+/// and writing the MSP.
+#[cfg(cortex_m)]
+#[inline(always)]
+pub fn read_msp() -> u32 {
+    let r;
+    unsafe { asm!("mrs {}, MSP", out(reg) r, options(nomem, nostack, preserves_flags)) };
+    r
+}
+
+/// Reads the Process Stack Pointer with a single `MRS`.
+///
+/// See [`read_msp`]. On exception entry the exception frame is pushed on the stack the
+/// interrupted code was using, which bit 2 of the `EXC_RETURN` value in LR tells: MSP if clear,
+/// PSP if set. Code that needs the frame before any Rust code runs, e.g. a fault trampoline
+/// handing the frame to a Rust handler as its first argument, selects the stack pointer in
+/// assembly:
+///
+/// ```text
+/// tst   lr, #4
+/// ite   eq
+/// mrseq r0, MSP
+/// mrsne r0, PSP
+/// b     rust_handler
+/// ```
+///
+/// Armv6-M and Armv8-M Baseline have no `IT` blocks nor `TST` with an immediate:
+///
+/// ```text
+/// movs  r0, #4
+/// mov   r1, lr
+/// tst   r0, r1
+/// beq   1f
+/// mrs   r0, PSP
+/// b     rust_handler
+/// 1:
+/// mrs   r0, MSP
+/// b     rust_handler
+/// ```
+///
+/// From Rust code the PSP still points at the frame, as handlers run on MSP, but the MSP doesn't:
+/// the compiler may have pushed to it before the first line of the handler runs. See
+/// [`register::frame`](crate::register::frame) for the layout of the frame.
+#[cfg(cortex_m)]
+#[inline(always)]
+pub fn read_psp() -> u32 {
+    let r;
+    unsafe { asm!("mrs {}, PSP", out(reg) r, options(nomem, nostack, preserves_flags)) };
+    r
+}
+
 /// Test Target
 ///
 /// Queries the Security state and access permissions of a memory location.
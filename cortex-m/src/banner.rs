@@ -0,0 +1,309 @@
+//! Boot banner summarizing the processor and system configuration
+
+use core::fmt;
+
+use crate::peripheral::cpuid::{ArchProfile, CoreIdentity, Implementer};
+#[cfg(not(armv6m))]
+use crate::peripheral::cpuid::{CacheDescription, CacheHierarchy, CacheKind, Features};
+#[cfg(not(armv6m))]
+use crate::peripheral::fpb::{FpbCapabilities, FpbRevision};
+#[cfg(any(has_fpu, native))]
+use crate::peripheral::fpu::FpuFeatures;
+use crate::peripheral::{Peripherals, SCB, SYST};
+
+/// A summary of the processor and system configuration, see [`system_info`].
+///
+/// `Display` prints one line per item, e.g.
+///
+/// ```text
+/// core:    Cortex-M7 r1p2, Armv7-M, Arm
+/// isa:     DSP divide
+/// fpu:     SP DP VDIV VSQRT FMA FP16
+/// caches:  L1 I 16 KiB 2-way 32 B lines, L1 D 16 KiB 4-way 32 B lines
+/// mpu:     16 regions
+/// fpb:     8 breakpoints, 0 literal comparators, v2
+/// nvic:    4 priority bits
+/// systick: 10 ms = 100000 ticks, reference clock
+/// ```
+///
+/// The lines for blocks the core or target lacks are left out or say `none`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SystemInfo {
+    /// The identity of the core
+    pub identity: CoreIdentity,
+    /// The architectural features, from the CPUID ID registers
+    #[cfg(not(armv6m))]
+    pub features: Features,
+    /// The floating-point features, all `false` without an FPU
+    #[cfg(any(has_fpu, native))]
+    pub fpu: FpuFeatures,
+    /// The caches
+    #[cfg(not(armv6m))]
+    pub caches: CacheHierarchy,
+    /// The number of MPU regions, `0` without an MPU
+    pub mpu_regions: u8,
+    /// The Flash Patch and Breakpoint unit comparators
+    #[cfg(not(armv6m))]
+    pub fpb: FpbCapabilities,
+    /// The number of implemented priority bits
+    pub priority_bits: u8,
+    /// The raw SysTick `CALIB` register
+    pub systick_calib: u32,
+}
+
+impl SystemInfo {
+    /// Reads the configuration from the core peripherals.
+    ///
+    /// The number of priority bits is probed with [`SCB::priority_bits`].
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn collect(p: &mut Peripherals) -> Self {
+        SystemInfo {
+            identity: p.CPUID.identity(),
+            #[cfg(not(armv6m))]
+            features: p.CPUID.features(),
+            #[cfg(any(has_fpu, native))]
+            fpu: p.FPU.features(),
+            #[cfg(not(armv6m))]
+            caches: p.CPUID.cache_hierarchy(),
+            mpu_regions: p.MPU.region_count(),
+            #[cfg(not(armv6m))]
+            fpb: p.FPB.capabilities(),
+            priority_bits: SCB::priority_bits(),
+            // NOTE(unsafe) atomic read with no side effects
+            systick_calib: unsafe { (*SYST::PTR).calib.read() },
+        }
+    }
+}
+
+/// Writes a summary of the processor and system configuration to `out`.
+///
+/// This is the banner firmware typically prints at boot. It doesn't allocate; see [`SystemInfo`]
+/// for the format, and for a `defmt` flavor.
+///
+/// # Example
+///
+/// ```no_run
+/// use core::fmt::Write;
+///
+/// # struct Uart;
+/// # impl Write for Uart {
+/// #     fn write_str(&mut self, _: &str) -> core::fmt::Result { Ok(()) }
+/// # }
+/// # let mut uart = Uart;
+/// let mut p = cortex_m::Peripherals::take().unwrap();
+/// cortex_m::system_info(&mut uart, &mut p).unwrap();
+/// ```
+#[inline]
+pub fn system_info<W>(out: &mut W, p: &mut Peripherals) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    write!(out, "{}", SystemInfo::collect(p))
+}
+
+const CALIB_NOREF: u32 = 1 << 31;
+const CALIB_SKEW: u32 = 1 << 30;
+const CALIB_TENMS: u32 = 0x00FF_FFFF;
+
+fn arch_name(architecture: ArchProfile) -> &'static str {
+    match architecture {
+        ArchProfile::Armv6M => "Armv6-M",
+        ArchProfile::Armv7M => "Armv7-M",
+        ArchProfile::Armv8MBase => "Armv8-M Baseline",
+        ArchProfile::Armv8MMain => "Armv8-M Mainline",
+        ArchProfile::Armv8_1M => "Armv8.1-M",
+        ArchProfile::Unknown(_) => "unknown architecture",
+    }
+}
+
+#[cfg(not(armv6m))]
+struct Cache<'a>(&'a CacheDescription);
+
+#[cfg(not(armv6m))]
+impl fmt::Display for Cache<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cache = self.0;
+        let kind = match cache.kind {
+            CacheKind::Instruction => "I",
+            CacheKind::Data => "D",
+            CacheKind::Unified => "unified",
+        };
+        write!(f, "L{} {} ", cache.level, kind)?;
+        if cache.size_bytes % 1024 == 0 {
+            write!(f, "{} KiB", cache.size_bytes / 1024)?;
+        } else {
+            write!(f, "{} B", cache.size_bytes)?;
+        }
+        write!(f, " {}-way {} B lines", cache.ways, cache.line_size_bytes)
+    }
+}
+
+impl fmt::Display for SystemInfo {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let identity = &self.identity;
+        write!(f, "core:    {}, ", identity)?;
+        match identity.architecture {
+            ArchProfile::Unknown(architecture) => write!(f, "architecture {:#x}", architecture)?,
+            architecture => f.write_str(arch_name(architecture))?,
+        }
+        match identity.implementer {
+            Implementer::Arm => writeln!(f, ", Arm")?,
+            Implementer::Unknown(code) => writeln!(f, ", implementer {:#04x}", code)?,
+        }
+
+        #[cfg(not(armv6m))]
+        {
+            let features = &self.features;
+            f.write_str("isa:    ")?;
+            let mut any = false;
+            for (present, name) in [
+                (features.has_dsp, "DSP"),
+                (features.has_divide, "divide"),
+                (features.has_security_ext, "security"),
+            ] {
+                if present {
+                    write!(f, " {}", name)?;
+                    any = true;
+                }
+            }
+            if !any {
+                f.write_str(" baseline")?;
+            }
+            f.write_str("\n")?;
+        }
+
+        #[cfg(any(has_fpu, native))]
+        {
+            let fpu = &self.fpu;
+            f.write_str("fpu:    ")?;
+            if fpu.single_precision {
+                for (present, name) in [
+                    (fpu.single_precision, "SP"),
+                    (fpu.double_precision, "DP"),
+                    (fpu.divide, "VDIV"),
+                    (fpu.square_root, "VSQRT"),
+                    (fpu.fused_multiply_accumulate, "FMA"),
+                    (fpu.half_precision_conversion, "FP16"),
+                ] {
+                    if present {
+                        write!(f, " {}", name)?;
+                    }
+                }
+            } else {
+                f.write_str(" none")?;
+            }
+            f.write_str("\n")?;
+        }
+        #[cfg(all(not(armv6m), not(any(has_fpu, native))))]
+        {
+            match (self.features.has_fp, self.features.has_fp_double) {
+                (false, _) => f.write_str("fpu:     none\n")?,
+                (true, false) => f.write_str("fpu:     SP\n")?,
+                (true, true) => f.write_str("fpu:     SP DP\n")?,
+            }
+        }
+
+        #[cfg(not(armv6m))]
+        {
+            f.write_str("caches:  ")?;
+            let mut caches = self
+                .caches
+                .levels()
+                .iter()
+                .flat_map(|level| level.instruction.iter().chain(level.data.iter()));
+            match caches.next() {
+                None => f.write_str("none")?,
+                Some(first) => {
+                    write!(f, "{}", Cache(first))?;
+                    for cache in caches {
+                        write!(f, ", {}", Cache(cache))?;
+                    }
+                }
+            }
+            f.write_str("\n")?;
+        }
+
+        match self.mpu_regions {
+            0 => writeln!(f, "mpu:     none")?,
+            regions => writeln!(f, "mpu:     {} regions", regions)?,
+        }
+
+        #[cfg(not(armv6m))]
+        {
+            let fpb = &self.fpb;
+            write!(
+                f,
+                "fpb:     {} breakpoints, {} literal comparators, ",
+                fpb.code_comparators, fpb.literal_comparators
+            )?;
+            match fpb.revision {
+                FpbRevision::V1 => writeln!(f, "v1")?,
+                FpbRevision::V2 => writeln!(f, "v2")?,
+                FpbRevision::Unknown(rev) => writeln!(f, "revision {}", rev)?,
+            }
+        }
+
+        writeln!(f, "nvic:    {} priority bits", self.priority_bits)?;
+
+        let tenms = self.systick_calib & CALIB_TENMS;
+        f.write_str("systick: ")?;
+        if tenms == 0 {
+            f.write_str("no calibration")?;
+        } else {
+            write!(f, "10 ms = {} ticks", tenms + 1)?;
+            if self.systick_calib & CALIB_SKEW != 0 {
+                f.write_str(" (inexact)")?;
+            }
+        }
+        if self.systick_calib & CALIB_NOREF != 0 {
+            f.write_str(", no reference clock")
+        } else {
+            f.write_str(", reference clock")
+        }
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for SystemInfo {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "core: {} {=str}, {}",
+            self.identity,
+            arch_name(self.identity.architecture),
+            self.identity.implementer
+        );
+        #[cfg(not(armv6m))]
+        defmt::write!(
+            f,
+            "\nisa: dsp={=bool} divide={=bool} security={=bool}",
+            self.features.has_dsp,
+            self.features.has_divide,
+            self.features.has_security_ext
+        );
+        #[cfg(any(has_fpu, native))]
+        defmt::write!(f, "\nfpu: {}", self.fpu);
+        #[cfg(all(not(armv6m), not(any(has_fpu, native))))]
+        defmt::write!(
+            f,
+            "\nfpu: sp={=bool} dp={=bool}",
+            self.features.has_fp,
+            self.features.has_fp_double
+        );
+        #[cfg(not(armv6m))]
+        defmt::write!(f, "\ncaches: {}", self.caches);
+        defmt::write!(f, "\nmpu: {=u8} regions", self.mpu_regions);
+        #[cfg(not(armv6m))]
+        defmt::write!(f, "\nfpb: {}", self.fpb);
+        defmt::write!(f, "\nnvic: {=u8} priority bits", self.priority_bits);
+        defmt::write!(
+            f,
+            "\nsystick: TENMS={=u32} noref={=bool} skew={=bool}",
+            self.systick_calib & CALIB_TENMS,
+            self.systick_calib & CALIB_NOREF != 0,
+            self.systick_calib & CALIB_SKEW != 0
+        );
+    }
+}
@@ -34,6 +34,10 @@
 
 use crate::asm::{tt, tta, ttat, ttt};
 use bitfield::bitfield;
+use core::arch::asm;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{BitOr, BitOrAssign};
 
 /// Memory access behaviour: determine which privilege execution mode is used and which Memory
 /// Protection Unit (MPU) is used.
@@ -236,3 +240,512 @@ impl TestTarget {
         }
     }
 }
+
+/// Checks that Non-secure unprivileged code may access the whole range `ptr..ptr + len`.
+///
+/// Secure entry functions called from Non-secure state through a gateway (`SG`) receive
+/// pointers, stack pointers and return addresses chosen by Non-secure code. If Secure code
+/// dereferences such a pointer without checking it, a Non-secure caller can make Secure code
+/// read or overwrite Secure memory on its behalf (a confused deputy attack), e.g. by passing the
+/// address of a Secure key as an output buffer. Validate every Non-secure provided address range
+/// with this function before accessing it from Secure state.
+///
+/// This is [`check_address_range`] with [`AccessFlags::NONSECURE`], [`AccessFlags::MPU_UNPRIV`]
+/// and [`AccessFlags::MPU_READ`] or, when `write` is `true`, [`AccessFlags::MPU_READWRITE`]: the
+/// `TTAT` instruction reports the permissions of the Non-secure MPU for unprivileged accesses
+/// together with the Security attribution of the SAU and IDAU, and the range is accepted only if
+/// it is Non-secure and has the requested permissions. It differs from `check_address_range` in
+/// three ways:
+///   * an empty range is accepted
+///   * a range that crosses MPU, SAU or IDAU region boundaries is not rejected outright, every
+///     32-byte granule of it, the granularity of MPU and SAU regions, is checked instead
+///   * a range that lies in no valid region at both ends, e.g. with the Non-secure MPU disabled,
+///     is also checked every 32 bytes rather than accepted from its two ends
+///
+/// A range that wraps around the end of the address space is rejected.
+///
+/// Checking against the unprivileged permissions is the conservative choice: a range
+/// accessible to unprivileged Non-secure code is also accessible to privileged Non-secure code.
+/// If the caller is known to be privileged, use `check_address_range` without `MPU_UNPRIV`.
+///
+/// This must be called from Secure state, and the memory must not be reconfigured between the
+/// check and the access, e.g. by an interrupt handler changing the MPU or SAU, or by Non-secure
+/// code running in between.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn check_ns_pointer(ptr: *const u8, len: usize, write: bool) -> bool {
+    if len == 0 {
+        return true;
+    }
+    let flags = AccessFlags::NONSECURE
+        | AccessFlags::MPU_UNPRIV
+        | if write {
+            AccessFlags::MPU_READWRITE
+        } else {
+            AccessFlags::MPU_READ
+        };
+
+    let begin = ptr as usize;
+    let end = match begin.checked_add(len - 1) {
+        Some(end) => end,
+        None => return false,
+    };
+
+    if let Some(range) = check_address_range(ptr, len, flags) {
+        let target = range.test_target();
+        if begin / 32 == end / 32
+            || (target.mpu_region().is_some()
+                && target.sau_region().is_some()
+                && target.idau_region().is_some())
+        {
+            return true;
+        }
+    }
+
+    // the range spans several regions, or lies in none, check every 32-byte granule
+    let mut granule = begin & !31;
+    loop {
+        let addr = granule.max(begin) as *const u8;
+        if check_address_range(addr, 1, flags).is_none() {
+            return false;
+        }
+        if granule == end & !31 {
+            return true;
+        }
+        granule += 32;
+    }
+}
+
+/// A set of flags selecting how [`check_address_range`] tests a range
+///
+/// The flags and their values are those of the `CMSE_*` macros of ACLE, e.g.
+/// `AccessFlags::NONSECURE | AccessFlags::MPU_READWRITE` is `CMSE_NONSECURE | CMSE_MPU_READWRITE`.
+/// Sets are combined with `|`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AccessFlags {
+    bits: u32,
+}
+
+impl AccessFlags {
+    /// The range must be readable and writable (`CMSE_MPU_READWRITE`)
+    pub const MPU_READWRITE: Self = Self { bits: 1 << 0 };
+    /// Check the MPU permissions of unprivileged accesses (`CMSE_MPU_UNPRIV`)
+    pub const MPU_UNPRIV: Self = Self { bits: 1 << 2 };
+    /// The range must be readable (`CMSE_MPU_READ`)
+    pub const MPU_READ: Self = Self { bits: 1 << 3 };
+    /// Check the permissions of the Non-secure MPU, Secure state only (`CMSE_MPU_NONSECURE`)
+    pub const MPU_NONSECURE: Self = Self { bits: 1 << 16 };
+    /// The range must be Non-secure, Secure state only (`CMSE_AU_NONSECURE`)
+    pub const AU_NONSECURE: Self = Self { bits: 1 << 17 };
+    /// The range must be Non-secure, checked against the Non-secure MPU, Secure state only
+    /// (`CMSE_NONSECURE`)
+    pub const NONSECURE: Self = Self {
+        bits: Self::MPU_NONSECURE.bits | Self::AU_NONSECURE.bits,
+    };
+    /// All flags
+    pub const ALL: Self = Self {
+        bits: Self::MPU_READWRITE.bits
+            | Self::MPU_UNPRIV.bits
+            | Self::MPU_READ.bits
+            | Self::NONSECURE.bits,
+    };
+
+    /// The empty set
+    #[inline]
+    pub const fn empty() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// Creates a set from ACLE `CMSE_*` flag values, ignoring unknown bits
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self {
+            bits: bits & Self::ALL.bits,
+        }
+    }
+
+    /// Returns the flags as ACLE `CMSE_*` flag values
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.bits
+    }
+
+    /// Is no flag set?
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Are all flags of `other` set in `self`?
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Is any flag of `other` set in `self`?
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.bits & other.bits != 0
+    }
+
+    /// The TT instruction variant selected by the MPU flags
+    #[inline]
+    const fn access_type(self) -> AccessType {
+        match (
+            self.contains(Self::MPU_NONSECURE),
+            self.contains(Self::MPU_UNPRIV),
+        ) {
+            (false, false) => AccessType::Current,
+            (false, true) => AccessType::Unprivileged,
+            (true, false) => AccessType::NonSecure,
+            (true, true) => AccessType::NonSecureUnprivileged,
+        }
+    }
+}
+
+impl BitOr for AccessFlags {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self {
+            bits: self.bits | rhs.bits,
+        }
+    }
+}
+
+impl BitOrAssign for AccessFlags {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.bits |= rhs.bits;
+    }
+}
+
+/// An address range accepted by [`check_address_range`], with its security attributes
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct RangeAttributes {
+    ptr: *const u8,
+    size: usize,
+    target: TestTarget,
+}
+
+impl RangeAttributes {
+    /// Start of the range
+    #[inline]
+    pub fn ptr(self) -> *const u8 {
+        self.ptr
+    }
+
+    /// Size of the range in bytes, never zero
+    #[inline]
+    pub fn size(self) -> usize {
+        self.size
+    }
+
+    /// Result of the TT instruction, the same for the first and the last byte of the range
+    #[inline]
+    pub fn test_target(self) -> TestTarget {
+        self.target
+    }
+}
+
+/// Checks the permissions of the range `addr..addr + size`, like `cmse_check_address_range` of
+/// ACLE.
+///
+/// The first and the last byte of the range are tested with the TT instruction variant selected
+/// by [`AccessFlags::MPU_UNPRIV`] and [`AccessFlags::MPU_NONSECURE`]. Returns `None` if:
+///   * `size` is 0, or the range wraps around the end of the address space
+///   * the two results differ, i.e. the range crosses an MPU, SAU or IDAU region boundary, which
+///     could change the security state or the permissions inside the range
+///   * [`AccessFlags::AU_NONSECURE`] is set and the range is Secure
+///   * [`AccessFlags::MPU_READWRITE`] is set and the range isn't readable and writable, or
+///     [`AccessFlags::MPU_READ`] is set and the range isn't readable; with `AU_NONSECURE`, the
+///     Non-secure permissions are checked
+///
+/// Testing only the first byte of a buffer passed by Non-secure code is not enough: the buffer
+/// may extend into Secure memory. Secure entry functions should validate every such buffer with
+/// this function, usually with [`AccessFlags::NONSECURE`] and the access they need, before
+/// touching it.
+///
+/// Like ACLE, a range that lies in no valid region at both ends, e.g. with the MPU disabled, is
+/// accepted if both ends have the same attributes. [`check_ns_pointer`] additionally checks
+/// every 32 bytes of such a range.
+///
+/// The `NONSECURE` flags must only be used from Secure state.
+///
+/// # Example
+///
+/// ```no_run
+/// use cortex_m::cmse::{check_address_range, AccessFlags};
+///
+/// # let (buf, len) = (0x2000_0000 as *const u8, 64);
+/// // `buf` and `len` were passed by Non-secure code
+/// let flags = AccessFlags::NONSECURE | AccessFlags::MPU_UNPRIV | AccessFlags::MPU_READWRITE;
+/// if check_address_range(buf, len, flags).is_none() {
+///     // reject the request
+/// }
+/// ```
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn check_address_range(
+    addr: *const u8,
+    size: usize,
+    flags: AccessFlags,
+) -> Option<RangeAttributes> {
+    let begin = addr as usize;
+    let end = begin.checked_add(size.checked_sub(1)?)?;
+
+    let access_type = flags.access_type();
+    let first = TestTarget::check(begin as *mut u32, access_type);
+    // regions are 32-byte aligned, so a range within one 32-byte line needs a single test
+    if begin / 32 != end / 32 && TestTarget::check(end as *mut u32, access_type) != first {
+        return None;
+    }
+
+    let nonsecure = flags.contains(AccessFlags::AU_NONSECURE);
+    let allowed = if flags.contains(AccessFlags::MPU_READWRITE) {
+        if nonsecure {
+            first.ns_read_and_writable()
+        } else {
+            first.read_and_writable()
+        }
+    } else if flags.contains(AccessFlags::MPU_READ) {
+        if nonsecure {
+            first.ns_readable()
+        } else {
+            first.readable()
+        }
+    } else {
+        !(nonsecure && first.secure())
+    };
+
+    if allowed {
+        Some(RangeAttributes {
+            ptr: addr,
+            size,
+            target: first,
+        })
+    } else {
+        None
+    }
+}
+
+/// A pointer to a Non-secure function, called from Secure state with `BLXNS`
+///
+/// `F` is the signature of the function, one of `fn() -> u32`, `fn(u32) -> u32`,
+/// `fn(u32, u32) -> u32`, `fn(u32, u32, u32) -> u32` and `fn(u32, u32, u32, u32) -> u32`: the
+/// arguments are passed in `r0` to `r3` and the result is returned in `r0`, as AAPCS does for
+/// word sized values. Functions returning nothing can be called through the `-> u32` signature
+/// and the result ignored.
+///
+/// Unlike a plain function pointer, [`call`](NonSecureFnPtr::call) doesn't leak Secure state
+/// through the registers: before the call, it saves `r4` to `r11`, overwrites every register that
+/// doesn't carry an argument, clears the `APSR` flags and, on targets with an FPU, saves `d8` to
+/// `d15`, clears all FP registers and the `FPSCR` flags. The Non-secure function can't corrupt
+/// the Secure callee-saved registers either, as they are restored from the Secure stack after it
+/// returns. This is the sequence of `__gnu_cmse_nonsecure_call`.
+///
+/// # `cmse_nonsecure_call`
+///
+/// C compilers implement the same thing as the `cmse_nonsecure_call` function attribute, and
+/// nightly Rust as the `extern "C-cmse-nonsecure-call"` ABI (`abi_c_cmse_nonsecure_call`
+/// feature). With the ABI, the compiler knows the full signature, so it can pass floats, 64-bit
+/// values and arguments on the stack, and only clears the registers it has to. Prefer it when a
+/// nightly toolchain is an option; `NonSecureFnPtr` works on stable, for the word sized
+/// signatures above.
+///
+/// # Example
+///
+/// ```no_run
+/// use cortex_m::cmse::NonSecureFnPtr;
+///
+/// # let ns_callback_addr = 0x0020_0001;
+/// // `ns_callback_addr` was registered by Non-secure code
+/// let callback: NonSecureFnPtr<fn(u32) -> u32> = unsafe { NonSecureFnPtr::new(ns_callback_addr) };
+/// let status = callback.call(42);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct NonSecureFnPtr<F> {
+    addr: u32,
+    _signature: PhantomData<F>,
+}
+
+impl<F> NonSecureFnPtr<F> {
+    /// Creates a pointer to the Non-secure function at `addr`.
+    ///
+    /// The least significant bit of `addr` is ignored: it is cleared before `BLXNS`, which
+    /// switches to Non-secure state when it is zero.
+    ///
+    /// # Safety
+    ///
+    /// `addr` must be the entry point of a Non-secure function with the signature `F`, and
+    /// [`call`](NonSecureFnPtr::call) must only be used from Secure state. The function may
+    /// access any Non-secure memory, so no Secure code may hold a reference into Non-secure memory
+    /// that the function could write while it is called. [`check_address_range`] with
+    /// [`AccessFlags::NONSECURE`] can check that `addr` is Non-secure.
+    #[inline]
+    pub const unsafe fn new(addr: u32) -> Self {
+        Self {
+            addr,
+            _signature: PhantomData,
+        }
+    }
+
+    /// Returns the address passed to [`new`](NonSecureFnPtr::new).
+    #[inline]
+    pub const fn addr(&self) -> u32 {
+        self.addr
+    }
+}
+
+impl<F> fmt::Debug for NonSecureFnPtr<F> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NonSecureFnPtr({:#010x})", self.addr)
+    }
+}
+
+impl NonSecureFnPtr<fn() -> u32> {
+    /// Calls the Non-secure function.
+    #[inline]
+    pub fn call(&self) -> u32 {
+        unsafe { nonsecure_call(self.addr, [0; 4]) }
+    }
+}
+
+impl NonSecureFnPtr<fn(u32) -> u32> {
+    /// Calls the Non-secure function.
+    #[inline]
+    pub fn call(&self, a0: u32) -> u32 {
+        unsafe { nonsecure_call(self.addr, [a0, 0, 0, 0]) }
+    }
+}
+
+impl NonSecureFnPtr<fn(u32, u32) -> u32> {
+    /// Calls the Non-secure function.
+    #[inline]
+    pub fn call(&self, a0: u32, a1: u32) -> u32 {
+        unsafe { nonsecure_call(self.addr, [a0, a1, 0, 0]) }
+    }
+}
+
+impl NonSecureFnPtr<fn(u32, u32, u32) -> u32> {
+    /// Calls the Non-secure function.
+    #[inline]
+    pub fn call(&self, a0: u32, a1: u32, a2: u32) -> u32 {
+        unsafe { nonsecure_call(self.addr, [a0, a1, a2, 0]) }
+    }
+}
+
+impl NonSecureFnPtr<fn(u32, u32, u32, u32) -> u32> {
+    /// Calls the Non-secure function.
+    #[inline]
+    pub fn call(&self, a0: u32, a1: u32, a2: u32, a3: u32) -> u32 {
+        unsafe { nonsecure_call(self.addr, [a0, a1, a2, a3]) }
+    }
+}
+
+/// Calls the Non-secure function at `addr` with `args` in `r0` to `r3`, returning `r0`.
+///
+/// `r4` to `r11` are saved with low register pushes only, so the same sequence works on
+/// Baseline. The unused registers are overwritten with the (Non-secure) target address, which
+/// doesn't leak anything.
+#[inline(always)]
+unsafe fn nonsecure_call(addr: u32, args: [u32; 4]) -> u32 {
+    let r0;
+
+    #[cfg(not(has_fpu))]
+    asm!(
+        "lsrs r4, r4, #1",
+        "lsls r4, r4, #1",
+        "push {{r4-r7}}",
+        "mov r5, r8",
+        "mov r6, r9",
+        "mov r7, r10",
+        "push {{r5-r7}}",
+        "mov r5, r11",
+        "push {{r5}}",
+        "mov r5, r4",
+        "mov r6, r4",
+        "mov r7, r4",
+        "mov r8, r4",
+        "mov r9, r4",
+        "mov r10, r4",
+        "mov r11, r4",
+        "mov r12, r4",
+        "msr APSR_nzcvq, r4",
+        "blxns r4",
+        "pop {{r5}}",
+        "mov r11, r5",
+        "pop {{r5-r7}}",
+        "mov r10, r7",
+        "mov r9, r6",
+        "mov r8, r5",
+        "pop {{r4-r7}}",
+        inout("r0") args[0] => r0,
+        inout("r1") args[1] => _,
+        inout("r2") args[2] => _,
+        inout("r3") args[3] => _,
+        inout("r4") addr => _,
+        out("r12") _,
+        out("lr") _,
+    );
+
+    #[cfg(has_fpu)]
+    asm!(
+        "lsrs r4, r4, #1",
+        "lsls r4, r4, #1",
+        "push {{r4-r11}}",
+        "vpush {{d8-d15}}",
+        "movs r5, #0",
+        "vmov d0, r5, r5",
+        "vmov d1, r5, r5",
+        "vmov d2, r5, r5",
+        "vmov d3, r5, r5",
+        "vmov d4, r5, r5",
+        "vmov d5, r5, r5",
+        "vmov d6, r5, r5",
+        "vmov d7, r5, r5",
+        "vmov d8, r5, r5",
+        "vmov d9, r5, r5",
+        "vmov d10, r5, r5",
+        "vmov d11, r5, r5",
+        "vmov d12, r5, r5",
+        "vmov d13, r5, r5",
+        "vmov d14, r5, r5",
+        "vmov d15, r5, r5",
+        // clear the condition and cumulative exception flags, keep the FP configuration
+        "vmrs r5, fpscr",
+        "movw r6, #0xff60",
+        "movt r6, #0x0fff",
+        "ands r5, r6",
+        "vmsr fpscr, r5",
+        "mov r5, r4",
+        "mov r6, r4",
+        "mov r7, r4",
+        "mov r8, r4",
+        "mov r9, r4",
+        "mov r10, r4",
+        "mov r11, r4",
+        "mov r12, r4",
+        "msr APSR_nzcvq, r4",
+        "blxns r4",
+        "vpop {{d8-d15}}",
+        "pop {{r4-r11}}",
+        inout("r0") args[0] => r0,
+        inout("r1") args[1] => _,
+        inout("r2") args[2] => _,
+        inout("r3") args[3] => _,
+        inout("r4") addr => _,
+        out("r12") _,
+        out("lr") _,
+        out("d0") _,
+        out("d1") _,
+        out("d2") _,
+        out("d3") _,
+        out("d4") _,
+        out("d5") _,
+        out("d6") _,
+        out("d7") _,
+    );
+
+    r0
+}
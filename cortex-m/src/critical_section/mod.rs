@@ -0,0 +1,81 @@
+#[cfg(all(cortex_m, feature = "critical-section-single-core"))]
+use critical_section::{set_impl, Impl, RawRestoreState};
+
+#[cfg(cortex_m)]
+use crate::interrupt;
+#[cfg(cortex_m)]
+use crate::register::primask;
+
+#[cfg(test)]
+mod test;
+
+/// The interrupt mask manipulated by the critical section.
+///
+/// This indirection lets the acquire / release logic be tested on the host against a mock PRIMASK.
+trait InterruptMask {
+    /// Returns `true` if interrupts are currently enabled.
+    fn interrupts_enabled() -> bool;
+
+    /// Disables all interrupts.
+    fn disable();
+
+    /// Enables all interrupts.
+    unsafe fn enable();
+}
+
+#[cfg(cortex_m)]
+struct Primask;
+
+#[cfg(cortex_m)]
+impl InterruptMask for Primask {
+    #[inline]
+    fn interrupts_enabled() -> bool {
+        primask::read().is_active()
+    }
+
+    #[inline]
+    fn disable() {
+        // NOTE: Fence guarantees are provided by interrupt::disable(), which performs a `compiler_fence(SeqCst)`.
+        interrupt::disable();
+    }
+
+    #[inline]
+    unsafe fn enable() {
+        // NOTE: Fence guarantees are provided by interrupt::enable(), which performs a
+        // `compiler_fence(SeqCst)`.
+        interrupt::enable()
+    }
+}
+
+/// Enters a critical section, returning whether interrupts were enabled before.
+#[inline]
+unsafe fn acquire<M: InterruptMask>() -> bool {
+    let was_active = M::interrupts_enabled();
+    M::disable();
+    was_active
+}
+
+/// Leaves a critical section entered with [`acquire`].
+#[inline]
+unsafe fn release<M: InterruptMask>(was_active: bool) {
+    // Only re-enable interrupts if they were enabled before the critical section.
+    if was_active {
+        M::enable()
+    }
+}
+
+#[cfg(all(cortex_m, feature = "critical-section-single-core"))]
+struct SingleCoreCriticalSection;
+#[cfg(all(cortex_m, feature = "critical-section-single-core"))]
+set_impl!(SingleCoreCriticalSection);
+
+#[cfg(all(cortex_m, feature = "critical-section-single-core"))]
+unsafe impl Impl for SingleCoreCriticalSection {
+    unsafe fn acquire() -> RawRestoreState {
+        acquire::<Primask>()
+    }
+
+    unsafe fn release(was_active: RawRestoreState) {
+        release::<Primask>(was_active)
+    }
+}
@@ -0,0 +1,159 @@
+extern crate std;
+
+use std::cell::Cell;
+
+use super::{acquire, release, InterruptMask};
+
+std::thread_local! {
+    // `true` while interrupts are masked, i.e. PRIMASK = 1
+    static PRIMASK: Cell<bool> = const { Cell::new(false) };
+    static DISABLES: Cell<usize> = const { Cell::new(0) };
+    static ENABLES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// PRIMASK shim backed by thread locals, so tests running in parallel don't share a mask. Tests
+/// may still run one after another on the same thread, so they start with [`reset`].
+struct MockPrimask;
+
+impl InterruptMask for MockPrimask {
+    fn interrupts_enabled() -> bool {
+        !PRIMASK.with(Cell::get)
+    }
+
+    fn disable() {
+        PRIMASK.with(|p| p.set(true));
+        DISABLES.with(|c| c.set(c.get() + 1));
+    }
+
+    unsafe fn enable() {
+        PRIMASK.with(|p| p.set(false));
+        ENABLES.with(|c| c.set(c.get() + 1));
+    }
+}
+
+fn set_interrupts_enabled(enabled: bool) {
+    PRIMASK.with(|p| p.set(!enabled));
+}
+
+/// Sets the mask and zeroes the call counters left behind by earlier tests on this thread
+fn reset(enabled: bool) {
+    set_interrupts_enabled(enabled);
+    DISABLES.with(|c| c.set(0));
+    ENABLES.with(|c| c.set(0));
+}
+
+fn interrupts_enabled() -> bool {
+    MockPrimask::interrupts_enabled()
+}
+
+fn enables() -> usize {
+    ENABLES.with(Cell::get)
+}
+
+/// Mirrors `critical_section::with` on top of the mock
+fn with<R>(f: impl FnOnce() -> R) -> R {
+    let restore = unsafe { acquire::<MockPrimask>() };
+    let r = f();
+    unsafe { release::<MockPrimask>(restore) };
+    r
+}
+
+#[test]
+fn nested_sections_keep_interrupts_disabled() {
+    reset(true);
+
+    with(|| {
+        assert!(!interrupts_enabled());
+
+        with(|| {
+            assert!(!interrupts_enabled());
+            with(|| assert!(!interrupts_enabled()));
+            // leaving an inner section must not re-enable interrupts
+            assert!(!interrupts_enabled());
+        });
+
+        assert!(!interrupts_enabled());
+    });
+
+    assert!(interrupts_enabled());
+    assert_eq!(enables(), 1);
+}
+
+#[test]
+fn already_disabled_state_is_preserved() {
+    reset(false);
+
+    let restore = unsafe { acquire::<MockPrimask>() };
+    assert!(!restore);
+    unsafe { release::<MockPrimask>(restore) };
+
+    with(|| with(|| {}));
+
+    assert!(!interrupts_enabled());
+    assert_eq!(enables(), 0);
+}
+
+#[test]
+fn acquire_release_are_balanced() {
+    reset(true);
+
+    let outer = unsafe { acquire::<MockPrimask>() };
+    let inner = unsafe { acquire::<MockPrimask>() };
+    assert!(outer);
+    assert!(!inner);
+
+    unsafe { release::<MockPrimask>(inner) };
+    assert!(!interrupts_enabled());
+    unsafe { release::<MockPrimask>(outer) };
+    assert!(interrupts_enabled());
+
+    assert_eq!(DISABLES.with(Cell::get), 2);
+    assert_eq!(enables(), 1);
+}
+
+fn disables() -> usize {
+    DISABLES.with(Cell::get)
+}
+
+/// Nests `depth` sections, checking the mask on the way in and on the way out of every level
+fn nest(depth: usize, outside_enabled: bool) {
+    let restore = unsafe { acquire::<MockPrimask>() };
+    assert_eq!(restore, outside_enabled);
+    assert!(!interrupts_enabled());
+
+    if depth > 1 {
+        nest(depth - 1, false);
+        // the inner section left the mask as it found it
+        assert!(!interrupts_enabled());
+    }
+
+    unsafe { release::<MockPrimask>(restore) };
+    assert_eq!(interrupts_enabled(), outside_enabled);
+}
+
+#[test]
+fn deep_nesting_restores_every_level() {
+    for enabled in [true, false] {
+        for depth in 1..=32 {
+            set_interrupts_enabled(enabled);
+            let (disables_before, enables_before) = (disables(), enables());
+
+            nest(depth, enabled);
+
+            assert_eq!(interrupts_enabled(), enabled);
+            // every acquire masks interrupts, only the outermost release may unmask them
+            assert_eq!(disables() - disables_before, depth);
+            assert_eq!(enables() - enables_before, usize::from(enabled));
+        }
+    }
+}
+
+#[test]
+fn sequential_sections_restore_prior_state() {
+    for enabled in [true, false, true, true, false, false, true] {
+        set_interrupts_enabled(enabled);
+        let r = with(|| with(interrupts_enabled));
+        assert!(!r);
+        assert_eq!(interrupts_enabled(), enabled);
+    }
+}
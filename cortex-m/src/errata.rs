@@ -0,0 +1,53 @@
+//! Runtime queries for silicon errata
+//!
+//! Some errata only affect particular revisions of a core. The `cm7-r0p1` Cargo feature applies
+//! the corresponding workarounds unconditionally; the functions in this module make the same
+//! knowledge available at runtime, for images that run on several silicon revisions.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cortex_m::errata;
+//!
+//! let cp = cortex_m::Peripherals::take().unwrap();
+//! let identity = cp.CPUID.identity();
+//! if errata::needs_basepri_workaround(&identity) && !errata::BASEPRI_WORKAROUND_ENABLED {
+//!     // warn: this image was built without the `cm7-r0p1` feature
+//! }
+//! ```
+
+use crate::peripheral::cpuid::CoreIdentity;
+
+/// Whether this image applies the BASEPRI workaround, either always (`cm7-r0p1`) or when running
+/// on an affected core (`cm7-r0p1-detect`).
+pub const BASEPRI_WORKAROUND_ENABLED: bool =
+    cfg!(any(feature = "cm7-r0p1", feature = "cm7-r0p1-detect"));
+
+/// Returns `true` if the core is affected by Cortex-M7 erratum 837070, "Increasing priority using
+/// a write to BASEPRI does not take effect immediately".
+///
+/// Affected cores need BASEPRI writes to be performed with interrupts disabled, which
+/// [`register::basepri::write`] and [`register::basepri_max::write`] do when the `cm7-r0p1` or
+/// `cm7-r0p1-detect` feature is enabled. Only Cortex-M7 r0p1 is affected.
+///
+/// [`register::basepri::write`]: crate::register::basepri
+/// [`register::basepri_max::write`]: crate::register::basepri_max
+#[inline]
+pub const fn needs_basepri_workaround(identity: &CoreIdentity) -> bool {
+    identity.is_cm7_r0p1()
+}
+
+/// Returns `true` if BASEPRI writes on the running core must use the erratum 837070 workaround.
+#[cfg(all(cortex_m, not(armv6m), not(armv8m_base)))]
+#[inline]
+pub(crate) fn basepri_workaround_active() -> bool {
+    if cfg!(feature = "cm7-r0p1") {
+        true
+    } else if cfg!(feature = "cm7-r0p1-detect") {
+        // NOTE(unsafe): atomic read with no side effects
+        let cpuid = unsafe { (*crate::peripheral::CPUID::PTR).base.read() };
+        needs_basepri_workaround(&CoreIdentity::decode(cpuid))
+    } else {
+        false
+    }
+}
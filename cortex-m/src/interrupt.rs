@@ -2,9 +2,13 @@
 
 #[cfg(cortex_m)]
 use core::arch::asm;
+#[cfg(all(cortex_m, feature = "debug-interrupt-free"))]
+use core::sync::atomic::AtomicUsize;
 #[cfg(cortex_m)]
 use core::sync::atomic::{compiler_fence, Ordering};
 
+use crate::peripheral::scb::Exception;
+
 /// Trait for enums of external interrupt numbers.
 ///
 /// This trait should be implemented by a peripheral access crate (PAC)
@@ -60,6 +64,9 @@ pub unsafe fn enable() {
 ///
 /// This crate provides an implementation for `critical-section` suitable for single-core systems,
 /// based on disabling all interrupts. It can be enabled with the `critical-section-single-core` feature.
+///
+/// With the `debug-interrupt-free` feature, nested calls are counted, see [`free_depth`]. Without
+/// it, no counter exists and the check costs nothing.
 #[cfg(cortex_m)]
 #[inline]
 pub fn free<F, R>(f: F) -> R
@@ -71,8 +78,14 @@ where
     // disable interrupts
     disable();
 
+    #[cfg(feature = "debug-interrupt-free")]
+    let depth = enter_free();
+
     let r = f();
 
+    #[cfg(feature = "debug-interrupt-free")]
+    leave_free(depth, primask.is_active());
+
     // If the interrupts were active before our `disable` call, then re-enable
     // them. Otherwise, keep them disabled
     if primask.is_active() {
@@ -82,6 +95,96 @@ where
     r
 }
 
+/// The number of nested [`free`] calls currently running.
+///
+/// NOTE only accessed with interrupts disabled, so plain loads and stores don't race.
+#[cfg(all(cortex_m, feature = "debug-interrupt-free"))]
+static FREE_DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of nested [`free`] calls currently running.
+///
+/// This is `0` outside of `free`, and `1` in the closure of the outermost call. Only available
+/// with the `debug-interrupt-free` feature: `free` then debug-asserts that the count is back to
+/// its value on entry when each call returns, and back to `0` when the call that disabled
+/// interrupts returns. A mismatch means that the critical section bookkeeping is unbalanced, e.g.
+/// a closure that re-enabled interrupts, or a call entered from an interrupt handler that ran
+/// because of it.
+#[cfg(all(cortex_m, feature = "debug-interrupt-free"))]
+#[inline]
+pub fn free_depth() -> usize {
+    FREE_DEPTH.load(Ordering::Relaxed)
+}
+
+/// Increments the nesting depth, returning the new depth. Interrupts must be disabled.
+#[cfg(all(cortex_m, feature = "debug-interrupt-free"))]
+#[inline]
+fn enter_free() -> usize {
+    let depth = FREE_DEPTH.load(Ordering::Relaxed) + 1;
+    FREE_DEPTH.store(depth, Ordering::Relaxed);
+    depth
+}
+
+/// Decrements the nesting depth entered at `depth`. Interrupts must be disabled.
+#[cfg(all(cortex_m, feature = "debug-interrupt-free"))]
+#[inline]
+fn leave_free(depth: usize, outermost: bool) {
+    debug_assert_eq!(
+        FREE_DEPTH.load(Ordering::Relaxed),
+        depth,
+        "unbalanced `interrupt::free` nesting"
+    );
+    FREE_DEPTH.store(depth - 1, Ordering::Relaxed);
+    if outermost {
+        debug_assert_eq!(
+            depth, 1,
+            "`interrupt::free` nesting depth did not return to zero"
+        );
+    }
+}
+
+/// Execute closure `f` with BASEPRI raised to the priority `level`, masking the interrupts of the
+/// same or lower priority.
+///
+/// This implements a priority ceiling: interrupts with a priority value numerically greater than
+/// or equal to `level` are held pending while `f` runs, while more urgent interrupts are still
+/// taken. `level` is a priority in the `priority_bits` bits the device implements, e.g. `0` to
+/// `15` for 4 bits, and is shifted into the upper bits of BASEPRI. Level `0` masks nothing.
+///
+/// BASEPRI is raised with `BASEPRI_MAX`, so a section nested in a higher ceiling keeps the higher
+/// ceiling, and the previous value is restored afterwards.
+///
+/// Armv6-M and Armv8-M Baseline have no BASEPRI; use [`free`], which masks all interrupts with
+/// PRIMASK, instead.
+///
+/// **IMPORTANT** If you are using a Cortex-M7 device with revision r0p1 you MUST enable the
+/// `cm7-r0p1` Cargo feature or this function WILL misbehave.
+///
+/// # Panics
+///
+/// Panics if `priority_bits` isn't between 1 and 8, or if `level` doesn't fit in
+/// `priority_bits` bits.
+#[cfg(all(cortex_m, not(armv6m), not(armv8m_base)))]
+#[inline]
+pub fn with_basepri<F, R>(level: u8, priority_bits: u8, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let raw = crate::peripheral::nvic::Priority::from_level(level, priority_bits).raw();
+
+    let previous = crate::register::basepri::read();
+    crate::register::basepri_max::write(raw);
+    // Ensure no subsequent memory accesses are reordered to before BASEPRI is raised.
+    compiler_fence(Ordering::SeqCst);
+
+    let r = f();
+
+    // Ensure no preceeding memory accesses are reordered to after BASEPRI is restored.
+    compiler_fence(Ordering::SeqCst);
+    unsafe { crate::register::basepri::write(previous) };
+
+    r
+}
+
 // Make a `free()` function available to allow checking dependencies without specifying a target,
 // but that will panic at runtime if executed.
 #[doc(hidden)]
@@ -93,3 +196,168 @@ where
 {
     panic!("cortex_m::interrupt::free() is only functional on cortex-m platforms");
 }
+
+/// An exception or interrupt handler, as stored in the vector table
+pub type Handler = unsafe extern "C" fn();
+
+/// The highest number of external interrupts supported by the architecture (Armv6-M)
+#[cfg(armv6m)]
+pub const MAX_INTERRUPTS: usize = 32;
+
+/// The highest number of external interrupts supported by the architecture (Armv8-M)
+#[cfg(armv8m)]
+pub const MAX_INTERRUPTS: usize = 480;
+
+/// The highest number of external interrupts supported by the architecture (Armv7-M)
+#[cfg(not(any(armv6m, armv8m)))]
+pub const MAX_INTERRUPTS: usize = 496;
+
+/// A vector table entry: a handler or a reserved, zero, word
+#[derive(Clone, Copy)]
+#[repr(C)]
+union Vector {
+    handler: Handler,
+    reserved: usize,
+}
+
+/// A vector table with `N` external interrupts, for projects without a PAC or `cortex-m-rt`
+///
+/// The table has the layout the processor expects: the initial main stack pointer, the reset
+/// vector, the 14 system exception vectors and then the `N` interrupt vectors. It is built in a
+/// `const` context with [`new`](VectorTable::new), which points every vector at a default
+/// handler, and [`exception`](VectorTable::exception) and
+/// [`interrupt`](VectorTable::interrupt), which override single vectors. Vectors the
+/// architecture reserves, e.g. `MemManage` on Armv6-M and Armv8-M Baseline, are zero.
+///
+/// # Placement
+///
+/// The processor reads the table at the address in `VTOR`, which is the start of the boot memory
+/// out of reset (usually the start of flash; check the reference manual of the part). The table
+/// must be placed there by the linker: put it in a dedicated section, keep the section from being
+/// discarded, e.g. with `KEEP(*(.vector_table))` as the first input section of the flash output
+/// section in the linker script, and mark the static `#[used]` or `#[no_mangle]`. A table placed
+/// elsewhere, e.g. copied to RAM, can be activated by writing its address to `VTOR`, which
+/// requires it to be aligned to its size rounded up to a power of two, and to at least 128
+/// bytes.
+///
+/// The initial stack pointer is usually the end of RAM, defined in the linker script. A linker
+/// symbol can be used in the initializer as `unsafe { &_stack_start as *const u32 }`, with
+/// `_stack_start` declared as an `extern "C"` `static _stack_start: u32`.
+///
+/// # `cortex-m-rt`
+///
+/// `cortex-m-rt` provides the vector table itself, in its `.vector_table.*` sections, with its
+/// own reset handler that initializes RAM before calling `#[entry]`. Don't place a
+/// `VectorTable` at the boot address in a program using `cortex-m-rt`, as the two tables would
+/// overlap. A `VectorTable` can still be used there as a second table, e.g. in RAM, activated by
+/// writing `VTOR`.
+///
+/// # Example
+///
+/// ```no_run
+/// use cortex_m::interrupt::VectorTable;
+/// use cortex_m::peripheral::scb::Exception;
+///
+/// unsafe extern "C" fn reset() -> ! {
+///     // initialize RAM, then run the program
+///     loop {}
+/// }
+///
+/// unsafe extern "C" fn default_handler() {
+///     loop {}
+/// }
+///
+/// unsafe extern "C" fn systick() {}
+///
+/// unsafe extern "C" fn uart0() {}
+///
+/// #[used]
+/// #[link_section = ".vector_table"]
+/// static VECTOR_TABLE: VectorTable<32> =
+///     VectorTable::new(0x2000_4000 as *const u32, reset, default_handler)
+///         .exception(Exception::SysTick, systick)
+///         .interrupt(5, uart0);
+/// ```
+#[repr(C)]
+pub struct VectorTable<const N: usize> {
+    initial_sp: *const u32,
+    reset: unsafe extern "C" fn() -> !,
+    exceptions: [Vector; 14],
+    interrupts: [Vector; N],
+}
+
+// SAFETY the table is only read, and `initial_sp` is never dereferenced
+unsafe impl<const N: usize> Sync for VectorTable<N> {}
+
+// the initial SP, the reset vector, 14 exception vectors and the interrupt vectors, one word each
+const _: () = assert!(core::mem::size_of::<VectorTable<4>>() == 20 * core::mem::size_of::<usize>());
+
+impl<const N: usize> VectorTable<N> {
+    /// Creates a table with every exception and interrupt vector pointing at `default_handler`.
+    ///
+    /// # Panics
+    ///
+    /// Panics, at compile time when used to initialize a `static`, if `N` exceeds
+    /// [`MAX_INTERRUPTS`].
+    #[inline]
+    pub const fn new(
+        initial_sp: *const u32,
+        reset: unsafe extern "C" fn() -> !,
+        default_handler: Handler,
+    ) -> Self {
+        assert!(N <= MAX_INTERRUPTS, "too many interrupts");
+
+        let mut exceptions = [Vector { reserved: 0 }; 14];
+        let mut i = 0;
+        while i < exceptions.len() {
+            if is_exception_vector(i) {
+                exceptions[i] = Vector {
+                    handler: default_handler,
+                };
+            }
+            i += 1;
+        }
+
+        Self {
+            initial_sp,
+            reset,
+            exceptions,
+            interrupts: [Vector {
+                handler: default_handler,
+            }; N],
+        }
+    }
+
+    /// Sets the handler of a system exception.
+    #[inline]
+    pub const fn exception(mut self, exception: Exception, handler: Handler) -> Self {
+        // `irqn` is in -14..=-1, and the exception vectors start at exception number 2
+        self.exceptions[(exception.irqn() + 14) as usize] = Vector { handler };
+        self
+    }
+
+    /// Sets the handler of the external interrupt `index`, i.e. exception number `16 + index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below `N`.
+    #[inline]
+    pub const fn interrupt(mut self, index: usize, handler: Handler) -> Self {
+        assert!(index < N, "interrupt index out of range");
+        self.interrupts[index] = Vector { handler };
+        self
+    }
+}
+
+/// Is the exception vector `index`, i.e. exception number `index + 2`, implemented?
+const fn is_exception_vector(index: usize) -> bool {
+    match index {
+        // NMI, HardFault, SVCall, PendSV and SysTick
+        0 | 1 | 9 | 12 | 13 => true,
+        // MemManage, BusFault, UsageFault and DebugMonitor
+        2 | 3 | 4 | 10 => cfg!(not(any(armv6m, armv8m_base))),
+        // SecureFault
+        5 => cfg!(any(armv8m, native)),
+        _ => false,
+    }
+}
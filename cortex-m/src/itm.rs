@@ -2,9 +2,13 @@
 //!
 //! **NOTE** This module is only available on ARMv7-M and newer.
 
+#[cfg(feature = "log")]
+use core::sync::atomic::{AtomicBool, Ordering};
 use core::{fmt, ptr, slice};
 
 use crate::peripheral::itm::Stim;
+use crate::peripheral::tpiu::{poll_until, TpiuError};
+use crate::peripheral::{ITM, TPIU};
 
 // NOTE assumes that `bytes` is 32-bit aligned
 unsafe fn write_words(stim: &mut Stim, bytes: &[u32]) {
@@ -65,6 +69,30 @@ impl<'p> fmt::Write for Port<'p> {
     }
 }
 
+/// Drains the trace path from the stimulus ports to the trace port pins.
+///
+/// Waits for the ITM to emit the packets it holds, then flushes the TPIU with
+/// [`TPIU::flush_and_wait`]. Call this before entering deep sleep or resetting, so the trace
+/// output written so far isn't lost. Each of the two steps gives up after at least
+/// `timeout_cycles` processor cycles, with [`TpiuError::FlushTimeout`].
+///
+/// # Example
+///
+/// ```no_run
+/// use cortex_m::{iprintln, itm};
+///
+/// let mut p = cortex_m::Peripherals::take().unwrap();
+/// iprintln!(&mut p.ITM.stim[0], "going to sleep");
+/// itm::flush(&p.ITM, &mut p.TPIU, 100_000).ok();
+/// ```
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn flush(itm: &ITM, tpiu: &mut TPIU, timeout_cycles: u32) -> Result<(), TpiuError> {
+    if !poll_until(timeout_cycles, || !itm.is_busy()) {
+        return Err(TpiuError::FlushTimeout);
+    }
+    tpiu.flush_and_wait(timeout_cycles)
+}
+
 /// A wrapper type that aligns its contents on a 4-Byte boundary.
 ///
 /// ITM transfers are most efficient when the data is 4-Byte-aligned. This type provides an easy
@@ -143,6 +171,60 @@ pub fn write_aligned(port: &mut Stim, buffer: &Aligned<[u8]>) {
     unsafe { write_aligned_impl(port, &buffer.0) }
 }
 
+/// Writes each halfword of `halfwords` to an ITM port as a separate 16-bit stimulus write.
+///
+/// Unlike [`write_all`], which picks the write width from the alignment of the data, every element
+/// produces exactly one 16-bit stimulus write, so the host decodes one instrumentation packet
+/// with a 2-byte payload per element, in little-endian order. Use this for protocols layered on
+/// ITM that expect fixed-width stimulus entries.
+#[inline]
+pub fn write_u16_stream(port: &mut Stim, halfwords: &[u16]) {
+    for &halfword in halfwords {
+        while !port.is_fifo_ready() {}
+        port.write_u16(halfword);
+    }
+}
+
+/// Writes as much of `buffer` to an ITM port as it accepts without waiting, and returns the
+/// number of bytes written.
+///
+/// The ITM has no software-visible overflow indication: a write to a stimulus port whose FIFO
+/// is full is silently dropped, on Armv7-M as well as Armv8-M, which is why every write polls
+/// the FIFO ready bit first, and an overflow in the trace sink is only reported to the debugger
+/// by an overflow packet. This function stops at the first stimulus write the FIFO isn't ready
+/// for instead of waiting, so best-effort loggers can count the bytes it didn't write as lost
+/// trace data, e.g. `buffer.len() - written`. On Armv8-M writes to a
+/// [disabled](Stim::is_disabled) port are also reported as not written.
+///
+/// Like [`write_all`], the write width follows the alignment of the data.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn try_write_all(port: &mut Stim, buffer: &[u8]) -> usize {
+    let mut written = 0;
+    while written < buffer.len() {
+        #[cfg(armv8m)]
+        if port.is_disabled() {
+            break;
+        }
+        if !port.is_fifo_ready() {
+            break;
+        }
+
+        let rest = &buffer[written..];
+        let addr = rest.as_ptr() as usize;
+        if rest.len() >= 4 && addr % 4 == 0 {
+            port.write_u32(u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]));
+            written += 4;
+        } else if rest.len() >= 2 && addr % 2 == 0 {
+            port.write_u16(u16::from_le_bytes([rest[0], rest[1]]));
+            written += 2;
+        } else {
+            port.write_u8(rest[0]);
+            written += 1;
+        }
+    }
+    written
+}
+
 /// Writes `fmt::Arguments` to the ITM `port`
 #[inline]
 pub fn write_fmt(port: &mut Stim, args: fmt::Arguments) {
@@ -156,3 +238,226 @@ pub fn write_fmt(port: &mut Stim, args: fmt::Arguments) {
 pub fn write_str(port: &mut Stim, string: &str) {
     write_all(port, string.as_bytes())
 }
+
+/// A line-buffered writer for an ITM stimulus port.
+///
+/// Text written through [`fmt::Write`] is collected in a fixed-capacity buffer of `N` bytes and
+/// emitted when a newline is written, or on an explicit [`flush`](LineWriter::flush). Each line
+/// is written to the port inside a critical section, so lines emitted from different contexts
+/// through the same port don't interleave.
+///
+/// If a line doesn't fit in the buffer, the excess is dropped, the line is emitted truncated
+/// (still terminated by its newline), and the flag returned by
+/// [`take_truncated`](LineWriter::take_truncated) is set.
+///
+/// Requires the `heapless` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use core::fmt::Write;
+/// use cortex_m::itm::LineWriter;
+///
+/// let mut log = LineWriter::<64>::new(0);
+/// write!(log, "adc: ").ok();
+/// for sample in [1, 2, 3] {
+///     write!(log, "{} ", sample).ok();
+/// }
+/// writeln!(log).ok();
+/// ```
+#[cfg(feature = "heapless")]
+pub struct LineWriter<const N: usize> {
+    port: usize,
+    buffer: heapless::Vec<u8, N>,
+    truncated: bool,
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> LineWriter<N> {
+    /// Creates a writer for stimulus port `port`, with an empty buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `port` is 256 or more, the ITM has at most 256 stimulus ports.
+    #[inline]
+    pub const fn new(port: usize) -> Self {
+        assert!(port < 256, "the ITM has at most 256 stimulus ports");
+        LineWriter {
+            port,
+            buffer: heapless::Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// Writes out the buffered bytes, if any, without appending a newline.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        critical_section::with(|_| {
+            // NOTE(unsafe) the critical section serializes all `LineWriter` accesses to the port
+            let stim = unsafe { &mut (*crate::peripheral::ITM::PTR).stim[self.port] };
+            write_all(stim, &self.buffer);
+        });
+        self.buffer.clear();
+    }
+
+    /// Returns whether a line was truncated since the last call, and clears the flag.
+    #[inline]
+    pub fn take_truncated(&mut self) -> bool {
+        core::mem::replace(&mut self.truncated, false)
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.buffer.push(byte).is_err() {
+            self.truncated = true;
+            if byte == b'\n' {
+                // keep the line terminated
+                if let Some(last) = self.buffer.last_mut() {
+                    *last = b'\n';
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> fmt::Write for LineWriter<N> {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            self.push(byte);
+            if byte == b'\n' {
+                self.flush();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [`log`](https://docs.rs/log) logger that writes records to an ITM stimulus port.
+///
+/// Each record is written as one line, `LEVEL target: message`. Logging never blocks: once the
+/// ITM FIFO isn't ready for the next stimulus write the rest of the record is dropped, see
+/// [`try_write_all`]. A record cut short this way is terminated with a newline before the next
+/// record is written, so every record the host sees starts a new line. Records are written
+/// inside a critical section, so records logged from different contexts don't interleave.
+///
+/// Requires the `log` feature.
+///
+/// # Setup
+///
+/// The stimulus port must be enabled, and the ITM configured, before records show up; until then
+/// they are dropped. Then install the logger with `log::set_logger`:
+///
+/// ```no_run
+/// use cortex_m::itm::Logger;
+/// use log::LevelFilter;
+///
+/// static LOGGER: Logger = Logger::new(0, LevelFilter::Info);
+///
+/// // .. configure the ITM and enable stimulus port 0 ..
+///
+/// log::set_logger(&LOGGER).unwrap();
+/// log::set_max_level(LevelFilter::Info);
+///
+/// log::info!("booted");
+/// ```
+#[cfg(feature = "log")]
+#[derive(Debug)]
+pub struct Logger {
+    port: usize,
+    level: log::LevelFilter,
+    /// The last record was cut short, its line isn't terminated yet.
+    unterminated: AtomicBool,
+}
+
+#[cfg(feature = "log")]
+impl Logger {
+    /// Creates a logger writing records up to `level` to stimulus port `port`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `port` is 256 or more, the ITM has at most 256 stimulus ports.
+    #[inline]
+    pub const fn new(port: usize, level: log::LevelFilter) -> Self {
+        assert!(port < 256, "the ITM has at most 256 stimulus ports");
+        Logger {
+            port,
+            level,
+            unterminated: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Writes to an ITM port without blocking, failing once the FIFO isn't ready.
+#[cfg(feature = "log")]
+struct TryPort<'p> {
+    stim: &'p mut Stim,
+    /// At least one byte was written.
+    written: bool,
+}
+
+#[cfg(feature = "log")]
+impl<'p> fmt::Write for TryPort<'p> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let written = try_write_all(self.stim, s.as_bytes());
+        self.written |= written != 0;
+        if written == s.len() {
+            Ok(())
+        } else {
+            Err(fmt::Error)
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl log::Log for Logger {
+    #[inline]
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn log(&self, record: &log::Record) {
+        use core::fmt::Write;
+
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        critical_section::with(|_| {
+            // NOTE(unsafe) the critical section serializes all `Logger` accesses to the port
+            let stim = unsafe { &mut (*crate::peripheral::ITM::PTR).stim[self.port] };
+            // terminate the line of a record that was cut short, or drop this record too
+            if self.unterminated.load(Ordering::Relaxed) {
+                if try_write_all(stim, b"\n") == 0 {
+                    return;
+                }
+                self.unterminated.store(false, Ordering::Relaxed);
+            }
+
+            let mut port = TryPort {
+                stim,
+                written: false,
+            };
+            // the rest of a record that doesn't fit in the FIFO is dropped
+            let complete = writeln!(
+                port,
+                "{:<5} {}: {}",
+                record.level(),
+                record.target(),
+                record.args()
+            )
+            .is_ok();
+            self.unterminated
+                .store(!complete && port.written, Ordering::Relaxed);
+        });
+    }
+
+    #[inline]
+    fn flush(&self) {}
+}
@@ -25,6 +25,28 @@
 //! functions in this crate only work correctly on those chips if this Cargo feature is enabled
 //! (the functions are documented accordingly).
 //!
+//! Currently this covers erratum 837070, "Increasing priority using a write to BASEPRI does not
+//! take effect immediately": writes to BASEPRI and BASEPRI_MAX are performed with interrupts
+//! disabled and followed by ISB and DSB. PRIMASK is only changed through `cpsid`/`cpsie`
+//! (`interrupt::disable`, `interrupt::enable`), which this erratum does not affect.
+//!
+//! ## `cm7-r0p1-detect`
+//!
+//! Like `cm7-r0p1`, but for images that run on several Cortex-M7 revisions: the workarounds are
+//! only applied when the CPUID register identifies the core as r0p1, at the cost of reading CPUID
+//! on every affected operation. See the [`errata`] module for the runtime queries.
+//!
+//! ## `debug-interrupt-free`
+//!
+//! This feature makes [`interrupt::free`] count how deeply its calls are nested, and debug-assert
+//! that every call leaves the count as it found it, see [`interrupt::free_depth`]. It's a
+//! debugging aid for critical section bookkeeping: without it, `free` has no counter at all.
+//!
+//! ## `log`
+//!
+//! This feature provides [`itm::Logger`], a [`log`](https://docs.rs/log) logger that writes
+//! records to an ITM stimulus port. It is not available on Armv6-M and Armv8-M Baseline.
+//!
 //! # Minimum Supported Rust Version (MSRV)
 //!
 //! This crate is guaranteed to compile on stable Rust 1.61 and up. It *might*
@@ -57,18 +79,24 @@
 mod macros;
 
 pub mod asm;
+mod banner;
 #[cfg(armv8m)]
 pub mod cmse;
 pub mod delay;
+pub mod errata;
 pub mod interrupt;
 #[cfg(all(not(armv6m), not(armv8m_base)))]
 pub mod itm;
 pub mod peripheral;
 pub mod register;
+pub mod stack;
+#[cfg(all(not(armv6m), not(armv8m_base)))]
+pub mod trace;
 
+pub use crate::banner::{system_info, SystemInfo};
 pub use crate::peripheral::Peripherals;
 
-#[cfg(all(cortex_m, feature = "critical-section-single-core"))]
+#[cfg(any(all(cortex_m, feature = "critical-section-single-core"), test))]
 mod critical_section;
 
 /// Used to reexport items for use in macros. Do not use directly.
@@ -23,6 +23,163 @@ macro_rules! iprintln {
     };
 }
 
+/// Macro to break into the debugger if `cond` is true and a debugger is attached
+///
+/// Expands to `if cond && DCB::is_debugger_attached() { asm::bkpt() }`. Unlike a bare
+/// [`bkpt`](crate::asm::bkpt), which causes a HardFault when no debugger is attached, the trap is
+/// skipped entirely in the field, so checkpoints can be left in production code.
+///
+/// The debugger check is a read of the DHCSR register, which has a small cost. It is only done
+/// when `cond` is true. See [`DCB::is_debugger_attached`] for the limitations of the check on
+/// Armv6-M.
+///
+/// [`DCB::is_debugger_attached`]: crate::peripheral::DCB::is_debugger_attached
+///
+/// # Example
+///
+/// ``` no_run
+/// use cortex_m::debug_trap;
+///
+/// fn checksum_ok(data: &[u8]) -> bool {
+///     # true
+///     // ..
+/// }
+///
+/// fn receive(packet: &[u8]) {
+///     debug_trap!(!checksum_ok(packet));
+///     // ..
+/// }
+/// ```
+#[cfg(cortex_m)]
+#[macro_export]
+macro_rules! debug_trap {
+    ($cond:expr) => {
+        if $cond && $crate::peripheral::DCB::is_debugger_attached() {
+            $crate::asm::bkpt();
+        }
+    };
+}
+
+/// Macro to snapshot the callee-saved registers R4-R11 into a
+/// [`CoreRegisters`](crate::register::frame::CoreRegisters)
+///
+/// Together with the exception frame stacked by the hardware (R0-R3, R12, LR, PC and xPSR) this
+/// gives a complete register dump of the interrupted context.
+///
+/// # Accuracy
+///
+/// R4-R11 are only captured accurately if the handler hasn't modified them yet, so this macro must
+/// be the *very first* thing executed in the handler. The compiler is free to use R4-R11 in a
+/// function prologue, so the only reliable place for it is a naked function or trampoline that
+/// runs before any compiler-generated code of the handler. When called anywhere else the captured
+/// values are a best-effort snapshot.
+///
+/// # Example
+///
+/// ``` no_run
+/// use cortex_m::capture_registers;
+/// use cortex_m::register::frame::CoreRegisters;
+///
+/// fn fault_trampoline() {
+///     let mut regs = CoreRegisters::default();
+///     capture_registers!(regs);
+///     // .. report `regs` together with the stacked exception frame
+/// }
+/// ```
+#[cfg(cortex_m)]
+#[macro_export]
+macro_rules! capture_registers {
+    ($regs:expr) => {
+        $crate::register::frame::__capture(&mut $regs)
+    };
+}
+
+/// Macro to define a table of PMSAv7 MPU regions which is checked at compile time
+///
+/// Expands to a `static` slice of [`RegionConfig`](crate::peripheral::mpu::RegionConfig)s, where
+/// the table index is the region number. Compilation fails if
+/// [`mpu::validate_table`](crate::peripheral::mpu::validate_table) rejects the table, i.e. if a
+/// region is misaligned or otherwise invalid, or if it is shadowed by a higher numbered region.
+///
+/// Not available on Armv8-M, which uses a different region model.
+///
+/// # Example
+///
+/// ```
+/// use cortex_m::peripheral::mpu::{AccessPermission, MemoryAttributes, RegionConfig, RegionSize};
+/// use cortex_m::static_mpu_table;
+///
+/// static_mpu_table! {
+///     static MPU_TABLE = [
+///         RegionConfig {
+///             base: 0x0800_0000,
+///             size: RegionSize::Size512K,
+///             access: AccessPermission::ReadOnly,
+///             attributes: MemoryAttributes::normal_cacheable_wt(),
+///             subregion_disable: 0,
+///             execute_never: false,
+///         },
+///         RegionConfig {
+///             base: 0x2000_0000,
+///             size: RegionSize::Size128K,
+///             access: AccessPermission::ReadWrite,
+///             attributes: MemoryAttributes::normal_cacheable_wb_wa(),
+///             subregion_disable: 0,
+///             execute_never: true,
+///         },
+///     ];
+/// }
+///
+/// // at startup, with `mpu: &mut MPU`
+/// # fn program(mpu: &mut cortex_m::peripheral::MPU) {
+/// for (index, region) in MPU_TABLE.iter().enumerate() {
+///     mpu.configure_region(index as u8, region).unwrap();
+/// }
+/// # }
+/// ```
+///
+/// A misaligned base address is a compile error:
+///
+/// ```compile_fail
+/// use cortex_m::peripheral::mpu::{AccessPermission, MemoryAttributes, RegionConfig, RegionSize};
+///
+/// cortex_m::static_mpu_table! {
+///     static MPU_TABLE = [
+///         RegionConfig {
+///             base: 0x2000_1000,
+///             size: RegionSize::Size128K,
+///             access: AccessPermission::ReadWrite,
+///             attributes: MemoryAttributes::normal_noncacheable(),
+///             subregion_disable: 0,
+///             execute_never: true,
+///         },
+///     ];
+/// }
+/// ```
+#[cfg(not(armv8m))]
+#[macro_export]
+macro_rules! static_mpu_table {
+    ($(#[$meta:meta])* $vis:vis static $name:ident = [$($region:expr),* $(,)?];) => {
+        $(#[$meta])*
+        $vis static $name: &[$crate::peripheral::mpu::RegionConfig] = {
+            const TABLE: &[$crate::peripheral::mpu::RegionConfig] = &[$($region),*];
+            match $crate::peripheral::mpu::validate_table(TABLE) {
+                Ok(()) => TABLE,
+                Err($crate::peripheral::mpu::MpuError::MisalignedBase) => {
+                    panic!("MPU region base address is not aligned to the region size")
+                }
+                Err($crate::peripheral::mpu::MpuError::SubregionsNotSupported) => {
+                    panic!("MPU region smaller than 256 bytes has disabled subregions")
+                }
+                Err($crate::peripheral::mpu::MpuError::ShadowedRegion) => {
+                    panic!("MPU region is shadowed by a higher numbered region")
+                }
+                Err(_) => panic!("invalid MPU region"),
+            }
+        };
+    };
+}
+
 /// Macro to create a mutable reference to a statically allocated value
 ///
 /// This macro returns a value with type `Option<&'static mut $ty>`. `Some($expr)` will be returned
@@ -35,6 +192,8 @@ macro_rules! iprintln {
 /// This macro requires a `critical-section` implementation to be set. For most single core systems,
 /// you can enable the `critical-section-single-core` feature for this crate. For other systems, you
 /// have to provide one from elsewhere, typically your chip's HAL crate.
+/// If the macro is only ever executed from a single context, e.g. once from `main` before
+/// interrupts are enabled, [`singleton_unsync!`] avoids that requirement.
 ///
 /// For debuggability, you can set an explicit name for a singleton. This name only shows up the
 /// debugger and is not referenceable from other code. See example below.
@@ -93,6 +252,54 @@ macro_rules! singleton {
     };
 }
 
+/// Like [`singleton!`], but without a critical section
+///
+/// This macro returns a value with type `Option<&'static mut $ty>`, exactly like [`singleton!`],
+/// but it does not take a critical section around the check of the "used" flag. It therefore does
+/// not need a `critical-section` implementation, which makes it usable in minimal builds that only
+/// allocate their static buffers once from `main`, before interrupts are enabled.
+///
+/// # Safety
+///
+/// The macro must be invoked in an `unsafe` block. The caller must guarantee that the invocation
+/// is never executed concurrently with itself, e.g. from both thread mode and an interrupt handler,
+/// or from two cores. Concurrent execution is undefined behavior: both callers may observe the
+/// flag as unset and obtain aliasing `&'static mut` references.
+///
+/// # Example
+///
+/// ```
+/// use cortex_m::singleton_unsync;
+///
+/// fn main() {
+///     // SAFETY: called once from `main`, before interrupts are enabled
+///     let buf: &'static mut [u8; 1024] =
+///         unsafe { singleton_unsync!(: [u8; 1024] = [0; 1024]) }.unwrap();
+///     buf[0] = 1;
+/// }
+/// ```
+#[macro_export]
+macro_rules! singleton_unsync {
+    ($(#[$meta:meta])* $name:ident: $ty:ty = $expr:expr) => {{
+        // see `singleton!` for why this is not an `Option`
+        $(#[$meta])*
+        static mut $name: (::core::mem::MaybeUninit<$ty>, bool) =
+            (::core::mem::MaybeUninit::uninit(), false);
+
+        // deliberately no `unsafe` block here: the caller has to provide one
+        if $name.1 {
+            None
+        } else {
+            let expr = $expr;
+            $name.1 = true;
+            Some($name.0.write(expr))
+        }
+    }};
+    ($(#[$meta:meta])* : $ty:ty = $expr:expr) => {
+        $crate::singleton_unsync!($(#[$meta])* VAR: $ty = $expr)
+    };
+}
+
 /// ``` compile_fail
 /// use cortex_m::singleton;
 ///
@@ -127,3 +334,28 @@ const CPASS: () = ();
 /// ```
 #[allow(dead_code)]
 const CPASS_ATTR: () = ();
+
+/// ``` compile_fail
+/// use cortex_m::singleton_unsync;
+///
+/// fn foo() {
+///     // check that `singleton_unsync!` can only be used in an `unsafe` block
+///     singleton_unsync!(: u8 = 0);
+/// }
+/// ```
+#[allow(dead_code)]
+const CFAIL_UNSYNC: () = ();
+
+/// ```
+/// use cortex_m::singleton_unsync;
+///
+/// fn once() -> Option<&'static mut u8> {
+///     unsafe { singleton_unsync!(#[link_section = ".bss"] FOO: u8 = 0) }
+/// }
+///
+/// // check that a second execution returns `None`
+/// assert!(once().is_some());
+/// assert!(once().is_none());
+/// ```
+#[allow(dead_code)]
+const CPASS_UNSYNC: () = ();
@@ -4,7 +4,8 @@ use volatile_register::RO;
 #[cfg(not(armv6m))]
 use volatile_register::RW;
 
-#[cfg(not(armv6m))]
+use core::fmt;
+
 use crate::peripheral::CPUID;
 
 /// Register block
@@ -82,6 +83,11 @@ impl CPUID {
     /// * `ind`: select instruction cache or data/unified cache
     ///
     /// `level` is masked to be between 0 and 7.
+    ///
+    /// Prefer [`CPUID::with_cache_selected`]: CSSELR is global state, so an interrupt handler
+    /// that selects another cache between this call and the read of CCSIDR makes the interrupted
+    /// code read the geometry of the wrong cache, and this call changes the selection under any
+    /// code it interrupted.
     #[inline]
     pub fn select_cache(&mut self, level: u8, ind: CsselrCacheType) {
         const CSSELR_IND_POS: u32 = 0;
@@ -97,21 +103,52 @@ impl CPUID {
         }
     }
 
+    /// Selects a cache and hands its CCSIDR to `f`.
+    ///
+    /// * `level`: the required cache level minus 1, e.g. 0 for L1, 1 for L2
+    /// * `ind`: select instruction cache or data/unified cache
+    ///
+    /// Interrupts are masked while CSSELR is programmed and CCSIDR is read, and CSSELR is
+    /// restored to its prior value before `f` runs, so this is safe to use even if interrupt
+    /// handlers also inspect the caches. All cache geometry reads in this crate go through here;
+    /// a bare CSSELR write followed by a CCSIDR read would be a trap, as an interrupt in between
+    /// can change the selection.
+    #[inline]
+    pub fn with_cache_selected<F, R>(&mut self, level: u8, ind: CsselrCacheType, f: F) -> R
+    where
+        F: FnOnce(CcsidrView) -> R,
+    {
+        let ccsidr = crate::interrupt::free(|| {
+            let previous = self.csselr.read();
+            self.select_cache(level, ind);
+            crate::asm::dsb();
+            crate::asm::isb();
+            let ccsidr = self.ccsidr.read();
+            // NOTE(unsafe): restores a value read from the same register
+            unsafe { self.csselr.write(previous) };
+            ccsidr
+        });
+
+        f(CcsidrView(ccsidr))
+    }
+
     /// Returns the number of sets and ways in the selected cache
     #[inline]
     pub fn cache_num_sets_ways(&mut self, level: u8, ind: CsselrCacheType) -> (u16, u16) {
-        const CCSIDR_NUMSETS_POS: u32 = 13;
-        const CCSIDR_NUMSETS_MASK: u32 = 0x7FFF << CCSIDR_NUMSETS_POS;
-        const CCSIDR_ASSOCIATIVITY_POS: u32 = 3;
-        const CCSIDR_ASSOCIATIVITY_MASK: u32 = 0x3FF << CCSIDR_ASSOCIATIVITY_POS;
+        self.with_cache_selected(level, ind, |ccsidr| (ccsidr.sets(), ccsidr.ways()))
+    }
 
-        self.select_cache(level, ind);
-        crate::asm::dsb();
-        let ccsidr = self.ccsidr.read();
-        (
-            (1 + ((ccsidr & CCSIDR_NUMSETS_MASK) >> CCSIDR_NUMSETS_POS)) as u16,
-            (1 + ((ccsidr & CCSIDR_ASSOCIATIVITY_MASK) >> CCSIDR_ASSOCIATIVITY_POS)) as u16,
-        )
+    /// Describes the caches of the processor.
+    ///
+    /// This walks the levels reported by CLIDR and reads the CCSIDR of every cache found through
+    /// [`CPUID::with_cache_selected`], so CSSELR is left unchanged. Only ID registers are
+    /// accessed, so this can be called with the caches enabled.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn cache_hierarchy(&mut self) -> CacheHierarchy {
+        let clidr = self.clidr.read();
+        CacheHierarchy::walk(clidr, |level, ind| {
+            self.with_cache_selected(level, ind, CcsidrView::bits)
+        })
     }
 
     /// Returns log2 of the number of words in the smallest cache line of all the data cache and
@@ -138,3 +175,816 @@ impl CPUID {
         (ctr & CTR_IMINLINE_MASK) >> CTR_IMINLINE_POS
     }
 }
+
+/// Kind of a cache, as reported by CLIDR.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CacheKind {
+    /// Instruction cache
+    Instruction,
+    /// Data cache
+    Data,
+    /// Unified instruction and data cache
+    Unified,
+}
+
+/// A typed view of a Cache Size ID Register (CCSIDR) value, see [`CPUID::with_cache_selected`].
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CcsidrView(pub u32);
+
+#[cfg(not(armv6m))]
+impl CcsidrView {
+    const LINESIZE_MASK: u32 = 0x7;
+    const NUMSETS_POS: u32 = 13;
+    const NUMSETS_MASK: u32 = 0x7FFF << Self::NUMSETS_POS;
+    const ASSOCIATIVITY_POS: u32 = 3;
+    const ASSOCIATIVITY_MASK: u32 = 0x3FF << Self::ASSOCIATIVITY_POS;
+
+    /// Returns the raw register value.
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Number of sets
+    #[inline]
+    pub const fn sets(self) -> u16 {
+        (1 + ((self.0 & Self::NUMSETS_MASK) >> Self::NUMSETS_POS)) as u16
+    }
+
+    /// Number of ways (associativity)
+    #[inline]
+    pub const fn ways(self) -> u16 {
+        (1 + ((self.0 & Self::ASSOCIATIVITY_MASK) >> Self::ASSOCIATIVITY_POS)) as u16
+    }
+
+    /// Size of a cache line in bytes
+    #[inline]
+    pub const fn line_size_bytes(self) -> u32 {
+        // `LineSize` is log2(words per line) - 2
+        1 << ((self.0 & Self::LINESIZE_MASK) + 4)
+    }
+
+    /// Total size of the cache in bytes
+    #[inline]
+    pub const fn size_bytes(self) -> u32 {
+        self.sets() as u32 * self.ways() as u32 * self.line_size_bytes()
+    }
+
+    /// The cache supports write-through (`WT`).
+    #[inline]
+    pub const fn write_through(self) -> bool {
+        self.0 & (1 << 31) != 0
+    }
+
+    /// The cache supports write-back (`WB`).
+    #[inline]
+    pub const fn write_back(self) -> bool {
+        self.0 & (1 << 30) != 0
+    }
+
+    /// The cache supports read-allocation (`RA`).
+    #[inline]
+    pub const fn read_allocate(self) -> bool {
+        self.0 & (1 << 29) != 0
+    }
+
+    /// The cache supports write-allocation (`WA`).
+    #[inline]
+    pub const fn write_allocate(self) -> bool {
+        self.0 & (1 << 28) != 0
+    }
+}
+
+/// Description of a single cache.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CacheDescription {
+    /// Level of the cache, 1 for L1
+    pub level: u8,
+    /// What the cache holds
+    pub kind: CacheKind,
+    /// Number of sets
+    pub sets: u16,
+    /// Number of ways (associativity)
+    pub ways: u16,
+    /// Size of a cache line in bytes
+    pub line_size_bytes: u32,
+    /// Total size of the cache in bytes
+    pub size_bytes: u32,
+}
+
+#[cfg(not(armv6m))]
+impl CacheDescription {
+    /// Decodes the CCSIDR value of a cache of the given level (1 for L1) and kind.
+    #[inline]
+    pub const fn decode(level: u8, kind: CacheKind, ccsidr: u32) -> Self {
+        let view = CcsidrView(ccsidr);
+        CacheDescription {
+            level,
+            kind,
+            sets: view.sets(),
+            ways: view.ways(),
+            line_size_bytes: view.line_size_bytes(),
+            size_bytes: view.size_bytes(),
+        }
+    }
+}
+
+/// A set/way pair of a cache, as used by the cache maintenance by set/way operations.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetWay {
+    level: u8,
+    set: u16,
+    way: u16,
+    set_shift: u8,
+    way_shift: u8,
+}
+
+#[cfg(not(armv6m))]
+impl SetWay {
+    /// Level of the cache, 1 for L1
+    #[inline]
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// The set
+    #[inline]
+    pub fn set(&self) -> u16 {
+        self.set
+    }
+
+    /// The way
+    #[inline]
+    pub fn way(&self) -> u16 {
+        self.way
+    }
+
+    /// Encodes the pair in the register format of `DCISW`, `DCCSW` and `DCCISW`.
+    ///
+    /// This is the value the whole-cache maintenance operations of
+    /// [`SCB`](crate::peripheral::SCB) write for each pair.
+    ///
+    /// The way is in the top `log2(ways)` bits, the set starts at bit `log2(line size)` and the
+    /// level minus 1 is in bits `[3:1]`. On the Cortex-M7 this gives the fixed layout of its
+    /// Technical Reference Manual: way in `[31:30]`, set from bit 5.
+    #[inline]
+    pub fn encode(&self) -> u32 {
+        // a direct-mapped cache has no way field, `way_shift` is then 32
+        let way = u32::from(self.way)
+            .checked_shl(u32::from(self.way_shift))
+            .unwrap_or(0);
+        let level = u32::from(self.level.saturating_sub(1) & 0b111);
+        way | u32::from(self.set) << self.set_shift | level << 1
+    }
+}
+
+/// Iterator over the set/way pairs of a cache, see [`set_way_iter`].
+#[cfg(not(armv6m))]
+#[derive(Clone, Debug)]
+pub struct SetWayIter {
+    next: Option<SetWay>,
+    sets: u16,
+    ways: u16,
+}
+
+#[cfg(not(armv6m))]
+impl Iterator for SetWayIter {
+    type Item = SetWay;
+
+    #[inline]
+    fn next(&mut self) -> Option<SetWay> {
+        let current = self.next?;
+
+        let mut next = current;
+        next.way += 1;
+        if next.way == self.ways {
+            next.way = 0;
+            next.set += 1;
+        }
+        self.next = if next.set == self.sets {
+            None
+        } else {
+            Some(next)
+        };
+
+        Some(current)
+    }
+}
+
+/// Returns an iterator over every set/way pair of `cache`, ways of a set first.
+///
+/// The whole-cache maintenance operations of [`SCB`](crate::peripheral::SCB) walk the L1 data
+/// cache with this iterator and write [`SetWay::encode`] of each pair to the set/way register.
+/// It is exposed for custom sequences such as scrubbing a single way.
+#[cfg(not(armv6m))]
+#[inline]
+pub fn set_way_iter(cache: &CacheDescription) -> SetWayIter {
+    let first = if cache.sets == 0 || cache.ways == 0 {
+        None
+    } else {
+        // `ceil(log2(ways))` bits at the top of the register
+        let way_bits = 32 - (u32::from(cache.ways) - 1).leading_zeros();
+        Some(SetWay {
+            level: cache.level,
+            set: 0,
+            way: 0,
+            set_shift: cache.line_size_bytes.trailing_zeros() as u8,
+            way_shift: (32 - way_bits) as u8,
+        })
+    };
+
+    SetWayIter {
+        next: first,
+        sets: cache.sets,
+        ways: cache.ways,
+    }
+}
+
+/// Maximum number of cache levels CLIDR can describe.
+#[cfg(not(armv6m))]
+pub const CACHE_MAX_LEVELS: usize = 7;
+
+/// The caches at one level of the hierarchy.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CacheLevel {
+    /// The instruction cache, if the level has a separate one
+    pub instruction: Option<CacheDescription>,
+    /// The data or unified cache
+    pub data: Option<CacheDescription>,
+}
+
+/// Description of the caches of the processor, returned by [`CPUID::cache_hierarchy`].
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheHierarchy {
+    levels: [CacheLevel; CACHE_MAX_LEVELS],
+    num_levels: u8,
+}
+
+#[cfg(not(armv6m))]
+impl CacheHierarchy {
+    /// Builds the hierarchy from a CLIDR value, calling `ccsidr` to read the CCSIDR of every
+    /// cache present. `ccsidr` receives the level minus 1, like [`CPUID::select_cache`].
+    pub(crate) fn walk(clidr: u32, mut ccsidr: impl FnMut(u8, CsselrCacheType) -> u32) -> Self {
+        let mut hierarchy = CacheHierarchy::default();
+
+        for level in 0..CACHE_MAX_LEVELS as u8 {
+            // `Ctype<n>`: 0b001 instruction only, 0b010 data only, 0b011 separate instruction and
+            // data, 0b100 unified. Levels past the first 0b000 are not implemented.
+            let (instruction, data) = match (clidr >> (3 * level)) & 0b111 {
+                0b001 => (Some(CacheKind::Instruction), None),
+                0b010 => (None, Some(CacheKind::Data)),
+                0b011 => (Some(CacheKind::Instruction), Some(CacheKind::Data)),
+                0b100 => (None, Some(CacheKind::Unified)),
+                _ => break,
+            };
+
+            let entry = &mut hierarchy.levels[usize::from(level)];
+            entry.instruction = instruction.map(|kind| {
+                CacheDescription::decode(
+                    level + 1,
+                    kind,
+                    ccsidr(level, CsselrCacheType::Instruction),
+                )
+            });
+            entry.data = data.map(|kind| {
+                CacheDescription::decode(
+                    level + 1,
+                    kind,
+                    ccsidr(level, CsselrCacheType::DataOrUnified),
+                )
+            });
+            hierarchy.num_levels = level + 1;
+        }
+
+        hierarchy
+    }
+
+    /// Returns the implemented levels, starting at L1.
+    #[inline]
+    pub fn levels(&self) -> &[CacheLevel] {
+        &self.levels[..usize::from(self.num_levels)]
+    }
+
+    /// Returns the caches at `level`, 1 for L1. `None` if the level is not implemented.
+    #[inline]
+    pub fn level(&self, level: u8) -> Option<&CacheLevel> {
+        self.levels().get(usize::from(level).checked_sub(1)?)
+    }
+
+    /// Returns `true` if the processor has no caches.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.num_levels == 0
+    }
+}
+
+#[cfg(feature = "defmt")]
+#[cfg(not(armv6m))]
+impl defmt::Format for CacheHierarchy {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{}", self.levels())
+    }
+}
+
+/// A snapshot of the processor feature ID registers.
+///
+/// These registers are not implemented on Armv6-M.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IdRegisters {
+    /// `ID_PFR0` and `ID_PFR1`, Processor Feature Registers
+    pub pfr: [u32; 2],
+    /// `ID_DFR0`, Debug Feature Register
+    pub dfr: u32,
+    /// `ID_AFR0`, Auxiliary Feature Register
+    pub afr: u32,
+    /// `ID_MMFR0` to `ID_MMFR3`, Memory Model Feature Registers
+    pub mmfr: [u32; 4],
+    /// `ID_ISAR0` to `ID_ISAR4`, Instruction Set Attribute Registers. `ID_ISAR5` is reserved on
+    /// M-profile.
+    pub isar: [u32; 5],
+}
+
+/// Architectural features present on the processor, decoded from the ID registers.
+///
+/// This lets a single binary adapt to the core it runs on. Note that a feature being present
+/// doesn't mean the binary was compiled to use it.
+///
+/// The instruction set fields are a summary of [`IsaFeatures`], with unknown encodings treated as
+/// absent, plus the floating-point support from `MVFR0`.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Features {
+    /// The DSP extension is implemented (`ID_ISAR3.SIMD_instrs`).
+    pub has_dsp: bool,
+    /// The Armv8-M Security Extension is implemented (`ID_PFR1.Security`).
+    pub has_security_ext: bool,
+    /// A floating-point unit with single precision support is implemented (`MVFR0.SP`).
+    pub has_fp: bool,
+    /// The floating-point unit also supports double precision (`MVFR0.DP`).
+    pub has_fp_double: bool,
+    /// The `SDIV` and `UDIV` instructions are implemented (`ID_ISAR0.Divide_instrs`).
+    pub has_divide: bool,
+}
+
+#[cfg(not(armv6m))]
+impl Features {
+    /// Decodes the features from the ID registers and `MVFR0`, the Media and VFP Feature
+    /// Register 0.
+    ///
+    /// This isn't cross-checked against the architecture profile; to do that, use
+    /// [`from_isa`](Features::from_isa) with [`IsaFeatures::cross_check`].
+    #[inline]
+    pub const fn decode(id: &IdRegisters, mvfr0: u32) -> Self {
+        Self::from_isa(&IsaFeatures::decode(id), mvfr0)
+    }
+
+    /// Summarizes the instruction set features `isa`, adding the floating-point support from
+    /// `MVFR0`.
+    #[inline]
+    pub const fn from_isa(isa: &IsaFeatures, mvfr0: u32) -> Self {
+        Features {
+            has_dsp: isa.has_dsp(),
+            has_security_ext: isa.has_security_extension(),
+            has_fp: (mvfr0 >> 4) & 0xF != 0,
+            has_fp_double: (mvfr0 >> 8) & 0xF != 0,
+            has_divide: isa.has_divide(),
+        }
+    }
+}
+
+/// Whether a feature is implemented, as reported by a 4-bit ID register field.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FeatureSupport {
+    /// The feature is not implemented.
+    Absent,
+    /// The feature is implemented.
+    Present,
+    /// The field holds an encoding that is reserved in the architecture versions this crate
+    /// knows about.
+    Unknown(u8),
+}
+
+#[cfg(not(armv6m))]
+impl FeatureSupport {
+    /// Returns `true` only if the feature is known to be implemented.
+    ///
+    /// This is conservative: unknown encodings are treated as absent.
+    #[inline]
+    pub const fn is_present(self) -> bool {
+        matches!(self, FeatureSupport::Present)
+    }
+}
+
+/// Instruction set and system features, decoded from `ID_ISAR`, `ID_PFR` and `ID_MMFR`.
+///
+/// Unlike [`Features`], which is derived from it, every field keeps track of encodings it doesn't
+/// recognize, so a `Debug` dump of this struct is a good description of the core for bug reports.
+#[cfg(not(armv6m))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IsaFeatures {
+    /// The DSP extension (`ID_ISAR3.SIMD_instrs`). Absent when only `SSAT` and `USAT` are
+    /// implemented.
+    pub dsp: FeatureSupport,
+    /// The `SDIV` and `UDIV` instructions (`ID_ISAR0.Divide_instrs`).
+    pub divide: FeatureSupport,
+    /// The Armv8-M Security Extension (`ID_PFR1.Security`).
+    pub security_extension: FeatureSupport,
+    /// Support for a PMSAv7 or PMSAv8 MPU (`ID_MMFR0.PMSA`). Whether an MPU is actually
+    /// implemented is reported by `MPU_TYPE`.
+    pub mpu: FeatureSupport,
+    /// The `BFC`, `BFI`, `SBFX` and `UBFX` instructions (`ID_ISAR0.BitField_instrs`).
+    pub bitfield: FeatureSupport,
+    /// The `LDREX` and `STREX` family of instructions (`ID_ISAR3.SynchPrim_instrs`).
+    pub exclusive_access: FeatureSupport,
+}
+
+#[cfg(not(armv6m))]
+impl IsaFeatures {
+    /// Decodes the features from the ID registers.
+    #[inline]
+    pub const fn decode(id: &IdRegisters) -> Self {
+        const fn field(register: u32, pos: u32, present: &[u8], absent: &[u8]) -> FeatureSupport {
+            let value = ((register >> pos) & 0xF) as u8;
+            let mut i = 0;
+            while i < present.len() {
+                if present[i] == value {
+                    return FeatureSupport::Present;
+                }
+                i += 1;
+            }
+            let mut i = 0;
+            while i < absent.len() {
+                if absent[i] == value {
+                    return FeatureSupport::Absent;
+                }
+                i += 1;
+            }
+            FeatureSupport::Unknown(value)
+        }
+
+        IsaFeatures {
+            // 0b0001 is `SSAT`/`USAT` and the Q flag only
+            dsp: field(id.isar[3], 4, &[0b0011], &[0b0000, 0b0001]),
+            divide: field(id.isar[0], 24, &[0b0001], &[0b0000]),
+            // 0b0011 on Armv8.1-M
+            security_extension: field(id.pfr[1], 4, &[0b0001, 0b0011], &[0b0000]),
+            // PMSAv7, PMSAv8
+            mpu: field(id.mmfr[0], 4, &[0b0011, 0b0100], &[0b0000]),
+            bitfield: field(id.isar[0], 8, &[0b0001], &[0b0000]),
+            exclusive_access: field(id.isar[3], 12, &[0b0001], &[0b0000]),
+        }
+    }
+
+    /// Marks the features that the architecture profile rules out as absent.
+    ///
+    /// This guards against misreading the ID registers, e.g. on a core that doesn't implement
+    /// them as expected.
+    #[inline]
+    pub const fn cross_check(mut self, architecture: ArchProfile) -> Self {
+        if !architecture.may_have_dsp() {
+            self.dsp = FeatureSupport::Absent;
+        }
+        if !architecture.may_have_divide() {
+            self.divide = FeatureSupport::Absent;
+        }
+        if !architecture.may_have_security_extension() {
+            self.security_extension = FeatureSupport::Absent;
+        }
+        self
+    }
+
+    /// Returns `true` if the DSP extension is known to be implemented.
+    #[inline]
+    pub const fn has_dsp(&self) -> bool {
+        self.dsp.is_present()
+    }
+
+    /// Returns `true` if the hardware divide instructions are known to be implemented.
+    #[inline]
+    pub const fn has_divide(&self) -> bool {
+        self.divide.is_present()
+    }
+
+    /// Returns `true` if the Security Extension is known to be implemented.
+    #[inline]
+    pub const fn has_security_extension(&self) -> bool {
+        self.security_extension.is_present()
+    }
+
+    /// Returns `true` if the architecture is known to support an MPU.
+    #[inline]
+    pub const fn has_mpu(&self) -> bool {
+        self.mpu.is_present()
+    }
+}
+
+/// Address of `MVFR0`, which reads as zero when no floating-point unit is implemented.
+#[cfg(not(armv6m))]
+const MVFR0: *const u32 = 0xE000_EF40 as *const u32;
+
+#[cfg(not(armv6m))]
+impl CPUID {
+    /// Reads the processor feature ID registers.
+    #[inline]
+    pub fn id_registers(&self) -> IdRegisters {
+        IdRegisters {
+            pfr: [self.pfr[0].read(), self.pfr[1].read()],
+            dfr: self.dfr.read(),
+            afr: self.afr.read(),
+            mmfr: [
+                self.mmfr[0].read(),
+                self.mmfr[1].read(),
+                self.mmfr[2].read(),
+                self.mmfr[3].read(),
+            ],
+            isar: [
+                self.isar[0].read(),
+                self.isar[1].read(),
+                self.isar[2].read(),
+                self.isar[3].read(),
+                self.isar[4].read(),
+            ],
+        }
+    }
+
+    /// Detects the architectural features of the processor at runtime.
+    ///
+    /// The result is cross-checked against the architecture profile from [`CPUID::identity`].
+    /// This is available on Armv7-M and Armv8-M. Armv6-M doesn't implement the ID registers, and
+    /// its feature set is fixed anyway.
+    #[inline]
+    pub fn features(&self) -> Features {
+        // NOTE(unsafe) read-only register, RAZ without an FPU
+        let mvfr0 = unsafe { MVFR0.read_volatile() };
+        Features::from_isa(&self.instruction_set_features(), mvfr0)
+    }
+
+    /// Detects the instruction set and system features of the processor at runtime.
+    ///
+    /// See [`IsaFeatures`]; unrecognized encodings are reported as [`FeatureSupport::Unknown`].
+    /// The result is cross-checked against the architecture profile from [`CPUID::identity`].
+    #[inline]
+    pub fn instruction_set_features(&self) -> IsaFeatures {
+        IsaFeatures::decode(&self.id_registers()).cross_check(self.identity().architecture)
+    }
+}
+
+/// A processor part, decoded from `CPUID.PARTNO`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CorePart {
+    /// Cortex-M0
+    CortexM0,
+    /// Cortex-M0+
+    CortexM0Plus,
+    /// Cortex-M1
+    CortexM1,
+    /// Cortex-M3
+    CortexM3,
+    /// Cortex-M4
+    CortexM4,
+    /// Cortex-M7
+    CortexM7,
+    /// Cortex-M23
+    CortexM23,
+    /// Cortex-M33
+    CortexM33,
+    /// Cortex-M35P
+    CortexM35P,
+    /// Cortex-M55
+    CortexM55,
+    /// Cortex-M85
+    CortexM85,
+    /// Another part number, e.g. of a core by another implementer.
+    Unknown(u16),
+}
+
+impl CorePart {
+    /// Decodes a 12-bit `PARTNO` value.
+    #[inline]
+    pub const fn from_partno(partno: u16) -> Self {
+        match partno {
+            0xC20 => CorePart::CortexM0,
+            0xC60 => CorePart::CortexM0Plus,
+            0xC21 => CorePart::CortexM1,
+            0xC23 => CorePart::CortexM3,
+            0xC24 => CorePart::CortexM4,
+            0xC27 => CorePart::CortexM7,
+            0xD20 => CorePart::CortexM23,
+            0xD21 => CorePart::CortexM33,
+            0xD31 => CorePart::CortexM35P,
+            0xD22 => CorePart::CortexM55,
+            0xD23 => CorePart::CortexM85,
+            partno => CorePart::Unknown(partno),
+        }
+    }
+
+    /// The product name, `None` for unknown parts.
+    #[inline]
+    pub const fn name(self) -> Option<&'static str> {
+        Some(match self {
+            CorePart::CortexM0 => "Cortex-M0",
+            CorePart::CortexM0Plus => "Cortex-M0+",
+            CorePart::CortexM1 => "Cortex-M1",
+            CorePart::CortexM3 => "Cortex-M3",
+            CorePart::CortexM4 => "Cortex-M4",
+            CorePart::CortexM7 => "Cortex-M7",
+            CorePart::CortexM23 => "Cortex-M23",
+            CorePart::CortexM33 => "Cortex-M33",
+            CorePart::CortexM35P => "Cortex-M35P",
+            CorePart::CortexM55 => "Cortex-M55",
+            CorePart::CortexM85 => "Cortex-M85",
+            CorePart::Unknown(_) => return None,
+        })
+    }
+}
+
+impl fmt::Display for CorePart {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorePart::Unknown(partno) => write!(f, "part {:#05x}", partno),
+            part => f.write_str(part.name().unwrap_or_default()),
+        }
+    }
+}
+
+/// The implementer of a processor, decoded from `CPUID.IMPLEMENTER`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Implementer {
+    /// Arm, `0x41`
+    Arm,
+    /// Another implementer code, e.g. of a licensee that stamps its own.
+    Unknown(u8),
+}
+
+impl Implementer {
+    /// Decodes an 8-bit `IMPLEMENTER` value.
+    #[inline]
+    pub const fn from_code(code: u8) -> Self {
+        match code {
+            0x41 => Implementer::Arm,
+            code => Implementer::Unknown(code),
+        }
+    }
+
+    /// Returns the implementer code.
+    #[inline]
+    pub const fn code(self) -> u8 {
+        match self {
+            Implementer::Arm => 0x41,
+            Implementer::Unknown(code) => code,
+        }
+    }
+}
+
+/// The architecture profile and version of a processor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ArchProfile {
+    /// Armv6-M
+    Armv6M,
+    /// Armv7-M, including the DSP extension of Armv7E-M
+    Armv7M,
+    /// Armv8-M Baseline
+    Armv8MBase,
+    /// Armv8-M Mainline
+    Armv8MMain,
+    /// Armv8.1-M Mainline
+    Armv8_1M,
+    /// Not known for this part; holds the `CPUID.ARCHITECTURE` field, `0xC` for Armv6-M and
+    /// Armv8-M Baseline, `0xF` for Armv7-M and later Mainline profiles.
+    Unknown(u8),
+}
+
+impl ArchProfile {
+    /// Determines the profile from the `CPUID.ARCHITECTURE` field and the part.
+    ///
+    /// The field alone doesn't tell Armv7-M from Armv8-M Mainline, or Armv6-M from Armv8-M
+    /// Baseline, so the profile is only known for the parts listed in [`CorePart`].
+    #[inline]
+    pub const fn decode(architecture: u8, part: CorePart) -> Self {
+        match part {
+            CorePart::CortexM0 | CorePart::CortexM0Plus | CorePart::CortexM1 => ArchProfile::Armv6M,
+            CorePart::CortexM3 | CorePart::CortexM4 | CorePart::CortexM7 => ArchProfile::Armv7M,
+            CorePart::CortexM23 => ArchProfile::Armv8MBase,
+            CorePart::CortexM33 | CorePart::CortexM35P => ArchProfile::Armv8MMain,
+            CorePart::CortexM55 | CorePart::CortexM85 => ArchProfile::Armv8_1M,
+            CorePart::Unknown(_) => ArchProfile::Unknown(architecture),
+        }
+    }
+
+    /// Returns `false` if the DSP extension can't be implemented on this profile.
+    #[inline]
+    pub const fn may_have_dsp(self) -> bool {
+        !matches!(self, ArchProfile::Armv6M | ArchProfile::Armv8MBase)
+    }
+
+    /// Returns `false` if hardware divide can't be implemented on this profile.
+    #[inline]
+    pub const fn may_have_divide(self) -> bool {
+        !matches!(self, ArchProfile::Armv6M)
+    }
+
+    /// Returns `false` if the Security Extension can't be implemented on this profile.
+    #[inline]
+    pub const fn may_have_security_extension(self) -> bool {
+        !matches!(self, ArchProfile::Armv6M | ArchProfile::Armv7M)
+    }
+}
+
+/// The identity of the processor, decoded from the CPUID base register.
+///
+/// `Display` gives the conventional form, e.g. `Cortex-M7 r1p2`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CoreIdentity {
+    /// The implementer.
+    pub implementer: Implementer,
+    /// The processor part.
+    pub part: CorePart,
+    /// The architecture profile.
+    pub architecture: ArchProfile,
+    /// The major revision, the `N` of `rNpM`.
+    pub variant: u8,
+    /// The minor revision, the `M` of `rNpM`.
+    pub revision: u8,
+}
+
+impl CoreIdentity {
+    /// Decodes the value of the CPUID base register.
+    #[inline]
+    pub const fn decode(cpuid: u32) -> Self {
+        let part = CorePart::from_partno(((cpuid >> 4) & 0xFFF) as u16);
+        CoreIdentity {
+            implementer: Implementer::from_code((cpuid >> 24) as u8),
+            part,
+            architecture: ArchProfile::decode(((cpuid >> 16) & 0xF) as u8, part),
+            variant: ((cpuid >> 20) & 0xF) as u8,
+            revision: (cpuid & 0xF) as u8,
+        }
+    }
+
+    /// Returns `true` on a Cortex-M7 r0p1, which needs the `cm7-r0p1` Cargo feature.
+    ///
+    /// Use it to warn at runtime when an image built without the feature runs on that revision.
+    #[inline]
+    pub const fn is_cm7_r0p1(&self) -> bool {
+        matches!(self.implementer, Implementer::Arm)
+            && matches!(self.part, CorePart::CortexM7)
+            && self.variant == 0
+            && self.revision == 1
+    }
+
+    /// Returns `true` if the core is at revision `rNpM` or later, with `N` = `r` and `M` = `p`.
+    ///
+    /// Revisions are only comparable between identical parts, so check `part` first. See the
+    /// [`errata`](crate::errata) module for queries about specific errata.
+    #[inline]
+    pub const fn revision_at_least(&self, r: u8, p: u8) -> bool {
+        self.variant > r || (self.variant == r && self.revision >= p)
+    }
+}
+
+impl fmt::Display for CoreIdentity {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} r{}p{}", self.part, self.variant, self.revision)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for CoreIdentity {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn format(&self, f: defmt::Formatter) {
+        match self.part {
+            CorePart::Unknown(partno) => defmt::write!(f, "part {=u16:#05x}", partno),
+            part => defmt::write!(f, "{=str}", part.name().unwrap_or_default()),
+        }
+        defmt::write!(f, " r{=u8}p{=u8}", self.variant, self.revision)
+    }
+}
+
+impl CPUID {
+    /// Reads the identity of the processor from the CPUID base register.
+    #[inline]
+    pub fn identity(&self) -> CoreIdentity {
+        CoreIdentity::decode(self.base.read())
+    }
+}
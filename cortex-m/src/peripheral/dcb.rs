@@ -1,17 +1,90 @@
 //! Debug Control Block
+//!
+//! # Monitor mode debugging
+//!
+//! Halting the core on a breakpoint also stops everything it drives, which a system that has to
+//! keep a motor commutating can't afford. In monitor mode, debug events raise the
+//! [`DebugMonitor`](crate::peripheral::scb::Exception::DebugMonitor) exception instead, and
+//! interrupts with a higher priority keep being served while it's handled. Monitor mode is
+//! enabled with [`DCB::configure_debug_monitor`], and takes effect only while no halting debugger
+//! is connected, see [`DCB::is_monitor_active`]. The handler finds out what happened with
+//! [`SCB::debug_fault_status`](crate::peripheral::SCB::debug_fault_status).
+//!
+//! *NOTE* Not available on Armv6-M.
+//!
+//! ## Example
+//!
+//! "Log and continue" breakpoints: the handler logs the breakpoint, then steps over the
+//! breakpointed instruction with the FPB disabled, and enables it again after the step.
+//!
+//! ```no_run
+//! # #[cfg(not(armv6m))] {
+//! use cortex_m::peripheral::{Peripherals, DCB};
+//!
+//! // register as the DebugMonitor handler, e.g. with cortex-m-rt's `#[exception]`
+//! fn debug_monitor() {
+//!     // NOTE(unsafe) the debug monitor is the only code using these peripherals
+//!     let mut p = unsafe { Peripherals::steal() };
+//!     let status = p.SCB.debug_fault_status();
+//!     p.SCB.clear_debug_fault_status(status);
+//!
+//!     if status.bkpt() {
+//!         // log the breakpoint here, then execute the instruction without it
+//!         p.FPB.disable();
+//!         p.DCB.set_monitor_step(true);
+//!     } else if status.halted() {
+//!         // the step is done, put the breakpoint back
+//!         p.DCB.set_monitor_step(false);
+//!         p.FPB.enable();
+//!     }
+//! }
+//!
+//! let mut p = Peripherals::take().unwrap();
+//! // NOTE(unsafe) no priority based critical section depends on the DebugMonitor priority
+//! unsafe { p.DCB.configure_debug_monitor(&mut p.SCB, 0xC0) };
+//! p.FPB.enable();
+//! p.FPB.set_breakpoint(0, 0x0000_1234).unwrap();
+//! # }
+//! ```
+
+use core::ops::{BitOr, BitOrAssign};
 
 use volatile_register::{RW, WO};
 
-use crate::peripheral::DCB;
+#[cfg(not(armv6m))]
+use crate::peripheral::scb::SystemHandler;
+use crate::peripheral::{DCB, SCB};
 use core::ptr;
 
-const DCB_DEMCR_TRCENA: u32 = 1 << 24;
+#[cfg(not(armv6m))]
+pub mod monitor;
+
+const DCB_DHCSR_C_DEBUGEN: u32 = 1 << 0;
+const DCB_DHCSR_C_HALT: u32 = 1 << 1;
+/// The control bits, C_DEBUGEN to C_SNAPSTALL
+const DCB_DHCSR_CONTROL_MASK: u32 = 0b10_1111;
+/// The key that must be written to bits `[31:16]` for a write to DHCSR to take effect
+const DCB_DHCSR_DBGKEY: u32 = 0xA05F << 16;
+const DCB_DHCSR_S_REGRDY: u32 = 1 << 16;
+const DCB_DHCSR_S_HALT: u32 = 1 << 17;
+const DCB_DHCSR_S_SLEEP: u32 = 1 << 18;
+const DCB_DHCSR_S_LOCKUP: u32 = 1 << 19;
+const DCB_DHCSR_S_RETIRE_ST: u32 = 1 << 24;
+const DCB_DHCSR_S_RESET_ST: u32 = 1 << 25;
+pub(crate) const DCB_DEMCR_TRCENA: u32 = 1 << 24;
 const DCB_DEMCR_MON_EN: u32 = 1 << 16;
+#[cfg(not(armv6m))]
+const DCB_DEMCR_MON_PEND: u32 = 1 << 17;
+#[cfg(not(armv6m))]
+const DCB_DEMCR_MON_STEP: u32 = 1 << 18;
 
 /// Register block
 #[repr(C)]
 pub struct RegisterBlock {
     /// Debug Halting Control and Status
+    ///
+    /// Writes only take effect with the debug key in the upper half, prefer the `DCB` methods.
+    /// Reading it clears the sticky status bits, see [`DCB::status`].
     pub dhcsr: RW<u32>,
     /// Debug Core Register Selector
     pub dcrsr: WO<u32>,
@@ -21,7 +94,180 @@ pub struct RegisterBlock {
     pub demcr: RW<u32>,
 }
 
+/// The status of the core, from the read-only bits of DHCSR, see [`DCB::status`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DebugStatus {
+    /// Halting debug is enabled (`C_DEBUGEN`), see [`DCB::is_debugger_attached`]
+    pub debug_enabled: bool,
+    /// The core is halted in Debug state (`S_HALT`)
+    pub halted: bool,
+    /// The core is sleeping (`S_SLEEP`)
+    pub sleeping: bool,
+    /// The core is locked up after an unrecoverable exception (`S_LOCKUP`)
+    pub locked_up: bool,
+    /// A core register transfer through DCRSR has completed (`S_REGRDY`)
+    pub register_ready: bool,
+    /// An instruction has completed since the last read of DHCSR (`S_RETIRE_ST`, sticky)
+    pub retired_since_read: bool,
+    /// The core has been reset since the last read of DHCSR (`S_RESET_ST`, sticky)
+    pub reset_since_read: bool,
+}
+
+impl DebugStatus {
+    /// Decodes a DHCSR value
+    #[inline]
+    pub const fn from_bits(dhcsr: u32) -> Self {
+        DebugStatus {
+            debug_enabled: dhcsr & DCB_DHCSR_C_DEBUGEN != 0,
+            halted: dhcsr & DCB_DHCSR_S_HALT != 0,
+            sleeping: dhcsr & DCB_DHCSR_S_SLEEP != 0,
+            locked_up: dhcsr & DCB_DHCSR_S_LOCKUP != 0,
+            register_ready: dhcsr & DCB_DHCSR_S_REGRDY != 0,
+            retired_since_read: dhcsr & DCB_DHCSR_S_RETIRE_ST != 0,
+            reset_since_read: dhcsr & DCB_DHCSR_S_RESET_ST != 0,
+        }
+    }
+}
+
+/// A set of DEMCR vector catch events, see [`DCB::set_vector_catch`]
+///
+/// Sets are combined with `|`, e.g. `VectorCatch::CORE_RESET | VectorCatch::HARD_FAULT`. Armv6-M
+/// and Armv8-M Baseline only implement [`CORE_RESET`](VectorCatch::CORE_RESET),
+/// [`HARD_FAULT`](VectorCatch::HARD_FAULT) and, with the Security Extension,
+/// [`SECURE_FAULT`](VectorCatch::SECURE_FAULT); the other bits read as zero there and writes to
+/// them are ignored.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct VectorCatch {
+    bits: u32,
+}
+
+impl VectorCatch {
+    /// Core reset (`VC_CORERESET`)
+    pub const CORE_RESET: Self = Self { bits: 1 << 0 };
+    /// MemManage fault (`VC_MMERR`)
+    pub const MEM_MANAGE: Self = Self { bits: 1 << 4 };
+    /// UsageFault on coprocessor access (`VC_NOCPERR`)
+    pub const NO_COPROCESSOR: Self = Self { bits: 1 << 5 };
+    /// UsageFault on a checking error, e.g. an unaligned access or a division by zero
+    /// (`VC_CHKERR`)
+    pub const CHECK_ERROR: Self = Self { bits: 1 << 6 };
+    /// UsageFault on a state information error, e.g. an undefined instruction (`VC_STATERR`)
+    pub const STATE_ERROR: Self = Self { bits: 1 << 7 };
+    /// BusFault (`VC_BUSERR`)
+    pub const BUS_FAULT: Self = Self { bits: 1 << 8 };
+    /// Fault during exception entry or return (`VC_INTERR`)
+    pub const EXCEPTION_ERROR: Self = Self { bits: 1 << 9 };
+    /// HardFault (`VC_HARDERR`)
+    pub const HARD_FAULT: Self = Self { bits: 1 << 10 };
+    /// SecureFault, Armv8-M with the Security Extension only (`VC_SFERR`)
+    pub const SECURE_FAULT: Self = Self { bits: 1 << 11 };
+    /// All vector catch events
+    pub const ALL: Self = Self {
+        bits: 0b1111_1111_0001,
+    };
+
+    /// The empty set
+    #[inline]
+    pub const fn empty() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// Creates a set from DEMCR bits, ignoring all bits other than the vector catch events
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self {
+            bits: bits & Self::ALL.bits,
+        }
+    }
+
+    /// Returns the events as DEMCR bits
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.bits
+    }
+
+    /// Is no event set?
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Are all events of `other` set in `self`?
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Is any event of `other` set in `self`?
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.bits & other.bits != 0
+    }
+}
+
+impl BitOr for VectorCatch {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self {
+            bits: self.bits | rhs.bits,
+        }
+    }
+}
+
+impl BitOrAssign for VectorCatch {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.bits |= rhs.bits;
+    }
+}
+
 impl DCB {
+    /// Writes the DHCSR control bits `control`, with the debug key without which the write is
+    /// ignored
+    ///
+    /// Software writes to C_DEBUGEN are ignored, only a debugger can set or clear it.
+    unsafe fn write_dhcsr(&mut self, control: u32) {
+        self.dhcsr
+            .write(DCB_DHCSR_DBGKEY | (control & DCB_DHCSR_CONTROL_MASK));
+    }
+
+    /// Reads the status of the core from DHCSR
+    ///
+    /// The sticky bits [`retired_since_read`](DebugStatus::retired_since_read) and
+    /// [`reset_since_read`](DebugStatus::reset_since_read) are cleared by any read of DHCSR,
+    /// including this one, so they only tell about the time since the previous read. Another
+    /// core reading this core's DHCSR, or a debugger polling it, also clears them.
+    ///
+    /// *NOTE* This takes `&mut self` because the read clears the sticky bits. See
+    /// [`is_debugger_attached`](DCB::is_debugger_attached) for the cores on which software can't
+    /// access DHCSR.
+    #[inline]
+    pub fn status(&mut self) -> DebugStatus {
+        DebugStatus::from_bits(self.dhcsr.read())
+    }
+
+    /// Clears the sticky status bits, see [`status`](DCB::status)
+    ///
+    /// The sticky bits are cleared by reading DHCSR, they can't be written.
+    #[inline]
+    pub fn clear_sticky_status(&mut self) {
+        self.dhcsr.read();
+    }
+
+    /// Requests the core to halt (DHCSR.C_HALT)
+    ///
+    /// The core enters Debug state as if a debugger had halted it, but only if halting debug is
+    /// enabled (C_DEBUGEN, see [`is_debugger_attached`](DCB::is_debugger_attached)); otherwise
+    /// the request is ignored. Unlike a `bkpt`, this never escalates to a HardFault.
+    #[inline]
+    pub fn request_halt(&mut self) {
+        let control = self.dhcsr.read();
+        unsafe { self.write_dhcsr(control | DCB_DHCSR_C_HALT) }
+    }
+
     /// Enables TRACE. This is for example required by the
     /// `peripheral::DWT` cycle counter to work properly.
     /// As by STM documentation, this flag is not reset on
@@ -30,21 +276,47 @@ impl DCB {
     /// Note: vendor-specific registers may have to be set to completely
     /// enable tracing. For example, on the STM32F401RE, `TRACE_MODE`
     /// and `TRACE_IOEN` must be configured in `DBGMCU_CR` register.
+    ///
+    /// Trace must be enabled before configuring the DWT or the ITM: while
+    /// TRCENA is clear their registers may ignore writes and read as zero,
+    /// which typically shows up as a cycle counter stuck at 0. The DWT and
+    /// ITM configuration functions take the `DCB` and call this themselves:
+    /// [`DWT::enable_cycle_counter`](crate::peripheral::DWT::enable_cycle_counter),
+    /// [`DWT::enable_sleep_counting`](crate::peripheral::DWT::enable_sleep_counting),
+    /// [`ITM::configure`](crate::peripheral::ITM::configure),
+    /// [`ITM::emit_sync`](crate::peripheral::ITM::emit_sync) and
+    /// [`ITM::configure_timestamps`](crate::peripheral::ITM::configure_timestamps).
+    /// A barrier makes sure the enable has taken effect before returning.
     #[inline]
     pub fn enable_trace(&mut self) {
         // set bit 24 / TRCENA
         unsafe {
             self.demcr.modify(|w| w | DCB_DEMCR_TRCENA);
         }
+        crate::asm::dsb();
+        crate::asm::isb();
     }
 
     /// Disables TRACE. See `DCB::enable_trace()` for more details
+    ///
+    /// This powers down the DWT and ITM on most implementations, which saves
+    /// power when no trace or profiling is needed. Their configuration is
+    /// kept, but the cycle counter stops.
     #[inline]
     pub fn disable_trace(&mut self) {
         // unset bit 24 / TRCENA
         unsafe {
             self.demcr.modify(|w| w & !DCB_DEMCR_TRCENA);
         }
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+
+    /// Returns `true` if TRACE is enabled, see [`DCB::enable_trace`]
+    #[inline]
+    pub fn is_trace_enabled() -> bool {
+        // NOTE(unsafe) atomic read with no side effects
+        unsafe { (*Self::PTR).demcr.read() & DCB_DEMCR_TRCENA != 0 }
     }
 
     /// Enables the [`DebugMonitor`](crate::peripheral::scb::Exception::DebugMonitor) exception
@@ -63,6 +335,129 @@ impl DCB {
         }
     }
 
+    /// Enables the [`DebugMonitor`](crate::peripheral::scb::Exception::DebugMonitor) exception at
+    /// priority `priority`
+    ///
+    /// This sets the priority of the exception, then DEMCR.MON_EN. Interrupts with a higher
+    /// priority (a lower value) keep running while a debug event is handled; the events are lost
+    /// while the processor runs at the DebugMonitor priority or above. See the
+    /// [module documentation](self#monitor-mode-debugging).
+    ///
+    /// # Unsafety
+    ///
+    /// Changing priority levels can break priority-based critical sections (see
+    /// [`register::basepri`](crate::register::basepri)) and compromise memory safety, as for
+    /// [`SCB::set_priority`].
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub unsafe fn configure_debug_monitor(&mut self, scb: &mut SCB, priority: u8) {
+        scb.set_priority(SystemHandler::DebugMonitor, priority);
+        self.enable_debug_monitor();
+    }
+
+    /// Returns `true` if debug events raise the DebugMonitor exception
+    ///
+    /// That is, if it's enabled (DEMCR.MON_EN) and no halting debugger is connected: halting
+    /// debug (DHCSR.C_DEBUGEN) takes precedence over monitor mode. C_DEBUGEN can outlive the
+    /// debugger, see [`is_debugger_attached`](DCB::is_debugger_attached), in which case this
+    /// returns `false` and debug events halt the core instead.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn is_monitor_active() -> bool {
+        // NOTE(unsafe) atomic read with no side effects
+        let monitor_enabled = unsafe { (*Self::PTR).demcr.read() & DCB_DEMCR_MON_EN != 0 };
+        monitor_enabled && !Self::is_debugger_attached()
+    }
+
+    /// Pends the DebugMonitor exception (DEMCR.MON_PEND)
+    ///
+    /// The handler runs once the processor priority is below the DebugMonitor priority, even if
+    /// monitor mode is disabled. The request is cleared on entry to the handler.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn pend_debug_monitor(&mut self) {
+        unsafe {
+            self.demcr.modify(|w| w | DCB_DEMCR_MON_PEND);
+        }
+    }
+
+    /// Requests a single step on return from the DebugMonitor handler (DEMCR.MON_STEP)
+    ///
+    /// While set, the processor executes one instruction after returning from the handler, then
+    /// re-enters it with [`Dfsr::halted`](crate::peripheral::scb::Dfsr::halted) set. This is the
+    /// building block of a self-hosted single stepper: the handler keeps it set to step again, and
+    /// clears it to resume normal execution.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn set_monitor_step(&mut self, step: bool) {
+        unsafe {
+            self.demcr.modify(|w| {
+                if step {
+                    w | DCB_DEMCR_MON_STEP
+                } else {
+                    w & !DCB_DEMCR_MON_STEP
+                }
+            });
+        }
+    }
+
+    /// Enables or disables catching the vector catch events in `events`, leaving the others
+    /// unchanged
+    ///
+    /// A caught event halts the core on entry to the corresponding exception handler, or right
+    /// out of reset for [`VectorCatch::CORE_RESET`], before any instruction of it executes. Vector
+    /// catch only has an effect with halting debug enabled (DHCSR.C_DEBUGEN, see
+    /// [`is_debugger_attached`](DCB::is_debugger_attached)); otherwise the exceptions are taken
+    /// as usual. Bits the core doesn't implement are ignored, see [`VectorCatch`].
+    #[inline]
+    pub fn set_vector_catch(&mut self, events: VectorCatch, enabled: bool) {
+        unsafe {
+            self.demcr.modify(|w| {
+                if enabled {
+                    w | events.bits()
+                } else {
+                    w & !events.bits()
+                }
+            });
+        }
+    }
+
+    /// Returns the enabled vector catch events
+    #[inline]
+    pub fn vector_catch() -> VectorCatch {
+        // NOTE(unsafe) atomic read with no side effects
+        VectorCatch::from_bits(unsafe { (*Self::PTR).demcr.read() })
+    }
+
+    /// Resets the system with a halt on the reset vector armed (DEMCR.VC_CORERESET)
+    ///
+    /// With a halting debugger attached, the core halts before the first instruction of the reset
+    /// handler, e.g. to attach to the image a bootloader starts next at its very start. DEMCR
+    /// keeps its value across the system reset requested through `SCB` (see [`SCB::sys_reset`]),
+    /// only a power-on reset clears it, so the catch stays armed until
+    /// [`clear_reset_catch`](DCB::clear_reset_catch) is called, typically right after the
+    /// catch.
+    ///
+    /// # Safety
+    ///
+    /// This is meant for debug builds. The catch only halts the core while halting debug is
+    /// enabled, but C_DEBUGEN can stay set after the debugger is gone, e.g. if the probe was
+    /// unplugged without detaching, until the next power-on reset (see
+    /// [`is_debugger_attached`](DCB::is_debugger_attached)). A unit left with the catch armed
+    /// then hangs in Debug state at its next reset, e.g. a watchdog reset, with no debugger to
+    /// resume it.
+    #[inline]
+    pub unsafe fn catch_next_reset(&mut self, _scb: &mut SCB) -> ! {
+        self.set_vector_catch(VectorCatch::CORE_RESET, true);
+        SCB::sys_reset()
+    }
+
+    /// Disarms the halt on the reset vector, see [`catch_next_reset`](DCB::catch_next_reset)
+    #[inline]
+    pub fn clear_reset_catch(&mut self) {
+        self.set_vector_catch(VectorCatch::CORE_RESET, false);
+    }
+
     /// Is there a debugger attached? (see note)
     ///
     /// Note: This function is [reported not to
@@ -70,12 +465,22 @@ impl DCB {
     /// on Cortex-M0 devices. Per the ARM v6-M Architecture Reference Manual, "Access to the DHCSR
     /// from software running on the processor is IMPLEMENTATION DEFINED". Indeed, from the
     /// [Cortex-M0+ r0p1 Technical Reference Manual](http://infocenter.arm.com/help/index.jsp?topic=/com.arm.doc.ddi0484c/BABJHEIG.html), "Note Software cannot access the debug registers."
+    ///
+    /// This reads DHCSR.C_DEBUGEN, which a debugger sets when it connects, and normally clears
+    /// when it detaches. A system reset doesn't clear it, only a power-on reset does, so if the
+    /// debugger goes away without clearing it, e.g. because the probe was unplugged, this keeps
+    /// reporting a debugger until the device is power cycled. While C_DEBUGEN is set, a `bkpt`
+    /// halts the core whether or not a debugger is there to resume it; it only escalates to
+    /// HardFault with C_DEBUGEN clear (and no debug monitor enabled).
+    ///
+    /// This is an associated function, so that fault handlers can call it without owning the
+    /// DCB.
     #[inline]
     pub fn is_debugger_attached() -> bool {
         unsafe {
             // do an 8-bit read of the 32-bit DHCSR register, and get the LSB
             let value = ptr::read_volatile(Self::PTR as *const u8);
-            value & 0x1 == 1
+            u32::from(value) & DCB_DHCSR_C_DEBUGEN != 0
         }
     }
 }
@@ -0,0 +1,170 @@
+//! Core register access for debug monitors
+//!
+//! A monitor mode debug stub, e.g. a GDB stub over a UART, reads and writes the core registers of
+//! the code it interrupted. From the
+//! [`DebugMonitor`](crate::peripheral::scb::Exception::DebugMonitor) handler these are in the
+//! exception frame the processor stacked on entry, see
+//! [`read_stacked_register`], and in the callee-saved registers the handler captured with
+//! [`capture_registers!`](crate::capture_registers) on entry, see
+//! [`read_callee_saved_register`]. The stacked SP is the frame address plus
+//! [`stacked_frame_size`](crate::register::frame::stacked_frame_size).
+//!
+//! The other registers are still live in the handler, and read directly. PRIMASK, FAULTMASK and
+//! BASEPRI hold the values of the interrupted context until the handler changes them. CONTROL
+//! doesn't, as exception entry clears its SPSEL and FPCA bits, and SP is the handler's. For the FP
+//! registers, what the handler sees depends on the frame type: if `EXC_RETURN.FType` is clear, the
+//! interrupted context was using the FPU and an extended frame reserves S0-S15 and FPSCR after the
+//! basic frame. With lazy stacking (`FPCCR.LSPEN`, the reset default) they are only written there
+//! when the handler executes its first FP instruction; until then, the live S0-S15 and FPSCR still
+//! hold the interrupted context's values, and after it, the stacked copy does. S16-S31 are never
+//! stacked on exception entry, and are the interrupted context's until the handler itself uses
+//! them.
+//!
+//! [`read_core_register`] and [`write_core_register`] access the registers through DCRSR and
+//! DCRDR instead. They are the debugger side: the core has to be halted in Debug state, and the
+//! DCB is private to each core, so they take the debug registers of *another* core, e.g. mapped
+//! into the address space of this one by a debug access port. They can't be used on the core
+//! they run on.
+//!
+//! *NOTE* Not available on Armv6-M.
+
+use super::{RegisterBlock, DCB_DHCSR_S_HALT, DCB_DHCSR_S_REGRDY};
+use crate::register::frame::{CoreRegisters, ExceptionFrame};
+
+const DCB_DCRSR_REGWNR: u32 = 1 << 16;
+
+/// A core register, as selected by `DCRSR.REGSEL`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CoreRegister {
+    /// (General purpose) Register 0 to 12, `R(13)` and up are invalid
+    R(u8),
+    /// The current stack pointer, MSP or PSP
+    Sp,
+    /// Link Register
+    Lr,
+    /// Program Counter, the debug return address when halted
+    Pc,
+    /// Program Status Register
+    Xpsr,
+    /// Main Stack Pointer
+    Msp,
+    /// Process Stack Pointer
+    Psp,
+    /// CONTROL, FAULTMASK, BASEPRI and PRIMASK packed in bits `[31:24]`, `[23:16]`, `[15:8]` and
+    /// `[7:0]`
+    Special,
+    /// Floating-point Status and Control Register
+    Fpscr,
+    /// Floating-point register S0 to S31, `S(32)` and up are invalid
+    S(u8),
+}
+
+impl CoreRegister {
+    /// Returns the `DCRSR.REGSEL` value selecting this register, or `None` if the register number
+    /// of [`R`](CoreRegister::R) or [`S`](CoreRegister::S) is out of range
+    #[inline]
+    pub const fn selector(self) -> Option<u32> {
+        match self {
+            CoreRegister::R(n) if n <= 12 => Some(n as u32),
+            CoreRegister::R(_) => None,
+            CoreRegister::Sp => Some(13),
+            CoreRegister::Lr => Some(14),
+            CoreRegister::Pc => Some(15),
+            CoreRegister::Xpsr => Some(16),
+            CoreRegister::Msp => Some(17),
+            CoreRegister::Psp => Some(18),
+            CoreRegister::Special => Some(20),
+            CoreRegister::Fpscr => Some(33),
+            CoreRegister::S(n) if n <= 31 => Some(64 + n as u32),
+            CoreRegister::S(_) => None,
+        }
+    }
+}
+
+/// Returns the value of `reg` in the interrupted context from the exception frame `frame`
+///
+/// Only R0-R3, R12, LR, PC and xPSR are stacked; this returns `None` for any other register, see
+/// the [module documentation](self) for where to find those.
+#[inline]
+pub fn read_stacked_register(frame: &ExceptionFrame, reg: CoreRegister) -> Option<u32> {
+    match reg {
+        CoreRegister::R(0) => Some(frame.r0),
+        CoreRegister::R(1) => Some(frame.r1),
+        CoreRegister::R(2) => Some(frame.r2),
+        CoreRegister::R(3) => Some(frame.r3),
+        CoreRegister::R(12) => Some(frame.r12),
+        CoreRegister::Lr => Some(frame.lr),
+        CoreRegister::Pc => Some(frame.pc),
+        CoreRegister::Xpsr => Some(frame.xpsr),
+        _ => None,
+    }
+}
+
+/// Returns the value of `reg` in the interrupted context from the callee-saved registers `regs`
+///
+/// `regs` must have been captured with [`capture_registers!`](crate::capture_registers) on entry
+/// to the handler. This returns `None` for any register other than R4-R11.
+#[inline]
+pub fn read_callee_saved_register(regs: &CoreRegisters, reg: CoreRegister) -> Option<u32> {
+    match reg {
+        CoreRegister::R(4) => Some(regs.r4),
+        CoreRegister::R(5) => Some(regs.r5),
+        CoreRegister::R(6) => Some(regs.r6),
+        CoreRegister::R(7) => Some(regs.r7),
+        CoreRegister::R(8) => Some(regs.r8),
+        CoreRegister::R(9) => Some(regs.r9),
+        CoreRegister::R(10) => Some(regs.r10),
+        CoreRegister::R(11) => Some(regs.r11),
+        _ => None,
+    }
+}
+
+/// Reads `reg` of the core whose debug registers are at `dcb`, through DCRSR and DCRDR
+///
+/// Returns `None` if `reg` has no [selector](CoreRegister::selector), or if the core isn't
+/// halted (`DHCSR.S_HALT`). Checking this reads DHCSR, which clears its sticky status bits.
+///
+/// # Safety
+///
+/// `dcb` must point to the debug registers of another core, see the
+/// [module documentation](self), and nothing else may use its DCRSR and DCRDR at the same time.
+/// If the core resumes during the access, the result is unpredictable, and this may never return
+/// as it waits for `DHCSR.S_REGRDY`.
+#[inline]
+pub unsafe fn read_core_register(dcb: *const RegisterBlock, reg: CoreRegister) -> Option<u32> {
+    let selector = reg.selector()?;
+    let dcb = &*dcb;
+    if dcb.dhcsr.read() & DCB_DHCSR_S_HALT == 0 {
+        return None;
+    }
+    dcb.dcrsr.write(selector);
+    while dcb.dhcsr.read() & DCB_DHCSR_S_REGRDY == 0 {}
+    Some(dcb.dcrdr.read())
+}
+
+/// Writes `value` to `reg` of the core whose debug registers are at `dcb`, through DCRSR and DCRDR
+///
+/// Returns `None`, and writes nothing, if `reg` has no [selector](CoreRegister::selector) or if
+/// the core isn't halted.
+///
+/// # Safety
+///
+/// See [`read_core_register`]. Writing the registers of the halted core changes the state it
+/// resumes with.
+#[inline]
+pub unsafe fn write_core_register(
+    dcb: *const RegisterBlock,
+    reg: CoreRegister,
+    value: u32,
+) -> Option<()> {
+    let selector = reg.selector()?;
+    let dcb = &*dcb;
+    if dcb.dhcsr.read() & DCB_DHCSR_S_HALT == 0 {
+        return None;
+    }
+    dcb.dcrdr.write(value);
+    dcb.dcrsr.write(selector | DCB_DCRSR_REGWNR);
+    while dcb.dhcsr.read() & DCB_DHCSR_S_REGRDY == 0 {}
+    Some(())
+}
@@ -4,6 +4,8 @@
 use volatile_register::WO;
 use volatile_register::{RO, RW};
 
+#[cfg(not(armv6m))]
+use crate::peripheral::DCB;
 use crate::peripheral::DWT;
 use bitfield::bitfield;
 
@@ -57,8 +59,17 @@ bitfield! {
     #[derive(Copy, Clone)]
     pub struct Ctrl(u32);
     cyccntena, set_cyccntena: 0;
+    u8, postpreset, set_postpreset: 4, 1;
+    u8, postinit, set_postinit: 8, 5;
+    cyctap, set_cyctap: 9;
+    u8, synctap, set_synctap: 11, 10;
     pcsamplena, set_pcsamplena: 12;
     exctrcena, set_exctrcena: 16;
+    cpievtena, set_cpievtena: 17;
+    excevtena, set_excevtena: 18;
+    sleepevtena, set_sleepevtena: 19;
+    lsuevtena, set_lsuevtena: 20;
+    foldevtena, set_foldevtena: 21;
     noprfcnt, _: 24;
     nocyccnt, _: 25;
     noexttrig, _: 26;
@@ -138,15 +149,13 @@ impl DWT {
 
     /// Enables the cycle counter
     ///
-    /// The global trace enable ([`DCB::enable_trace`]) should be set before
-    /// enabling the cycle counter, the processor may ignore writes to the
-    /// cycle counter enable if the global trace is disabled
+    /// The global trace enable is set with `dcb` first, see [`DCB::enable_trace`]: the processor
+    /// may ignore writes to the cycle counter enable while global trace is disabled
     /// (implementation defined behaviour).
-    ///
-    /// [`DCB::enable_trace`]: crate::peripheral::DCB::enable_trace
     #[cfg(not(armv6m))]
     #[inline]
-    pub fn enable_cycle_counter(&mut self) {
+    pub fn enable_cycle_counter(&mut self, dcb: &mut DCB) {
+        dcb.enable_trace();
         unsafe {
             self.ctrl.modify(|mut r| {
                 r.set_cyccntena(true);
@@ -174,6 +183,27 @@ impl DWT {
         self.ctrl.read().cyccntena()
     }
 
+    /// Enables the sleep counter (`SLEEPCNT`) and the cycle counter
+    ///
+    /// The global trace enable is set with `dcb` first, see [`DCB::enable_trace`]. Returns an
+    /// error if the implementation lacks the profiling counters or the cycle counter.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn enable_sleep_counting(&mut self, dcb: &mut DCB) -> Result<(), DwtError> {
+        dcb.enable_trace();
+        if !self.has_profiling_counter() || !self.has_cycle_counter() {
+            return Err(DwtError::UnsupportedOnArchitecture);
+        }
+        unsafe {
+            self.ctrl.modify(|mut r| {
+                r.set_sleepevtena(true);
+                r.set_cyccntena(true);
+                r
+            });
+        }
+        Ok(())
+    }
+
     /// Enables exception tracing
     #[cfg(not(armv6m))]
     #[inline]
@@ -210,6 +240,49 @@ impl DWT {
         }
     }
 
+    /// Returns the cycle counter bit that generates periodic synchronization packets
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn sync_tap(&self) -> SyncTap {
+        match self.ctrl.read().synctap() {
+            0b01 => SyncTap::Cycles2Pow24,
+            0b10 => SyncTap::Cycles2Pow26,
+            0b11 => SyncTap::Cycles2Pow28,
+            _ => SyncTap::Disabled,
+        }
+    }
+
+    /// Selects the cycle counter bit that generates periodic synchronization packets
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn set_sync_tap(&mut self, tap: SyncTap) {
+        unsafe {
+            self.ctrl.modify(|mut r| {
+                r.set_synctap(tap as u8);
+                r
+            });
+        }
+    }
+
+    /// Configures the `POSTCNT` counter, which sets the period of PC samples and cycle count
+    /// events
+    ///
+    /// `POSTCNT` decrements every time the selected cycle counter `tap` bit toggles and generates a
+    /// sample when it underflows, after which it is reloaded with `reload`. A sample is thus taken
+    /// every `(reload + 1) * tap` cycles. Only the 4 lowest bits of `reload` are used.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn set_sample_period(&mut self, tap: PostCntTap, reload: u8) {
+        unsafe {
+            self.ctrl.modify(|mut r| {
+                r.set_cyctap(tap == PostCntTap::Cycles1024);
+                r.set_postinit(reload);
+                r.set_postpreset(reload);
+                r
+            });
+        }
+    }
+
     /// Returns the current clock cycle count
     #[cfg(not(armv6m))]
     #[inline]
@@ -246,6 +319,88 @@ impl DWT {
         unsafe { (*Self::PTR).lar.write(0xC5AC_CE55) }
     }
 
+    /// Reads a profiling counter and clears it, with the counter stopped in between
+    ///
+    /// `enabled` and `set_enabled` access the enable bit of the counter, which is restored
+    /// afterwards.
+    #[cfg(not(armv6m))]
+    fn take_count(
+        &mut self,
+        enabled: fn(&Ctrl) -> bool,
+        set_enabled: fn(&mut Ctrl, bool),
+        counter: fn(&RegisterBlock) -> &RW<u32>,
+    ) -> u8 {
+        crate::interrupt::free(|| {
+            let ctrl = self.ctrl.read();
+            if enabled(&ctrl) {
+                let mut stopped = ctrl;
+                set_enabled(&mut stopped, false);
+                unsafe { self.ctrl.write(stopped) };
+            }
+            let count = counter(self).read() as u8;
+            unsafe {
+                counter(self).write(0);
+                self.ctrl.write(ctrl);
+            }
+            count
+        })
+    }
+
+    /// Reads and clears the CPI count, see [`cpi_count`](DWT::cpi_count)
+    ///
+    /// Unlike [`cpi_count`](DWT::cpi_count) followed by
+    /// [`set_cpi_count`](DWT::set_cpi_count), this doesn't lose the
+    /// events between the read and the write: the counter is stopped
+    /// while it's read and cleared, with interrupts disabled, and
+    /// restarted afterwards if it was enabled. What is lost instead are
+    /// the events of the few instructions that run with the counter
+    /// stopped, which are the same on every call, so periodic samples
+    /// stay comparable.
+    ///
+    /// To measure without stopping the counter at all, use the wrapping
+    /// differences of [`ProfilingSnapshot`]s instead.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn take_cpi_count(&mut self) -> u8 {
+        self.take_count(Ctrl::cpievtena, Ctrl::set_cpievtena, |dwt| &dwt.cpicnt)
+    }
+
+    /// Reads and clears the exception count, see
+    /// [`exception_count`](DWT::exception_count) and
+    /// [`take_cpi_count`](DWT::take_cpi_count) for the guarantees.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn take_exception_count(&mut self) -> u8 {
+        self.take_count(Ctrl::excevtena, Ctrl::set_excevtena, |dwt| &dwt.exccnt)
+    }
+
+    /// Reads and clears the sleep count, see [`sleep_count`](DWT::sleep_count)
+    /// and [`take_cpi_count`](DWT::take_cpi_count) for the guarantees.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn take_sleep_count(&mut self) -> u8 {
+        self.take_count(Ctrl::sleepevtena, Ctrl::set_sleepevtena, |dwt| {
+            &dwt.sleepcnt
+        })
+    }
+
+    /// Reads and clears the LSU count, see [`lsu_count`](DWT::lsu_count) and
+    /// [`take_cpi_count`](DWT::take_cpi_count) for the guarantees.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn take_lsu_count(&mut self) -> u8 {
+        self.take_count(Ctrl::lsuevtena, Ctrl::set_lsuevtena, |dwt| &dwt.lsucnt)
+    }
+
+    /// Reads and clears the folded instruction count, see
+    /// [`fold_count`](DWT::fold_count) and
+    /// [`take_cpi_count`](DWT::take_cpi_count) for the guarantees.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn take_fold_count(&mut self) -> u8 {
+        self.take_count(Ctrl::foldevtena, Ctrl::set_foldevtena, |dwt| &dwt.foldcnt)
+    }
+
     /// Get the CPI count
     ///
     /// Counts additional cycles required to execute multi-cycle instructions,
@@ -336,6 +491,285 @@ impl DWT {
     }
 }
 
+/// A snapshot of the DWT profiling counters
+///
+/// See [`ProfilingSnapshot::capture_fast`] and [`ProfilingSnapshot::delta`].
+#[cfg(not(armv6m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ProfilingSnapshot {
+    /// `CYCCNT`, the clock cycle count
+    pub cycles: u32,
+    /// `CPICNT`, see [`DWT::cpi_count`]
+    pub cpi: u8,
+    /// `EXCCNT`, see [`DWT::exception_count`]
+    pub exception: u8,
+    /// `SLEEPCNT`, see [`DWT::sleep_count`]
+    pub sleep: u8,
+    /// `LSUCNT`, see [`DWT::lsu_count`]
+    pub lsu: u8,
+    /// `FOLDCNT`, see [`DWT::fold_count`]
+    pub fold: u8,
+}
+
+#[cfg(not(armv6m))]
+impl ProfilingSnapshot {
+    /// Reads all profiling counters in a tight sequence of volatile reads
+    ///
+    /// Every read is itself a load instruction that perturbs the counters, so reading them one at
+    /// a time through the `DWT` accessors skews them relative to `CYCCNT`. Here `CYCCNT` is read
+    /// first, followed by `CPICNT`, `EXCCNT`, `SLEEPCNT`, `LSUCNT` and `FOLDCNT` in register order,
+    /// with no other work in between. The perturbation is then the same for every snapshot and
+    /// cancels out in [`delta`](Self::delta).
+    ///
+    /// The counters must have been enabled, e.g. with [`DWT::enable_cycle_counter`].
+    #[inline]
+    pub fn capture_fast(dwt: &DWT) -> Self {
+        // NOTE the `as u8` truncations happen after all reads have been issued
+        let cycles = dwt.cyccnt.read();
+        let cpi = dwt.cpicnt.read();
+        let exception = dwt.exccnt.read();
+        let sleep = dwt.sleepcnt.read();
+        let lsu = dwt.lsucnt.read();
+        let fold = dwt.foldcnt.read();
+
+        ProfilingSnapshot {
+            cycles,
+            cpi: cpi as u8,
+            exception: exception as u8,
+            sleep: sleep as u8,
+            lsu: lsu as u8,
+            fold: fold as u8,
+        }
+    }
+
+    /// Computes the change of all counters since the `earlier` snapshot
+    ///
+    /// All counters are wrapping-subtracted. The 8-bit counters wrap every 256 events, so the
+    /// result is only meaningful if fewer events than that happened between both snapshots.
+    #[inline]
+    pub fn delta(&self, earlier: &Self) -> ProfilingDelta {
+        ProfilingDelta {
+            cycles: self.cycles.wrapping_sub(earlier.cycles),
+            cpi: self.cpi.wrapping_sub(earlier.cpi),
+            exception: self.exception.wrapping_sub(earlier.exception),
+            sleep: self.sleep.wrapping_sub(earlier.sleep),
+            lsu: self.lsu.wrapping_sub(earlier.lsu),
+            fold: self.fold.wrapping_sub(earlier.fold),
+        }
+    }
+}
+
+/// The change of the DWT profiling counters between two [`ProfilingSnapshot`]s
+///
+/// The `Debug` (and `defmt::Format`) output includes the derived metrics, so a single log line
+/// gives a profile summary.
+#[cfg(not(armv6m))]
+#[derive(Eq, PartialEq, Copy, Clone)]
+pub struct ProfilingDelta {
+    /// Elapsed clock cycles
+    pub cycles: u32,
+    /// Additional cycles of multi-cycle instructions and instruction fetch stalls
+    pub cpi: u8,
+    /// Cycles spent in exception processing
+    pub exception: u8,
+    /// Cycles spent sleeping
+    pub sleep: u8,
+    /// Additional cycles of load and store instructions
+    pub lsu: u8,
+    /// Folded instructions
+    pub fold: u8,
+}
+
+#[cfg(not(armv6m))]
+impl ProfilingDelta {
+    /// Fraction of the elapsed cycles spent stalled (`(CPICNT + LSUCNT) / CYCCNT`)
+    #[inline]
+    pub fn stall_fraction(&self) -> f32 {
+        self.fraction(self.cpi as u32 + self.lsu as u32)
+    }
+
+    /// Percentage of the elapsed cycles spent in exception processing (`100 * EXCCNT / CYCCNT`)
+    #[inline]
+    pub fn exception_overhead_percent(&self) -> f32 {
+        self.fraction(self.exception as u32) * 100.0
+    }
+
+    fn fraction(&self, count: u32) -> f32 {
+        if self.cycles == 0 {
+            0.0
+        } else {
+            count as f32 / self.cycles as f32
+        }
+    }
+}
+
+#[cfg(not(armv6m))]
+impl core::fmt::Debug for ProfilingDelta {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ProfilingDelta")
+            .field("cycles", &self.cycles)
+            .field("cpi", &self.cpi)
+            .field("exception", &self.exception)
+            .field("sleep", &self.sleep)
+            .field("lsu", &self.lsu)
+            .field("fold", &self.fold)
+            .field("stall_fraction", &self.stall_fraction())
+            .field(
+                "exception_overhead_percent",
+                &self.exception_overhead_percent(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(all(not(armv6m), feature = "defmt"))]
+impl defmt::Format for ProfilingDelta {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "ProfilingDelta {{ cycles: {=u32}, cpi: {=u8}, exception: {=u8}, sleep: {=u8}, ",
+            self.cycles,
+            self.cpi,
+            self.exception,
+            self.sleep,
+        );
+        defmt::write!(
+            f,
+            "lsu: {=u8}, fold: {=u8}, stall_fraction: {=f32}, ",
+            self.lsu,
+            self.fold,
+            self.stall_fraction(),
+        );
+        defmt::write!(
+            f,
+            "exception_overhead_percent: {=f32} }}",
+            self.exception_overhead_percent(),
+        )
+    }
+}
+
+/// Measures the fraction of time the processor spends idle, from `CYCCNT`
+///
+/// Enable the cycle counter with [`DWT::enable_cycle_counter`], create the meter with
+/// [`start`](IdleMeter::start), then run every wait for an event or interrupt through
+/// [`idle`](IdleMeter::idle), which counts the cycles spent in it as idle. All other cycles are
+/// counted as busy.
+///
+/// `SLEEPCNT` isn't used: it is an 8-bit counter that wraps silently, so a single sleep of more
+/// than 255 cycles would be lost, and it can't be read while the processor sleeps. `CYCCNT` is
+/// 32 bits wide, so each busy or idle interval has to be shorter than 2^32 cycles:
+/// call [`sample`](IdleMeter::sample) at least that often while busy, e.g. from a periodic
+/// interrupt, and keep single waits shorter than that. Whether `CYCCNT` runs in deeper sleep
+/// states, where the processor clock may be gated, is implementation defined.
+///
+/// Interrupt handlers that run right after waking up, before `idle` returns, are counted as
+/// idle. To count them as busy, wait with interrupts disabled: `WFI` still wakes up on a pending
+/// interrupt, which is then taken once interrupts are enabled again.
+///
+/// # Examples
+///
+/// ```no_run
+/// use cortex_m::peripheral::dwt::IdleMeter;
+/// # fn wait_for_interrupt() {}
+///
+/// let mut p = cortex_m::Peripherals::take().unwrap();
+/// p.DWT.enable_cycle_counter(&mut p.DCB);
+///
+/// let mut meter = IdleMeter::start(&p.DWT);
+/// for _ in 0..1000 {
+///     // e.g. `cortex_m::asm::wfi`
+///     cortex_m::interrupt::free(|| meter.idle(&p.DWT, wait_for_interrupt));
+/// }
+/// let idle = meter.idle_percent();
+/// ```
+#[cfg(not(armv6m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct IdleMeter {
+    last_cycles: u32,
+    cycles: u64,
+    idle: u64,
+}
+
+#[cfg(not(armv6m))]
+impl IdleMeter {
+    /// Creates a meter starting at the given `CYCCNT` value
+    #[inline]
+    pub const fn new(cycles: u32) -> Self {
+        IdleMeter {
+            last_cycles: cycles,
+            cycles: 0,
+            idle: 0,
+        }
+    }
+
+    /// Creates a meter starting at the current `CYCCNT` value
+    #[inline]
+    pub fn start(dwt: &DWT) -> Self {
+        Self::new(dwt.cyccnt.read())
+    }
+
+    /// Runs `wait`, e.g. [`asm::wfi`](crate::asm::wfi), counting the cycles it takes as idle
+    #[inline]
+    pub fn idle<R>(&mut self, dwt: &DWT, wait: impl FnOnce() -> R) -> R {
+        self.record_busy(dwt.cyccnt.read());
+        let r = wait();
+        self.record_idle(dwt.cyccnt.read());
+        r
+    }
+
+    /// Reads `CYCCNT` and counts the cycles elapsed since the previous sample as busy
+    #[inline]
+    pub fn sample(&mut self, dwt: &DWT) {
+        self.record_busy(dwt.cyccnt.read());
+    }
+
+    /// Counts the cycles elapsed since the previous sample as busy, given the `CYCCNT` value
+    /// read now
+    #[inline]
+    pub fn record_busy(&mut self, cycles: u32) {
+        self.advance(cycles);
+    }
+
+    /// Counts the cycles elapsed since the previous sample as idle, given the `CYCCNT` value
+    /// read now
+    #[inline]
+    pub fn record_idle(&mut self, cycles: u32) {
+        self.idle += self.advance(cycles);
+    }
+
+    /// Cycles elapsed over all samples
+    #[inline]
+    pub fn total_cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Cycles spent idle over all samples
+    #[inline]
+    pub fn idle_cycles(&self) -> u64 {
+        self.idle
+    }
+
+    /// Percentage of the elapsed cycles spent idle, `0.0` if no cycles elapsed
+    #[inline]
+    pub fn idle_percent(&self) -> f32 {
+        if self.cycles == 0 {
+            0.0
+        } else {
+            self.idle as f32 * 100.0 / self.cycles as f32
+        }
+    }
+
+    /// Adds the cycles elapsed since the previous sample to the total, and returns them.
+    fn advance(&mut self, cycles: u32) -> u64 {
+        let elapsed = u64::from(cycles.wrapping_sub(self.last_cycles));
+        self.last_cycles = cycles;
+        self.cycles += elapsed;
+        elapsed
+    }
+}
+
 /// Whether the comparator should match on read, write or read/write operations.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum AccessType {
@@ -395,6 +829,30 @@ pub struct CycleCountSettings {
     pub compare: u32,
 }
 
+/// The cycle counter bit that generates periodic synchronization packets.
+#[cfg(not(armv6m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SyncTap {
+    /// No synchronization packets.
+    Disabled = 0b00,
+    /// Every 2^24 cycles.
+    Cycles2Pow24 = 0b01,
+    /// Every 2^26 cycles.
+    Cycles2Pow26 = 0b10,
+    /// Every 2^28 cycles.
+    Cycles2Pow28 = 0b11,
+}
+
+/// The cycle counter bit that decrements `POSTCNT`.
+#[cfg(not(armv6m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum PostCntTap {
+    /// `POSTCNT` decrements every 64 cycles.
+    Cycles64,
+    /// `POSTCNT` decrements every 1024 cycles.
+    Cycles1024,
+}
+
 /// The available functions of a DWT comparator.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 #[non_exhaustive]
@@ -414,10 +872,226 @@ pub enum ComparatorFunction {
 pub enum DwtError {
     /// Invalid combination of [AccessType] and [EmitOption].
     InvalidFunction,
+    /// The requested match is not supported by the comparator model of the target architecture.
+    UnsupportedOnArchitecture,
+    /// The watched address is not aligned to the size of the watched data.
+    MisalignedAddress,
+    /// The DWT implements too few comparators for the requested configuration.
+    NotEnoughComparators,
+}
+
+/// Size of the data item watched by a [`WatchpointConfig`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum DataSize {
+    /// A single byte.
+    Byte,
+    /// A halfword (2 bytes).
+    Halfword,
+    /// A word (4 bytes).
+    Word,
+}
+
+impl DataSize {
+    /// The `DATAVSIZE` field encoding of this size.
+    #[inline]
+    const fn datavsize(self) -> u8 {
+        match self {
+            DataSize::Byte => 0b00,
+            DataSize::Halfword => 0b01,
+            DataSize::Word => 0b10,
+        }
+    }
+
+    /// The size in bytes.
+    #[inline]
+    const fn bytes(self) -> u32 {
+        1 << self.datavsize()
+    }
+}
+
+/// What a [`WatchpointConfig`] matches against.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum WatchpointMatch {
+    /// Match data accesses to `size` bytes starting at `address`, which must be aligned to `size`.
+    DataAddress {
+        /// The address to match against.
+        address: u32,
+        /// Size of the watched data item.
+        size: DataSize,
+        /// Whether to match on read, write or read/write operations.
+        access_type: AccessType,
+    },
+    /// Match the execution of the instruction at `address`.
+    InstructionAddress {
+        /// The instruction address to match against.
+        address: u32,
+    },
+    /// Match data accesses of `size` bytes transferring `value`, at any address.
+    ///
+    /// **NOTE**: only supported on Armv8-M. On Armv7-M data value matching requires linking the
+    /// value comparator to address comparators, which a single [`WatchpointConfig`] can't express.
+    DataValue {
+        /// The data value to match against.
+        value: u32,
+        /// Size of the matched data.
+        size: DataSize,
+        /// Whether to match on read, write or read/write operations.
+        access_type: AccessType,
+    },
+}
+
+/// The event generated when a [`WatchpointConfig`] matches.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum WatchpointAction {
+    /// Generate a watchpoint debug event. Either halts execution or fires a `DebugMonitor`
+    /// exception.
+    DebugEvent,
+    /// Generate a `CMPMATCH[N]` event, e.g. to trigger the ETM.
+    CompareMatchEvent,
+}
+
+/// Architecture independent watchpoint configuration of a single comparator.
+///
+/// Armv7-M and Armv8-M encode the comparator `FUNCTION` register differently: Armv7-M uses the
+/// `FUNCTION`/`EMITRANGE`/`DATAVMATCH` fields plus a `MASK` register, whereas Armv8-M uses the
+/// `MATCH`/`ACTION` fields and has no `MASK` register. [`Comparator::configure_watchpoint`]
+/// picks the encoding of the target architecture; [`WatchpointConfig::encode_v7m`] and
+/// [`WatchpointConfig::encode_v8m`] expose both encodings on every target.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct WatchpointConfig {
+    /// What to match against.
+    pub matches: WatchpointMatch,
+    /// What to generate on a match.
+    pub action: WatchpointAction,
+}
+
+/// Comparator register values produced by encoding a [`WatchpointConfig`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct WatchpointEncoding {
+    /// Value of the `COMP` register.
+    pub comp: u32,
+    /// Value of the `MASK` register. Always zero for Armv8-M, which doesn't implement it.
+    pub mask: u32,
+    /// Value of the `FUNCTION` register.
+    pub function: u32,
+}
+
+impl WatchpointConfig {
+    /// Encodes the configuration for the Armv7-M comparator model.
+    ///
+    /// See Table C1-14 of the Armv7-M Architecture Reference Manual.
+    #[inline]
+    pub const fn encode_v7m(&self) -> Result<WatchpointEncoding, DwtError> {
+        let (address, mask, function) = match self.matches {
+            WatchpointMatch::DataAddress {
+                address,
+                size,
+                access_type,
+            } => {
+                if address % size.bytes() != 0 {
+                    return Err(DwtError::MisalignedAddress);
+                }
+
+                let function = match (access_type, self.action) {
+                    (AccessType::ReadOnly, WatchpointAction::DebugEvent) => 0b0101,
+                    (AccessType::WriteOnly, WatchpointAction::DebugEvent) => 0b0110,
+                    (AccessType::ReadWrite, WatchpointAction::DebugEvent) => 0b0111,
+                    (AccessType::ReadOnly, WatchpointAction::CompareMatchEvent) => 0b1001,
+                    (AccessType::WriteOnly, WatchpointAction::CompareMatchEvent) => 0b1010,
+                    (AccessType::ReadWrite, WatchpointAction::CompareMatchEvent) => 0b1011,
+                };
+
+                // MASK is the number of low address bits ignored by the comparison.
+                (address, size.datavsize() as u32, function)
+            }
+            WatchpointMatch::InstructionAddress { address } => {
+                let function = match self.action {
+                    WatchpointAction::DebugEvent => 0b0100,
+                    WatchpointAction::CompareMatchEvent => 0b1000,
+                };
+
+                (address, 0, function)
+            }
+            WatchpointMatch::DataValue { .. } => return Err(DwtError::UnsupportedOnArchitecture),
+        };
+
+        // EMITRANGE, CYCMATCH, DATAVMATCH, DATAVSIZE and DATAVADDRn are all left cleared.
+        Ok(WatchpointEncoding {
+            comp: address,
+            mask,
+            function,
+        })
+    }
+
+    /// Encodes the configuration for the Armv8-M comparator model.
+    ///
+    /// See section D1.2.66 "DWT_FUNCTIONn, DWT Comparator Function Register" of the Armv8-M
+    /// Architecture Reference Manual.
+    #[inline]
+    pub const fn encode_v8m(&self) -> Result<WatchpointEncoding, DwtError> {
+        const MATCH_INSTRUCTION_ADDRESS: u32 = 0b0010;
+        const MATCH_DATA_ADDRESS: u32 = 0b0100;
+        const MATCH_DATA_VALUE: u32 = 0b1000;
+        const ACTION_SHIFT: u32 = 4;
+        const DATAVSIZE_SHIFT: u32 = 10;
+
+        // MATCH encodes the access type in its low two bits, in this order, for both data address
+        // and data value matches.
+        const fn access_offset(access_type: AccessType) -> u32 {
+            match access_type {
+                AccessType::ReadWrite => 0b00,
+                AccessType::WriteOnly => 0b01,
+                AccessType::ReadOnly => 0b10,
+            }
+        }
+
+        let (comp, match_, size) = match self.matches {
+            WatchpointMatch::DataAddress {
+                address,
+                size,
+                access_type,
+            } => {
+                if address % size.bytes() != 0 {
+                    return Err(DwtError::MisalignedAddress);
+                }
+
+                (
+                    address,
+                    MATCH_DATA_ADDRESS | access_offset(access_type),
+                    size,
+                )
+            }
+            // Instructions are at least a halfword in size.
+            WatchpointMatch::InstructionAddress { address } => {
+                (address, MATCH_INSTRUCTION_ADDRESS, DataSize::Halfword)
+            }
+            WatchpointMatch::DataValue {
+                value,
+                size,
+                access_type,
+            } => (value, MATCH_DATA_VALUE | access_offset(access_type), size),
+        };
+
+        let action = match self.action {
+            WatchpointAction::CompareMatchEvent => 0b00,
+            WatchpointAction::DebugEvent => 0b01,
+        };
+
+        Ok(WatchpointEncoding {
+            comp,
+            mask: 0,
+            function: match_
+                | (action << ACTION_SHIFT)
+                | ((size.datavsize() as u32) << DATAVSIZE_SHIFT),
+        })
+    }
 }
 
 impl Comparator {
     /// Configure the function of the comparator
+    ///
+    /// *NOTE* this uses the Armv7-M comparator model. Use [`Comparator::configure_watchpoint`]
+    /// for watchpoints that work on both Armv7-M and Armv8-M.
     #[allow(clippy::missing_inline_in_public_items)]
     pub fn configure(&self, settings: ComparatorFunction) -> Result<(), DwtError> {
         match settings {
@@ -504,4 +1178,104 @@ impl Comparator {
 
         Ok(())
     }
+
+    /// Configure the comparator as a watchpoint, using the comparator model of the target
+    /// architecture.
+    ///
+    /// Returns [`DwtError::UnsupportedOnArchitecture`] for matches the target architecture can't
+    /// express with a single comparator, such as data value matching on Armv7-M.
+    #[inline]
+    pub fn configure_watchpoint(&self, config: WatchpointConfig) -> Result<(), DwtError> {
+        #[cfg(not(armv8m))]
+        let encoding = config.encode_v7m()?;
+        #[cfg(armv8m)]
+        let encoding = config.encode_v8m()?;
+
+        unsafe {
+            // Disable the comparator while it is being reconfigured.
+            self.function.write(Function(0));
+
+            self.comp.write(encoding.comp);
+            // Armv8-M doesn't implement the MASK register.
+            #[cfg(not(armv8m))]
+            self.mask.write(encoding.mask);
+
+            self.function.write(Function(encoding.function));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(armv7m)]
+impl DWT {
+    /// Halts (or fires a `DebugMonitor` exception) when `value` is written to the `size` bytes at
+    /// `address`
+    ///
+    /// On Armv7-M a data value watchpoint takes two linked comparators: comparator 0 matches the
+    /// address and comparator 1, the only one with data value matching, matches the value. Its
+    /// `FUNCTION.DATAVADDR0` field links it to comparator 0, so the watchpoint only fires when
+    /// both match. Both comparators are overwritten.
+    ///
+    /// Returns [`DwtError::NotEnoughComparators`] if fewer than two comparators are implemented
+    /// and [`DwtError::MisalignedAddress`] if `address` isn't aligned to `size`.
+    ///
+    /// Every data value comparator supports one linked address comparator (`DATAVADDR0`), which
+    /// is all this needs. `LNK1ENA` only says whether a second one (`DATAVADDR1`) is supported; if
+    /// it is, it's linked to comparator 0 too, as a single address watchpoint requires.
+    ///
+    /// On Armv8-M, use [`Comparator::configure_watchpoint`] with [`WatchpointMatch::DataValue`]
+    /// instead.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn set_data_value_watchpoint(
+        &mut self,
+        address: u32,
+        value: u32,
+        size: DataSize,
+    ) -> Result<(), DwtError> {
+        const ADDRESS_COMPARATOR: u8 = 0;
+        const VALUE_COMPARATOR: usize = 1;
+
+        if self.num_comp() < 2 {
+            return Err(DwtError::NotEnoughComparators);
+        }
+        if address % size.bytes() != 0 {
+            return Err(DwtError::MisalignedAddress);
+        }
+
+        // the data value has to be replicated over the whole comparator
+        let value = match size {
+            DataSize::Byte => (value & 0xFF) * 0x0101_0101,
+            DataSize::Halfword => (value & 0xFFFF) * 0x0001_0001,
+            DataSize::Word => value,
+        };
+
+        let address_comparator = &self.c[usize::from(ADDRESS_COMPARATOR)];
+        let value_comparator = &self.c[VALUE_COMPARATOR];
+        let second_link = value_comparator.function.read().lnk1ena();
+        unsafe {
+            value_comparator.function.write(Function(0));
+            address_comparator.function.write(Function(0));
+
+            // a linked address comparator keeps FUNCTION = 0b0000
+            address_comparator.comp.write(address);
+            address_comparator.mask.write(size.datavsize() as u32);
+
+            value_comparator.comp.write(value);
+            value_comparator.mask.write(0);
+            value_comparator.function.modify(|mut r| {
+                r.set_datavaddr0(ADDRESS_COMPARATOR);
+                if second_link {
+                    r.set_datavaddr1(ADDRESS_COMPARATOR);
+                }
+                r.set_datavsize(size.datavsize());
+                r.set_datavmatch(true);
+                // watchpoint debug event on write
+                r.set_function(0b0110);
+                r
+            });
+        }
+
+        Ok(())
+    }
 }
@@ -1,9 +1,39 @@
 //! Flash Patch and Breakpoint unit
 //!
 //! *NOTE* Not available on Armv6-M.
+//!
+//! # Breakpoints
+//!
+//! [`FPB::set_breakpoint`] plants a hardware breakpoint without an external probe. When a halting
+//! debugger is attached the breakpoint halts the core; otherwise it raises the
+//! [`DebugMonitor`](crate::peripheral::scb::Exception::DebugMonitor) exception, which must be
+//! enabled first with [`DCB::enable_debug_monitor`](crate::peripheral::DCB::enable_debug_monitor)
+//! (DEMCR.MON_EN). With neither, hitting a breakpoint escalates to a HardFault.
+//!
+//! # External debuggers
+//!
+//! An attached debugger programs the same registers to set its own breakpoints, and doesn't know
+//! about the ones set by software: it reports them as unexpected halts, and may overwrite or clear
+//! them whenever it updates its breakpoints. [`FPB::snapshot`] and [`FPB::restore`] save and put
+//! back the FPB as it was, but a restore also undoes any change the debugger made in between.
+//!
+//! # Flash patch
+//!
+//! [`FPB::configure_remap`] makes instruction fetches and literal loads from Code region words
+//! read their value from a [`RemapTable`] in SRAM instead, e.g. to hot-patch code in ROM. Not
+//! every FPB implements remapping, in particular most FPB v2 don't.
+//!
+//! The patched words may already be cached: after configuring the remap, invalidate the
+//! instruction cache and any data cache lines holding patched literals (see
+//! [`SCB::invalidate_icache`](crate::peripheral::SCB::invalidate_icache)) before running the
+//! patched code.
+
+use core::cell::UnsafeCell;
 
 use volatile_register::{RO, RW, WO};
 
+use crate::peripheral::FPB;
+
 /// Register block
 #[repr(C)]
 pub struct RegisterBlock {
@@ -19,3 +49,391 @@ pub struct RegisterBlock {
     /// Lock Status
     pub lsr: RO<u32>,
 }
+
+const FP_CTRL_ENABLE: u32 = 1 << 0;
+const FP_CTRL_KEY: u32 = 1 << 1;
+const FP_CTRL_REV_POS: u32 = 28;
+const FP_REMAP_RMPSPT: u32 = 1 << 29;
+
+/// Addresses past the Code region can't be breakpointed by an FPB v1, nor remapped.
+const CODE_REGION_END: u32 = 0x2000_0000;
+/// The remap table must be in the SRAM region.
+const SRAM_REGION: core::ops::Range<u32> = 0x2000_0000..0x4000_0000;
+
+/// The number of entries of a [`RemapTable`]
+pub const REMAP_TABLE_LEN: usize = 8;
+
+/// A flash patch remap table, see [`FPB::configure_remap`]
+///
+/// Comparator `n` remaps its address to entry `n`. The table is 32-byte aligned, as FP_REMAP
+/// requires, and has interior mutability so that a `static` table is placed in RAM: FP_REMAP can
+/// only point into the SRAM region.
+#[repr(C, align(32))]
+pub struct RemapTable(UnsafeCell<[u32; REMAP_TABLE_LEN]>);
+
+unsafe impl Sync for RemapTable {}
+
+impl RemapTable {
+    /// Creates a table with the replacement words `entries`
+    #[inline]
+    pub const fn new(entries: [u32; REMAP_TABLE_LEN]) -> Self {
+        RemapTable(UnsafeCell::new(entries))
+    }
+
+    /// Returns the address of the table
+    #[inline]
+    pub fn address(&self) -> u32 {
+        self.0.get() as u32
+    }
+}
+
+/// The architecture revision of the FPB, from FP_CTRL.REV
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FpbRevision {
+    /// Version 1, limited to breakpoints in the Code region (Armv7-M)
+    V1,
+    /// Version 2, which can breakpoint any address (Cortex-M7, Armv8-M)
+    V2,
+    /// An unknown revision, from the raw REV field
+    Unknown(u8),
+}
+
+/// The comparators and revision of an FPB, see [`FPB::capabilities`]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FpbCapabilities {
+    /// The number of instruction address comparators, which are the breakpoint slots
+    pub code_comparators: u8,
+    /// The number of literal address comparators, which follow the instruction comparators
+    pub literal_comparators: u8,
+    /// The architecture revision
+    pub revision: FpbRevision,
+}
+
+impl FpbCapabilities {
+    /// Decodes the capabilities from the FP_CTRL register value `ctrl`
+    #[inline]
+    pub const fn decode(ctrl: u32) -> Self {
+        FpbCapabilities {
+            // NUM_CODE is split in NUM_CODE1 (bits [7:4]) and NUM_CODE2 (bits [14:12])
+            code_comparators: (((ctrl >> 4) & 0xF) | ((ctrl >> 8) & 0x70)) as u8,
+            literal_comparators: ((ctrl >> 8) & 0xF) as u8,
+            revision: match (ctrl >> FP_CTRL_REV_POS) as u8 {
+                0 => FpbRevision::V1,
+                1 => FpbRevision::V2,
+                rev => FpbRevision::Unknown(rev),
+            },
+        }
+    }
+
+    /// Returns the total number of comparators
+    #[inline]
+    pub const fn comparators(&self) -> usize {
+        self.code_comparators as usize + self.literal_comparators as usize
+    }
+}
+
+/// The number of comparators saved by [`FPB::snapshot`], all the architecture allows
+const MAX_COMPARATORS: usize = 127;
+
+/// The state of the FPB, saved by [`FPB::snapshot`] and put back by [`FPB::restore`]
+#[derive(Clone)]
+pub struct FpbState {
+    enabled: bool,
+    remap: u32,
+    comparators: usize,
+    comp: [u32; MAX_COMPARATORS],
+}
+
+impl FpbState {
+    /// Returns whether the FPB was enabled
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the saved values of the implemented comparators
+    #[inline]
+    pub fn comparators(&self) -> &[u32] {
+        &self.comp[..self.comparators]
+    }
+}
+
+impl core::fmt::Debug for FpbState {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("FpbState")
+            .field("enabled", &self.enabled)
+            .field("remap", &self.remap)
+            .field("comparators", &self.comparators())
+            .finish()
+    }
+}
+
+/// Possible error values returned by the [`FPB`] breakpoint functions
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[non_exhaustive]
+pub enum FpbError {
+    /// The slot is not one of the implemented instruction comparators.
+    InvalidSlot,
+    /// The address is not halfword aligned.
+    MisalignedAddress,
+    /// The address is outside the Code region, which an FPB v1 can't breakpoint.
+    AddressNotBreakable,
+    /// The FPB revision is unknown, so the comparator encoding is too.
+    UnsupportedRevision,
+    /// The FPB doesn't support flash patch remapping.
+    RemapUnsupported,
+    /// The remap table is not in the SRAM region.
+    TableNotInSram,
+    /// The address is outside the Code region, or not word aligned, so it can't be remapped.
+    AddressNotRemappable,
+}
+
+/// Encodes a comparator value that breakpoints the instruction at `addr`.
+///
+/// An FPB v1 compares word addresses and selects which halfword to break on with the REPLACE
+/// field, and only covers the Code region (below `0x2000_0000`). An FPB v2 compares the full
+/// halfword address.
+#[inline]
+pub const fn encode_breakpoint(revision: FpbRevision, addr: u32) -> Result<u32, FpbError> {
+    const ENABLE: u32 = 1 << 0;
+    const REPLACE_LOWER: u32 = 0b01 << 30;
+    const REPLACE_UPPER: u32 = 0b10 << 30;
+
+    if addr % 2 != 0 {
+        return Err(FpbError::MisalignedAddress);
+    }
+
+    match revision {
+        FpbRevision::V1 => {
+            if addr >= CODE_REGION_END {
+                return Err(FpbError::AddressNotBreakable);
+            }
+            let replace = if addr & 0b10 == 0 {
+                REPLACE_LOWER
+            } else {
+                REPLACE_UPPER
+            };
+            Ok(replace | (addr & 0x1FFF_FFFC) | ENABLE)
+        }
+        // BPADDR is bits [31:1] and BE is bit 0
+        FpbRevision::V2 => Ok(addr | ENABLE),
+        FpbRevision::Unknown(_) => Err(FpbError::UnsupportedRevision),
+    }
+}
+
+/// Encodes a comparator value that remaps the word at `addr` to its [`RemapTable`] entry.
+#[inline]
+pub const fn encode_remap(revision: FpbRevision, addr: u32) -> Result<u32, FpbError> {
+    if addr % 4 != 0 || addr >= CODE_REGION_END {
+        return Err(FpbError::AddressNotRemappable);
+    }
+
+    match revision {
+        // REPLACE = 0b00 selects remapping
+        FpbRevision::V1 => Ok(addr | 1),
+        // FE is bit 31, and BE (bit 0) must be clear
+        FpbRevision::V2 => Ok(addr | (1 << 31)),
+        FpbRevision::Unknown(_) => Err(FpbError::UnsupportedRevision),
+    }
+}
+
+#[cfg(not(armv6m))]
+impl FPB {
+    /// Returns the number of comparators and the architecture revision of the FPB
+    #[inline]
+    pub fn capabilities(&self) -> FpbCapabilities {
+        FpbCapabilities::decode(self.ctrl.read())
+    }
+
+    /// Enables the FPB
+    #[inline]
+    pub fn enable(&mut self) {
+        // KEY must be set for the write to take effect
+        unsafe { self.ctrl.write(FP_CTRL_KEY | FP_CTRL_ENABLE) }
+    }
+
+    /// Disables the FPB, leaving the comparators configured
+    #[inline]
+    pub fn disable(&mut self) {
+        unsafe { self.ctrl.write(FP_CTRL_KEY) }
+    }
+
+    /// Sets a hardware breakpoint on the instruction at `addr`, using comparator `slot`
+    ///
+    /// The breakpoint only triggers while the FPB is [enabled](FPB::enable). See the
+    /// [module documentation](crate::peripheral::fpb#breakpoints) for where it is reported.
+    ///
+    /// Returns an error if `slot` isn't one of the
+    /// [`code_comparators`](FpbCapabilities::code_comparators) breakpoint slots, or if `addr`
+    /// can't be breakpointed, see [`encode_breakpoint`].
+    #[inline]
+    pub fn set_breakpoint(&mut self, slot: usize, addr: u32) -> Result<(), FpbError> {
+        let capabilities = self.capabilities();
+        if slot >= capabilities.code_comparators as usize {
+            return Err(FpbError::InvalidSlot);
+        }
+        let comp = encode_breakpoint(capabilities.revision, addr)?;
+        unsafe { self.comp[slot].write(comp) }
+        Ok(())
+    }
+
+    /// Clears the hardware breakpoint of comparator `slot`
+    #[inline]
+    pub fn clear_breakpoint(&mut self, slot: usize) -> Result<(), FpbError> {
+        if slot >= self.capabilities().code_comparators as usize {
+            return Err(FpbError::InvalidSlot);
+        }
+        unsafe { self.comp[slot].write(0) }
+        Ok(())
+    }
+
+    /// Saves the global enable, the remap address and all the comparators
+    ///
+    /// See the [module documentation](crate::peripheral::fpb#external-debuggers) about the
+    /// breakpoints of an attached debugger.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn snapshot(&self) -> FpbState {
+        let comparators = core::cmp::min(self.capabilities().comparators(), MAX_COMPARATORS);
+        let mut state = FpbState {
+            enabled: self.ctrl.read() & FP_CTRL_ENABLE != 0,
+            remap: self.remap.read(),
+            comparators,
+            comp: [0; MAX_COMPARATORS],
+        };
+        for (saved, comp) in state.comp[..comparators].iter_mut().zip(self.comp.iter()) {
+            *saved = comp.read();
+        }
+        state
+    }
+
+    /// Puts the FPB back in the `state` saved by [`FPB::snapshot`]
+    ///
+    /// The FPB is disabled while the comparators are written, then enabled again if it was.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn restore(&mut self, state: &FpbState) {
+        self.disable();
+        unsafe {
+            self.remap.write(state.remap);
+            for (comp, &saved) in self.comp.iter().zip(state.comparators()) {
+                comp.write(saved);
+            }
+        }
+        if state.enabled {
+            self.enable();
+        }
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+
+    /// Runs `f` with a hardware breakpoint on the instruction at `addr`, using comparator `slot`
+    ///
+    /// The FPB is enabled while `f` runs, and put back in its previous state afterwards, see
+    /// [`FPB::restore`]. Returns an error, without running `f`, if the breakpoint can't be set,
+    /// see [`FPB::set_breakpoint`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #[inline(never)]
+    /// fn under_test() {}
+    ///
+    /// let mut p = cortex_m::Peripherals::take().unwrap();
+    /// p.DCB.enable_debug_monitor();
+    /// p.FPB
+    ///     .with_breakpoint(0, under_test as usize as u32 & !1, || under_test())
+    ///     .unwrap();
+    /// ```
+    #[inline]
+    pub fn with_breakpoint<F, R>(&mut self, slot: usize, addr: u32, f: F) -> Result<R, FpbError>
+    where
+        F: FnOnce() -> R,
+    {
+        struct Restore<'a> {
+            fpb: &'a mut FPB,
+            state: FpbState,
+        }
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.fpb.restore(&self.state);
+            }
+        }
+
+        let state = self.snapshot();
+        let guard = Restore { fpb: self, state };
+        guard.fpb.set_breakpoint(slot, addr)?;
+        guard.fpb.enable();
+        crate::asm::dsb();
+        crate::asm::isb();
+        Ok(f())
+    }
+
+    /// Configures flash patch remapping to `table`, remapping each `(slot, addr)` of `entries`
+    ///
+    /// Comparator `slot` is set to remap the word at `addr` to entry `slot` of the table.
+    /// Instruction comparators (`0..`[`code_comparators`](FpbCapabilities::code_comparators))
+    /// remap instruction fetches; the literal comparators following them remap data loads.
+    /// Comparators not listed in `entries` are left as they are. The remap takes effect once the
+    /// FPB is [enabled](FPB::enable); see the
+    /// [module documentation](crate::peripheral::fpb#flash-patch) for cache maintenance.
+    ///
+    /// Nothing is written if any entry is invalid.
+    ///
+    /// # Examples
+    ///
+    /// Patching a constant in flash, with the first literal comparator:
+    ///
+    /// ```no_run
+    /// use core::ptr;
+    /// use cortex_m::peripheral::fpb::RemapTable;
+    ///
+    /// static ANSWER: u32 = 41;
+    /// static PATCH: RemapTable = RemapTable::new([42; 8]);
+    ///
+    /// let mut p = cortex_m::Peripherals::take().unwrap();
+    /// let slot = p.FPB.capabilities().code_comparators as usize;
+    /// p.FPB
+    ///     .configure_remap(&PATCH, &[(slot, &ANSWER as *const u32 as u32)])
+    ///     .unwrap();
+    /// p.FPB.enable();
+    /// p.SCB.invalidate_icache();
+    /// assert_eq!(unsafe { ptr::read_volatile(&ANSWER) }, 42);
+    /// ```
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn configure_remap(
+        &mut self,
+        table: &'static RemapTable,
+        entries: &[(usize, u32)],
+    ) -> Result<(), FpbError> {
+        if self.remap.read() & FP_REMAP_RMPSPT == 0 {
+            return Err(FpbError::RemapUnsupported);
+        }
+        if !SRAM_REGION.contains(&table.address()) {
+            return Err(FpbError::TableNotInSram);
+        }
+
+        let capabilities = self.capabilities();
+        let revision = capabilities.revision;
+        let slots = core::cmp::min(capabilities.comparators(), REMAP_TABLE_LEN);
+        for &(slot, addr) in entries {
+            if slot >= slots {
+                return Err(FpbError::InvalidSlot);
+            }
+            encode_remap(revision, addr)?;
+        }
+
+        unsafe {
+            self.remap.write(table.address());
+            for &(slot, addr) in entries {
+                // all entries were validated above
+                self.comp[slot].write(encode_remap(revision, addr).unwrap_or(0));
+            }
+        }
+        crate::asm::dsb();
+        crate::asm::isb();
+        Ok(())
+    }
+}
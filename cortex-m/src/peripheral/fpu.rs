@@ -2,8 +2,21 @@
 //!
 //! *NOTE* Available only on targets with a Floating Point Unit (FPU) extension.
 
+#[cfg(has_fpu)]
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
 use volatile_register::{RO, RW};
 
+#[cfg(has_fpu)]
+use crate::peripheral::scb::FpuAccessMode;
+use crate::peripheral::FPU;
+#[cfg(has_fpu)]
+use crate::peripheral::SCB;
+#[cfg(has_fpu)]
+use crate::register::fpscr::Fpscr;
+use bitfield::bitfield;
+
 /// Register block
 #[repr(C)]
 pub struct RegisterBlock {
@@ -17,3 +30,338 @@ pub struct RegisterBlock {
     /// Media and FP Feature
     pub mvfr: [RO<u32>; 3],
 }
+
+/// Floating-point capabilities of the FPU, decoded from the `MVFR0` to `MVFR2` registers
+///
+/// See [`FPU::features`]. All fields are `false` if no FPU is implemented.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FpuFeatures {
+    /// Single precision arithmetic is implemented (`MVFR0.SP`)
+    pub single_precision: bool,
+    /// Double precision arithmetic is implemented (`MVFR0.DP`)
+    pub double_precision: bool,
+    /// `VDIV` is implemented (`MVFR0.Divide`)
+    pub divide: bool,
+    /// `VSQRT` is implemented (`MVFR0.SquareRoot`)
+    pub square_root: bool,
+    /// The fused multiply accumulate instructions (`VFMA` and friends) are implemented
+    /// (`MVFR1.FMAC`)
+    pub fused_multiply_accumulate: bool,
+    /// Conversions between half precision and single precision are implemented (`MVFR1.FPHP`)
+    pub half_precision_conversion: bool,
+}
+
+impl FpuFeatures {
+    /// Decodes the features from the values of `MVFR0`, `MVFR1` and `MVFR2`
+    #[inline]
+    pub const fn decode(mvfr: [u32; 3]) -> Self {
+        FpuFeatures {
+            single_precision: (mvfr[0] >> 4) & 0xF != 0,
+            double_precision: (mvfr[0] >> 8) & 0xF != 0,
+            divide: (mvfr[0] >> 16) & 0xF != 0,
+            square_root: (mvfr[0] >> 20) & 0xF != 0,
+            fused_multiply_accumulate: (mvfr[1] >> 28) & 0xF != 0,
+            half_precision_conversion: (mvfr[1] >> 24) & 0xF != 0,
+        }
+    }
+}
+
+bitfield! {
+    /// Floating Point Context Control Register, see [`FPU::context_control`].
+    ///
+    /// The fields marked Armv8-M read as zero on Armv7-M.
+    #[repr(C)]
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct Fpccr(u32);
+    impl Debug;
+    /// Automatic state preservation enable: exception entry saves the FP context
+    pub aspen, _: 31;
+    /// Lazy state preservation enable: the saved FP context is only written when needed
+    pub lspen, _: 30;
+    /// Lazy state preservation enable for Secure state (Armv8-M)
+    pub lspens, _: 29;
+    /// Clear the FP registers on exception return (Armv8-M)
+    pub clronret, _: 28;
+    /// Only Secure state can change `CLRONRET` (Armv8-M)
+    pub clronrets, _: 27;
+    /// The FP context belongs to Secure state, which has to clear it on a switch to Non-secure
+    /// state (Armv8-M)
+    pub ts, _: 26;
+    /// A UsageFault could be pended when the lazy state preservation is done (Armv8-M)
+    pub ufrdy, _: 10;
+    /// The lazy state preservation hit a stack limit violation (Armv8-M)
+    pub splimviol, _: 9;
+    /// A DebugMonitor exception could be pended when the lazy state preservation is done
+    pub monrdy, _: 8;
+    /// A SecureFault could be pended when the lazy state preservation is done (Armv8-M)
+    pub sfrdy, _: 7;
+    /// A BusFault could be pended when the lazy state preservation is done
+    pub bfrdy, _: 6;
+    /// A MemManage fault could be pended when the lazy state preservation is done
+    pub mmrdy, _: 5;
+    /// A HardFault could be pended when the lazy state preservation is done
+    pub hfrdy, _: 4;
+    /// The FP context was allocated in Thread mode
+    pub thread, _: 3;
+    /// The FP context was allocated in Secure state (Armv8-M)
+    pub s, _: 2;
+    /// The FP context was allocated by unprivileged code
+    pub user, _: 1;
+    /// Lazy state preservation is active: stack space for the FP context was reserved but not
+    /// written yet
+    pub lspact, _: 0;
+}
+
+const FPCCR_ASPEN: u32 = 1 << 31;
+const FPCCR_LSPEN: u32 = 1 << 30;
+const FPCCR_LSPACT: u32 = 1 << 0;
+
+const FPCAR_ADDRESS: u32 = !0b111;
+
+/// The FPDSCR bits: AHP, DN, FZ and RMode
+#[cfg(has_fpu)]
+const FPDSCR_MASK: u32 = 0b1_1111 << 22;
+
+impl FPU {
+    /// Returns `true` if an FPU is implemented
+    ///
+    /// `MVFR0` reads as zero on processors without an FPU. Check this before enabling the FPU on
+    /// families that come with and without one. Without the `has_fpu` configuration, i.e. on
+    /// soft-float targets, use [`CPUID::features`](crate::peripheral::CPUID::features) instead.
+    #[inline]
+    pub fn is_present() -> bool {
+        // NOTE(unsafe) atomic read with no side effects
+        let mvfr0 = unsafe { (*Self::PTR).mvfr[0].read() };
+        FpuFeatures::decode([mvfr0, 0, 0]).single_precision
+    }
+
+    /// Reads the floating-point capabilities from the `MVFR` registers
+    #[inline]
+    pub fn features(&self) -> FpuFeatures {
+        FpuFeatures::decode([
+            self.mvfr[0].read(),
+            self.mvfr[1].read(),
+            self.mvfr[2].read(),
+        ])
+    }
+
+    /// Reads the Floating Point Context Control Register
+    #[inline]
+    pub fn context_control(&self) -> Fpccr {
+        Fpccr(self.fpccr.read())
+    }
+
+    /// Enables or disables lazy state preservation (`FPCCR.LSPEN`)
+    ///
+    /// With lazy preservation, exception entry only reserves stack space for the FP context, and
+    /// the registers are written there when the handler first executes an FP instruction.
+    /// Disabling it makes every exception entry with an active FP context save the registers,
+    /// which costs latency but makes it constant.
+    ///
+    /// *NOTE* Only change it while no lazy state preservation is pending, i.e.
+    /// [`is_lazy_state_pending`](FPU::is_lazy_state_pending) returns `false`, for example from
+    /// Thread mode before enabling interrupts. Otherwise the preservation already reserved on the
+    /// stack of a preempted context may be carried out differently than it was set up, and the FP
+    /// state of that context is lost.
+    #[inline]
+    pub fn set_lazy_stacking(&mut self, enabled: bool) {
+        unsafe {
+            self.fpccr.modify(|r| {
+                if enabled {
+                    r | FPCCR_LSPEN
+                } else {
+                    r & !FPCCR_LSPEN
+                }
+            })
+        }
+    }
+
+    /// Enables or disables automatic state preservation (`FPCCR.ASPEN`)
+    ///
+    /// When enabled (the reset value), executing an FP instruction sets `CONTROL.FPCA`, and
+    /// exception entry saves the FP context of the preempted code.
+    ///
+    /// *NOTE* While it is disabled, exception entry doesn't save the FP registers, so every
+    /// handler that uses the FPU must save and restore them itself, or FP values of the preempted
+    /// code get corrupted. Only disable it while no FP context is active (`CONTROL.FPCA` is
+    /// clear), otherwise the active context keeps using extended exception frames that are no
+    /// longer managed consistently.
+    #[inline]
+    pub fn set_automatic_state_preservation(&mut self, enabled: bool) {
+        unsafe {
+            self.fpccr.modify(|r| {
+                if enabled {
+                    r | FPCCR_ASPEN
+                } else {
+                    r & !FPCCR_ASPEN
+                }
+            })
+        }
+    }
+
+    /// Checks whether a lazy state preservation is pending (`FPCCR.LSPACT`)
+    ///
+    /// When `true`, stack space for the FP context of a preempted context has been reserved at
+    /// the address in `FPCAR` but the registers haven't been written there yet. Context switch
+    /// code must trigger the preservation, e.g. by executing an FP instruction, before it saves
+    /// or switches away from that stack.
+    #[inline]
+    pub fn is_lazy_state_pending(&self) -> bool {
+        self.fpccr.read() & FPCCR_LSPACT != 0
+    }
+
+    /// Returns the address of the stack space reserved for the pending lazy FP state
+    /// preservation, or `None` if none is pending
+    ///
+    /// `FPCAR` keeps the address of the last reserved space even after the preservation was
+    /// carried out, so the address is only returned while `FPCCR.LSPACT` is set. The space
+    /// doesn't hold the FP registers yet; see
+    /// [`force_lazy_state_preservation`](FPU::force_lazy_state_preservation) to write them there.
+    #[inline]
+    pub fn fp_context_address(&self) -> Option<NonNull<u32>> {
+        if !self.is_lazy_state_pending() {
+            return None;
+        }
+        NonNull::new((self.fpcar.read() & FPCAR_ADDRESS) as *mut u32)
+    }
+
+    /// Reads the Floating Point Default Status Control Register (`FPDSCR`)
+    ///
+    /// Only the `AHP`, `DN`, `FZ` and `RMode` bits are implemented, all other bits read as zero.
+    #[cfg(has_fpu)]
+    #[inline]
+    pub fn default_status_control(&self) -> Fpscr {
+        Fpscr::from_bits(self.fpdscr.read() & FPDSCR_MASK)
+    }
+
+    /// Sets the FPSCR control bits exception handlers start with (`FPDSCR`)
+    ///
+    /// Only the `AHP`, `DN`, `FZ` and `RMode` bits of `fpscr` are written, the flags are ignored.
+    ///
+    /// *NOTE* FPSCR is part of the FP context: with automatic state preservation enabled,
+    /// exception entry loads FPSCR from FPDSCR for the handler, and exception return restores the
+    /// FPSCR of the interrupted code. A flush-to-zero or rounding mode set with
+    /// [`fpscr::write`](crate::register::fpscr::write) in Thread mode therefore does *not* apply
+    /// to interrupt handlers, which compute with the FPDSCR settings (round-to-nearest and IEEE
+    /// behavior after reset) instead. Set the same mode here to make it consistent across
+    /// exception boundaries.
+    #[cfg(has_fpu)]
+    #[inline]
+    pub fn set_default_status_control(&mut self, fpscr: Fpscr) {
+        unsafe { self.fpdscr.write(fpscr.bits() & FPDSCR_MASK) }
+    }
+
+    /// Carries out a pending lazy FP state preservation
+    ///
+    /// With lazy stacking, exception entry only reserves stack space for the FP registers of the
+    /// preempted context and records its address in `FPCAR`; the registers are written there
+    /// when the next FP instruction executes. Reading `FPCAR` alone is therefore not enough for
+    /// a context switcher: the reserved space holds stale data, and if the switcher moves to
+    /// another stack first, the registers get written to the old stack later, or the next
+    /// context's FP values end up in them. Call this before saving or switching away from a
+    /// stack for which [`fp_context_address`](FPU::fp_context_address) returned an address.
+    ///
+    /// The sequence is a dummy FP register read, `vmov rN, s0`, which triggers the preservation
+    /// without changing any FP register, followed by a `dsb` so the stores of the preserved state
+    /// complete before the stack is used. If no preservation is pending it only costs the two
+    /// instructions.
+    ///
+    /// *NOTE* The FPU must be enabled in `CPACR`, otherwise the `vmov` raises a UsageFault.
+    #[cfg(has_fpu)]
+    #[inline]
+    pub fn force_lazy_state_preservation(&self) {
+        unsafe {
+            core::arch::asm!(
+                "vmov {}, s0",
+                "dsb",
+                out(reg) _,
+                options(nostack, preserves_flags),
+            )
+        };
+    }
+}
+
+/// Returns `true` if the FPU is accessible, at least to privileged code, according to `CPACR`
+#[cfg(has_fpu)]
+#[inline]
+pub fn is_enabled(scb: &SCB) -> bool {
+    let _ = scb;
+    SCB::fpu_access_mode() != FpuAccessMode::Disabled
+}
+
+/// Keeps the FPU enabled while it is alive, and restores the previous `CPACR` FPU access mode
+/// when dropped
+///
+/// Use it to keep the FPU disabled everywhere except in the code paths that need it, so a stray
+/// floating-point instruction raises a UsageFault (`NOCP`) instead of silently powering the FPU.
+///
+/// ```no_run
+/// use cortex_m::peripheral::fpu::FpuGuard;
+///
+/// # fn filter() {}
+/// let mut scb = cortex_m::Peripherals::take().unwrap().SCB;
+/// scb.disable_fpu();
+/// {
+///     let _fpu = FpuGuard::enable(&mut scb);
+///     filter();
+/// }
+/// // the FPU is disabled again
+/// ```
+///
+/// *IMPORTANT* The compiler doesn't know about the guard and may move floating-point
+/// instructions out of the guarded scope. Keep the floating-point code in functions marked
+/// `#[inline(never)]`, see [`SCB::set_fpu_access_mode`].
+///
+/// *NOTE* Using the FPU inside the guard sets `CONTROL.FPCA`, and with automatic state
+/// preservation (`FPCCR.ASPEN`, see [`FPU::set_automatic_state_preservation`]) exceptions taken
+/// inside the guard lazily stack the FP context of the guarded code. Disabling the FPU again
+/// doesn't undo that: if such a lazy preservation is still pending, or another context has FP
+/// state lazily stacked, the preservation is triggered by the next FP instruction and faults if
+/// the FPU is disabled by then. Only use the guard where no other context keeps FP state, or
+/// manage `ASPEN`/`LSPEN` accordingly.
+///
+/// The guard is neither `Send` nor `Sync`, as `CPACR` applies to the code running on this core.
+#[cfg(has_fpu)]
+pub struct FpuGuard<'a> {
+    scb: &'a mut SCB,
+    previous: FpuAccessMode,
+    _not_send: PhantomData<*const ()>,
+}
+
+#[cfg(has_fpu)]
+impl<'a> FpuGuard<'a> {
+    /// Grants privileged and unprivileged access to the FPU (`CP10` and `CP11`)
+    ///
+    /// The `CPACR` write is followed by a `DSB` and an `ISB`, so FP instructions after this call
+    /// see the FPU enabled.
+    #[inline]
+    pub fn enable(scb: &'a mut SCB) -> Self {
+        let previous = SCB::fpu_access_mode();
+        scb.set_fpu_access_mode(FpuAccessMode::Enabled);
+        crate::asm::dsb();
+        crate::asm::isb();
+        FpuGuard {
+            scb,
+            previous,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// The FPU access mode that is restored on drop
+    #[inline]
+    pub fn previous(&self) -> FpuAccessMode {
+        self.previous
+    }
+}
+
+#[cfg(has_fpu)]
+impl Drop for FpuGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        crate::asm::dsb();
+        self.scb.set_fpu_access_mode(self.previous);
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+}
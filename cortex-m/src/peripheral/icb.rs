@@ -4,6 +4,9 @@
 use volatile_register::RO;
 use volatile_register::RW;
 
+#[cfg(any(armv7m, armv8m, native))]
+use crate::peripheral::ICB;
+
 /// Register block
 #[repr(C)]
 pub struct RegisterBlock {
@@ -30,3 +33,16 @@ pub struct RegisterBlock {
     #[cfg(armv8m)]
     pub cppwr: RW<u32>,
 }
+
+#[cfg(any(armv7m, armv8m, native))]
+impl ICB {
+    /// Returns the number of interrupt lines the NVIC supports, from the `INTLINESNUM` field of
+    /// the ICTR.
+    ///
+    /// The value is a multiple of 32; the device may implement fewer interrupts. The ICTR is not
+    /// implemented on Armv6-M.
+    #[inline]
+    pub fn interrupt_lines(&self) -> u16 {
+        ((self.ictr.read() & 0xF) as u16 + 1) * 32
+    }
+}
@@ -7,7 +7,8 @@ use core::ptr;
 
 use volatile_register::{RO, RW, WO};
 
-use crate::peripheral::ITM;
+use crate::peripheral::dwt::{DwtError, SyncTap};
+use crate::peripheral::{DCB, DWT, ITM};
 use bitfield::bitfield;
 
 #[cfg(feature = "serde")]
@@ -90,6 +91,16 @@ impl Stim {
         // disabled (bit 1).
         unsafe { ptr::read_volatile(self.register.get()) & 0b11 != 0 }
     }
+
+    /// Returns `true` if the stimulus port is disabled, in which case writes to it are discarded
+    ///
+    /// A port is disabled when the ITM or the port's `TER` bit is disabled, or when unprivileged
+    /// code writes to a port protected by `TPR`.
+    #[cfg(armv8m)]
+    #[inline]
+    pub fn is_disabled(&self) -> bool {
+        unsafe { ptr::read_volatile(self.register.get()) & 0b10 != 0 }
+    }
 }
 
 /// The possible local timestamp options.
@@ -111,6 +122,71 @@ pub enum LocalTimestampOptions {
     EnabledDiv64,
 }
 
+impl LocalTimestampOptions {
+    /// The prescaler dividing the timestamp clock, `None` if local timestamps are disabled.
+    #[inline]
+    pub const fn divider(self) -> Option<u32> {
+        match self {
+            Self::Disabled => None,
+            Self::Enabled => Some(1),
+            Self::EnabledDiv4 => Some(4),
+            Self::EnabledDiv16 => Some(16),
+            Self::EnabledDiv64 => Some(64),
+        }
+    }
+
+    /// Picks the largest prescaler whose timestamp tick is no longer than `resolution_ns`, for a
+    /// timestamp clock of `clk_hz`. If even the undivided clock is too slow, the clock isn't
+    /// prescaled.
+    #[inline]
+    pub const fn for_resolution(clk_hz: u32, resolution_ns: u32) -> Self {
+        // the tick of divider `d` is `d * 1e9 / clk_hz` ns
+        let max_divider = resolution_ns as u64 * clk_hz as u64 / 1_000_000_000;
+        if max_divider >= 64 {
+            Self::EnabledDiv64
+        } else if max_divider >= 16 {
+            Self::EnabledDiv16
+        } else if max_divider >= 4 {
+            Self::EnabledDiv4
+        } else {
+            Self::Enabled
+        }
+    }
+
+    /// The local timestamp prescaling that the `TCR` value `tcr` applies.
+    ///
+    /// `TSPrescale` only divides the asynchronous TPIU clock (`SWOENA` set); timestamps counting
+    /// the processor clock are never prescaled. `TSENA` and `TSPrescale` may also be RAZ/WI on
+    /// implementations without local timestamps or without a prescaler.
+    pub(crate) const fn applied(tcr: u32) -> Self {
+        const TSENA: u32 = 1 << 1;
+        const SWOENA: u32 = 1 << 4;
+
+        if tcr & TSENA == 0 {
+            Self::Disabled
+        } else if tcr & SWOENA == 0 {
+            Self::Enabled
+        } else {
+            match (tcr >> 8) & 0b11 {
+                0b00 => Self::Enabled,
+                0b01 => Self::EnabledDiv4,
+                0b10 => Self::EnabledDiv16,
+                _ => Self::EnabledDiv64,
+            }
+        }
+    }
+
+    /// The `TCR.TSPrescale` encoding.
+    const fn tsprescale(self) -> u8 {
+        match self {
+            Self::Disabled | Self::Enabled => 0b00,
+            Self::EnabledDiv4 => 0b01,
+            Self::EnabledDiv16 => 0b10,
+            Self::EnabledDiv64 => 0b11,
+        }
+    }
+}
+
 #[cfg(feature = "std")]
 impl core::convert::TryFrom<u8> for LocalTimestampOptions {
     type Error = ();
@@ -156,6 +232,29 @@ pub enum TimestampClkSrc {
     AsyncTPIU,
 }
 
+/// The local timestamp configuration applied by [`ITM::configure_timestamps`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct TimestampConfig {
+    /// The prescaling that took effect, read back from `TCR`. [`Disabled`] if the ITM doesn't
+    /// implement local timestamps.
+    ///
+    /// [`Disabled`]: LocalTimestampOptions::Disabled
+    pub prescaler: LocalTimestampOptions,
+    /// The rate at which the timestamp counter increments, in Hz, `0` if timestamps are disabled.
+    pub tick_hz: u32,
+}
+
+impl TimestampConfig {
+    /// The effective timestamp resolution, the duration of one tick, in nanoseconds, rounded up.
+    #[inline]
+    pub const fn resolution_ns(&self) -> u32 {
+        if self.tick_hz == 0 {
+            return 0;
+        }
+        ((1_000_000_000 + self.tick_hz as u64 - 1) / self.tick_hz as u64) as u32
+    }
+}
+
 /// Available settings for the ITM peripheral.
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub struct ITMSettings {
@@ -175,6 +274,17 @@ pub struct ITMSettings {
     pub timestamp_clk_src: TimestampClkSrc,
 }
 
+/// Cycles left before the cycle counter bit tapped for synchronization toggles, after
+/// [`ITM::emit_sync`] moves the counter.
+const SYNC_MARGIN: u32 = 16;
+
+/// Returns the cycle count a few cycles before the bit selected by `tap` next goes from 0 to 1,
+/// keeping the bits above it. `tap` must not be [`SyncTap::Disabled`].
+pub(crate) const fn cycle_count_before_sync(cycle_count: u32, tap: SyncTap) -> u32 {
+    let bit = 1 << (22 + 2 * tap as u32);
+    (cycle_count & !(2 * bit - 1)) | (bit - SYNC_MARGIN)
+}
+
 impl ITM {
     /// Removes the software lock on the ITM.
     #[inline]
@@ -183,20 +293,123 @@ impl ITM {
         unsafe { self.lar.write(0xC5AC_CE55) }
     }
 
+    /// Whether to periodically generate synchronization packets.
+    ///
+    /// The period is selected with [`DWT::set_sync_tap`](crate::peripheral::DWT::set_sync_tap).
+    #[inline]
+    pub fn enable_sync_packets(&mut self, bit: bool) {
+        unsafe {
+            self.tcr.modify(|mut r| {
+                r.set_synena(bit);
+                r
+            });
+        }
+    }
+
+    /// Makes the DWT request a synchronization packet right away.
+    ///
+    /// A decoder attaching to a trace stream can only start parsing at a synchronization packet,
+    /// and the bytes emitted right after trace is (re)enabled are often garbage to it. There is no
+    /// register to request one directly: synchronization packets are requested each time the
+    /// cycle counter bit selected with [`DWT::set_sync_tap`] toggles. This enables
+    /// synchronization packets and the cycle counter, selects the 2^24 cycle tap if none is
+    /// selected, and moves the cycle counter to a few cycles before the tapped bit is set. Trace
+    /// is enabled with `dcb` first, see [`DCB::enable_trace`].
+    ///
+    /// **NOTE** this changes the cycle count by up to the tap period. Don't call it while
+    /// measuring time with [`DWT::cycle_count`].
+    ///
+    /// Returns an error if the DWT has no cycle counter.
+    ///
+    /// # Synchronization rate
+    ///
+    /// With periodic synchronization enabled (see [`ITM::enable_sync_packets`]) the decoder
+    /// recovers within one tap period, e.g. 2^24 cycles, about 100 ms at 168 MHz. Each
+    /// synchronization packet takes 6 bytes of trace bandwidth, which is negligible at any tap
+    /// rate, but a faster tap also shortens the wait for the first one. Asynchronous SWO
+    /// decoders usually also resynchronize on an idle line, so a synchronization packet matters
+    /// most for a continuous stream or a decoder attaching mid-stream.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn emit_sync(&mut self, dcb: &mut DCB, dwt: &mut DWT) -> Result<(), DwtError> {
+        dcb.enable_trace();
+        if !dwt.has_cycle_counter() {
+            return Err(DwtError::UnsupportedOnArchitecture);
+        }
+
+        let tap = match dwt.sync_tap() {
+            SyncTap::Disabled => {
+                dwt.set_sync_tap(SyncTap::Cycles2Pow24);
+                SyncTap::Cycles2Pow24
+            }
+            tap => tap,
+        };
+        self.enable_sync_packets(true);
+        dwt.enable_cycle_counter(dcb);
+        dwt.set_cycle_count(cycle_count_before_sync(DWT::cycle_count(), tap));
+        Ok(())
+    }
+
+    /// Returns `true` while the ITM is processing packets.
+    #[inline]
+    pub fn is_busy(&self) -> bool {
+        self.tcr.read().busy()
+    }
+
+    /// Enables local timestamps with the coarsest prescaler that still gives `resolution_ns`
+    /// nanosecond resolution, and returns the configuration that took effect.
+    ///
+    /// `clk_hz` is the frequency of the timestamp clock: the processor clock, or the TPIU
+    /// asynchronous clock if [`TimestampClkSrc::AsyncTPIU`] was selected with
+    /// [`configure`](ITM::configure). The other `TCR` settings are left unchanged. Trace is
+    /// enabled with `dcb` first, see [`DCB::enable_trace`].
+    ///
+    /// The prescaler only applies to the asynchronous TPIU clock, and may not be implemented, so
+    /// `TCR` is read back after the write: the returned [`TimestampConfig`] describes the
+    /// prescaling that actually took effect, e.g. no prescaling with the processor clock,
+    /// whatever `resolution_ns` asked for.
+    ///
+    /// Local timestamp packets carry tick counts, not time: the host decoder must be told the
+    /// timestamp clock and the prescaler, i.e. [`TimestampConfig::tick_hz`], to turn them into
+    /// durations.
+    #[inline]
+    pub fn configure_timestamps(
+        &mut self,
+        dcb: &mut DCB,
+        clk_hz: u32,
+        resolution_ns: u32,
+    ) -> TimestampConfig {
+        dcb.enable_trace();
+        let prescaler = LocalTimestampOptions::for_resolution(clk_hz, resolution_ns);
+        unsafe {
+            self.tcr.modify(|mut r| {
+                r.set_tsena(true);
+                r.set_tsprescale(prescaler.tsprescale());
+                r
+            });
+        }
+        let prescaler = LocalTimestampOptions::applied(self.tcr.read().0);
+        TimestampConfig {
+            prescaler,
+            tick_hz: match prescaler.divider() {
+                Some(divider) => clk_hz / divider,
+                None => 0,
+            },
+        }
+    }
+
     /// Configures the ITM with the passed [ITMSettings].
+    ///
+    /// Trace is enabled with `dcb` first, see [`DCB::enable_trace`]: the ITM registers can't be
+    /// written while global trace is disabled.
     #[inline]
-    pub fn configure(&mut self, settings: ITMSettings) {
+    pub fn configure(&mut self, dcb: &mut DCB, settings: ITMSettings) {
+        dcb.enable_trace();
         unsafe {
             self.tcr.modify(|mut r| {
                 r.set_itmena(settings.enable);
                 r.set_tsena(settings.local_timestamps != LocalTimestampOptions::Disabled);
                 r.set_txena(settings.forward_dwt);
-                r.set_tsprescale(match settings.local_timestamps {
-                    LocalTimestampOptions::Disabled | LocalTimestampOptions::Enabled => 0b00,
-                    LocalTimestampOptions::EnabledDiv4 => 0b10,
-                    LocalTimestampOptions::EnabledDiv16 => 0b10,
-                    LocalTimestampOptions::EnabledDiv64 => 0b11,
-                });
+                r.set_tsprescale(settings.local_timestamps.tsprescale());
                 r.set_gtsfreq(match settings.global_timestamps {
                     GlobalTimestampOptions::Disabled => 0b00,
                     GlobalTimestampOptions::Every128Cycles => 0b01,
@@ -29,8 +29,7 @@
 //! # use cortex_m::peripheral::{DWT, Peripherals};
 //! {
 //!     let mut peripherals = Peripherals::take().unwrap();
-//!     peripherals.DCB.enable_trace();
-//!     peripherals.DWT.enable_cycle_counter();
+//!     peripherals.DWT.enable_cycle_counter(&mut peripherals.DCB);
 //! } // all the peripheral singletons are destroyed here
 //!
 //! // but this method can be called without a DWT instance
@@ -45,8 +44,7 @@
 //! # use cortex_m::peripheral::{DWT, Peripherals};
 //! {
 //!     let mut peripherals = Peripherals::take().unwrap();
-//!     peripherals.DCB.enable_trace();
-//!     peripherals.DWT.enable_cycle_counter();
+//!     peripherals.DWT.enable_cycle_counter(&mut peripherals.DCB);
 //! } // all the peripheral singletons are destroyed here
 //!
 //! // actually safe because this is an atomic read with no side effects
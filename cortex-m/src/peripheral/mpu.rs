@@ -1,7 +1,51 @@
 //! Memory Protection Unit
+//!
+//! # Cost of region updates
+//!
+//! Region updates often sit on a context-switch path, so their cost is given here in register
+//! accesses and barriers, and in cycles for a Cortex-M3, M4 or M33 running optimized code. On
+//! these cores the MPU registers are on the Private Peripheral Bus, which has no wait states: a
+//! load takes 2 cycles, a store 1 or 2, `DMB` and `DSB` 1 cycle once earlier accesses have
+//! completed, and `ISB` 1 cycle plus a pipeline refill of up to 3 cycles. The first barrier of
+//! each update also waits for stores still in the write buffer; that depends on the memory the
+//! caller wrote to last and is not included below.
+//!
+//! - [`MPU::set_region_enabled`]: a store to `RNR`, a load and a store of `RASR` or `RLAR`, then
+//!   `DMB`, `DSB` and `ISB`. About 12 cycles.
+//! - [`MPU::update_regions`]: the same three barriers once, plus the three accesses, about 5
+//!   cycles, per change.
+//! - `MPU::reprogram`, for `N` regions: `2 + 3 * N` MPU register stores, one `DMB`, three `DSB`s
+//!   and two `ISB`s, all with interrupts masked. The masked section is about 25 cycles plus up to
+//!   20 per region, including the load and encoding of each region configuration.
+//!
+//! # Atomic reprogramming
+//!
+//! `MPU::reprogram` masks interrupts with `PRIMASK`, disables the MPU, writes the regions and
+//! enables the MPU again. No configurable-priority exception runs in between, so none of them
+//! observes the MPU disabled or a partially written configuration.
+//!
+//! NMI and HardFault can't be masked by `PRIMASK`. One that arrives while the MPU is disabled runs
+//! under the default memory map, without any protection, whatever `HFNMIENA` says: that bit only
+//! applies while the MPU is enabled. One that arrives before the disable or after the enable runs
+//! under the complete old or new configuration, and is subject to it only if `HFNMIENA` is set
+//! in that configuration. A fault caused by the update itself escalates to HardFault, because
+//! `PRIMASK` is set.
+
+#[cfg(not(armv8m))]
+use core::ops::RangeInclusive;
+
+#[cfg(armv8m)]
+use core::{marker::PhantomData, ops};
 
 use volatile_register::{RO, RW};
 
+#[cfg(not(armv6m))]
+use crate::peripheral::scb::Cfsr;
+use crate::peripheral::MPU;
+#[cfg(not(armv6m))]
+use crate::peripheral::SCB;
+use bitfield::bitfield;
+
 /// Register block for ARMv7-M
 #[cfg(not(armv8m))]
 #[repr(C)]
@@ -63,3 +107,2170 @@ pub struct RegisterBlock {
     /// Memory Attribute Indirection register 0 and 1
     pub mair: [RW<u32>; 2],
 }
+
+bitfield! {
+    /// MPU Type Register.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Type(u32);
+    impl Debug;
+    /// Separate instruction and data regions. Always `false` on M-profile, which has a unified
+    /// MPU.
+    pub separate, _: 0;
+    /// Number of (unified or data) regions
+    pub u8, dregion, _: 15, 8;
+    /// Number of instruction regions. Always `0` on M-profile.
+    pub u8, iregion, _: 23, 16;
+}
+
+impl MPU {
+    /// Reads the MPU Type register.
+    #[inline]
+    pub fn mpu_type(&self) -> Type {
+        (**self).mpu_type()
+    }
+
+    /// Returns the number of regions the MPU implements, `0` if there is no MPU.
+    #[inline]
+    pub fn region_count(&self) -> u8 {
+        (**self).region_count()
+    }
+
+    /// Returns `true` if an MPU is implemented.
+    #[inline]
+    pub fn is_present(&self) -> bool {
+        (**self).is_present()
+    }
+
+    /// Enables the MPU.
+    ///
+    /// Returns [`MpuError::NotPresent`] if there is no MPU, and [`MpuError::NoEnabledRegion`] if no
+    /// region is enabled and `options.privileged_default_map` is `false`. Without any region and
+    /// without the default memory map nothing is accessible, so the very next instruction fetch
+    /// would fault.
+    ///
+    /// The MPU configuration is in effect once this returns.
+    ///
+    /// *NOTE* With `options.enable_during_hardfault_nmi` set, the HardFault and NMI handlers (and
+    /// any code running with FAULTMASK set) are also subject to the MPU. A region configuration
+    /// that faults them escalates to a lockup.
+    #[inline]
+    pub fn enable(&mut self, options: MpuEnableOptions) -> Result<(), MpuError> {
+        (**self).enable(options)
+    }
+
+    /// Disables the MPU. The default memory map applies to all accesses afterwards.
+    #[inline]
+    pub fn disable(&mut self) {
+        (**self).disable()
+    }
+
+    /// Returns `true` if the MPU is enabled.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        (**self).is_enabled()
+    }
+
+    /// Runs `f` with the MPU disabled, then restores the previous `MPU_CTRL` settings.
+    ///
+    /// Changing regions one at a time while the MPU is enabled can fault half-way through, e.g.
+    /// when the code or stack in use is covered by a region being changed. This disables the MPU
+    /// around `f` with the required barriers (`DMB`, disable, reconfigure, `DSB`, enable, `ISB`),
+    /// so the new configuration comes into effect as a whole once this returns.
+    ///
+    /// While `f` runs the default memory map applies, so `f` itself can't fault on the MPU. But
+    /// the code after the MPU is re-enabled, including the rest of the caller, must only access
+    /// memory that is still accessible under the new configuration. Run this in a critical section
+    /// so that interrupt handlers don't run unprotected, or against a partially written
+    /// configuration.
+    ///
+    /// # Example
+    ///
+    /// ``` no_run
+    /// # use cortex_m::peripheral::{mpu::RegionConfig, MPU};
+    /// # fn example(mpu: &mut MPU, regions: &[RegionConfig]) {
+    /// cortex_m::interrupt::free(|| {
+    ///     mpu.reconfigure(|mpu| {
+    ///         for (index, region) in regions.iter().enumerate() {
+    ///             mpu.configure_region(index as u8, region).unwrap();
+    ///         }
+    ///     })
+    /// });
+    /// # }
+    /// ```
+    #[inline]
+    pub fn reconfigure<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut MPU) -> R,
+    {
+        // NOTE(unsafe) `f` gets `self`, the register block is only used around it
+        let block = unsafe { &*Self::PTR };
+        block.reconfigure(|| f(self))
+    }
+
+    /// Enables or disables region `index`, leaving its other settings untouched.
+    ///
+    /// This only selects the region and flips its enable bit (`RASR.ENABLE` on Armv7-M, `RLAR.EN`
+    /// on Armv8-M), which is cheaper than reprogramming it: three MPU register accesses plus the
+    /// `DMB`, `DSB` and `ISB` that make the change take effect before this returns, about 12
+    /// cycles in total. See [cost of region updates].
+    ///
+    /// [cost of region updates]: crate::peripheral::mpu#cost-of-region-updates
+    #[inline]
+    pub fn set_region_enabled(&mut self, index: u8, enabled: bool) -> Result<(), MpuError> {
+        (**self).set_region_enabled(index, enabled)
+    }
+
+    /// Enables or disables several regions at once, given as `(index, enabled)` pairs.
+    ///
+    /// All indices are checked before anything is written. The changes share a single set of
+    /// barriers, so each additional region costs only three MPU register accesses, about 5
+    /// cycles (see [cost of region updates]). The MPU is not disabled around the update: neither
+    /// PMSAv7 nor PMSAv8 requires it, and it would briefly lift all protection. Interrupt
+    /// handlers that run in the middle of the update see a partially updated map, so call this
+    /// in a critical section if they depend on the regions being changed.
+    ///
+    /// [cost of region updates]: crate::peripheral::mpu#cost-of-region-updates
+    #[inline]
+    pub fn update_regions(&mut self, changes: &[(u8, bool)]) -> Result<(), MpuError> {
+        (**self).update_regions(changes)
+    }
+
+    /// Returns an iterator over all implemented regions, including the disabled ones.
+    ///
+    /// This reprograms `RNR` to select each region in turn.
+    #[inline]
+    pub fn regions(&mut self) -> Regions<'_> {
+        (**self).regions()
+    }
+}
+
+// The implementations shared by `MPU` and, on Armv8-M, `MpuNs`, which differ only in the address
+// of the register block.
+impl RegisterBlock {
+    fn mpu_type(&self) -> Type {
+        Type(self._type.read())
+    }
+
+    fn region_count(&self) -> u8 {
+        self.mpu_type().dregion()
+    }
+
+    fn is_present(&self) -> bool {
+        self.region_count() != 0
+    }
+
+    fn enable(&self, options: MpuEnableOptions) -> Result<(), MpuError> {
+        let count = self.region_count();
+        if count == 0 {
+            return Err(MpuError::NotPresent);
+        }
+        if !options.privileged_default_map && !(0..count).any(|i| self.is_region_enabled(i)) {
+            return Err(MpuError::NoEnabledRegion);
+        }
+
+        // complete all outstanding memory accesses under the old configuration
+        crate::asm::dmb();
+        unsafe { self.ctrl.write(options.ctrl()) }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+
+    fn disable(&self) {
+        crate::asm::dmb();
+        unsafe { self.ctrl.write(0) }
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.ctrl.read() & MPU_CTRL_ENABLE != 0
+    }
+
+    /// Shared implementation of the `reconfigure` methods of both MPU models.
+    fn reconfigure<R>(&self, f: impl FnOnce() -> R) -> R {
+        let ctrl = self.ctrl.read();
+
+        // complete all outstanding memory accesses under the old configuration
+        crate::asm::dmb();
+        unsafe { self.ctrl.write(ctrl & !MPU_CTRL_ENABLE) }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        let r = f();
+
+        crate::asm::dsb();
+        unsafe { self.ctrl.write(ctrl) }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        r
+    }
+
+    /// Selects region `index` and returns whether it is enabled.
+    fn is_region_enabled(&self, index: u8) -> bool {
+        // RASR.ENABLE and RLAR.EN are both bit 0
+        self.read_region(index).1 & 1 != 0
+    }
+
+    fn set_region_enabled(&self, index: u8, enabled: bool) -> Result<(), MpuError> {
+        self.check_region(index)?;
+
+        crate::asm::dmb();
+        self.write_region_enabled(index, enabled);
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+
+    fn update_regions(&self, changes: &[(u8, bool)]) -> Result<(), MpuError> {
+        for &(index, _) in changes {
+            self.check_region(index)?;
+        }
+
+        crate::asm::dmb();
+        for &(index, enabled) in changes {
+            self.write_region_enabled(index, enabled);
+        }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+
+    /// Selects region `index` and sets its enable bit to `enabled`, without any barriers.
+    fn write_region_enabled(&self, index: u8, enabled: bool) {
+        // RASR.ENABLE and RLAR.EN are both bit 0
+        unsafe {
+            self.rnr.write(index as u32);
+            #[cfg(not(armv8m))]
+            self.rasr.modify(|r| (r & !1) | enabled as u32);
+            #[cfg(armv8m)]
+            self.rlar.modify(|r| (r & !1) | enabled as u32);
+        }
+    }
+
+    /// Shared implementation of the `reprogram` methods of both MPU models.
+    fn reprogram_regions<C>(
+        &self,
+        regions: &[(u8, C)],
+        options: MpuEnableOptions,
+        validate: fn(&C) -> Result<(), MpuError>,
+        encode: fn(&C) -> (u32, u32),
+    ) -> Result<(), MpuError> {
+        for (index, config) in regions {
+            self.check_region(*index)?;
+            validate(config)?;
+        }
+        if !options.privileged_default_map
+            && regions.is_empty()
+            && !(0..self.region_count()).any(|i| self.is_region_enabled(i))
+        {
+            return Err(MpuError::NoEnabledRegion);
+        }
+
+        crate::interrupt::free(|| {
+            // complete all outstanding memory accesses under the old configuration
+            crate::asm::dmb();
+            unsafe { self.ctrl.write(0) }
+            crate::asm::dsb();
+            crate::asm::isb();
+
+            for (index, config) in regions {
+                let (rbar, attributes) = encode(config);
+                unsafe {
+                    self.rnr.write(*index as u32);
+                    self.rbar.write(rbar);
+                    #[cfg(not(armv8m))]
+                    self.rasr.write(attributes);
+                    #[cfg(armv8m)]
+                    self.rlar.write(attributes);
+                }
+            }
+
+            crate::asm::dsb();
+            unsafe { self.ctrl.write(options.ctrl()) }
+            crate::asm::dsb();
+            crate::asm::isb();
+        });
+
+        Ok(())
+    }
+
+    /// Checks that an MPU is present and implements region `index`.
+    #[inline]
+    fn check_region(&self, index: u8) -> Result<(), MpuError> {
+        let count = self.region_count();
+        if count == 0 {
+            Err(MpuError::NotPresent)
+        } else if index >= count {
+            Err(MpuError::NoSuchRegion)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn regions(&self) -> Regions<'_> {
+        Regions {
+            block: self,
+            next: 0,
+            count: self.region_count(),
+        }
+    }
+
+    /// Selects region `index` and reads its `RBAR` and `RASR` (Armv7-M) or `RLAR` (Armv8-M)
+    /// values.
+    fn read_region(&self, index: u8) -> (u32, u32) {
+        unsafe { self.rnr.write(index as u32) };
+        #[cfg(not(armv8m))]
+        let attributes = self.rasr.read();
+        #[cfg(armv8m)]
+        let attributes = self.rlar.read();
+        (self.rbar.read(), attributes)
+    }
+}
+
+const MPU_CTRL_ENABLE: u32 = 1 << 0;
+const MPU_CTRL_HFNMIENA: u32 = 1 << 1;
+const MPU_CTRL_PRIVDEFENA: u32 = 1 << 2;
+
+/// Options for [`MPU::enable`], the `MPU_CTRL` bits besides `ENABLE`.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct MpuEnableOptions {
+    /// Use the default memory map as a background region for privileged accesses that don't hit
+    /// any enabled region (`PRIVDEFENA`). Unprivileged accesses that don't hit a region fault
+    /// regardless.
+    pub privileged_default_map: bool,
+    /// Keep the MPU enabled while running the HardFault and NMI handlers (`HFNMIENA`). When
+    /// `false`, these handlers ignore the MPU.
+    pub enable_during_hardfault_nmi: bool,
+}
+
+impl MpuEnableOptions {
+    /// The `MPU_CTRL` value enabling the MPU with these options.
+    #[inline]
+    const fn ctrl(self) -> u32 {
+        let mut ctrl = MPU_CTRL_ENABLE;
+        if self.enable_during_hardfault_nmi {
+            ctrl |= MPU_CTRL_HFNMIENA;
+        }
+        if self.privileged_default_map {
+            ctrl |= MPU_CTRL_PRIVDEFENA;
+        }
+        ctrl
+    }
+}
+
+/// Possible error values returned by the MPU configuration APIs.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum MpuError {
+    /// The region base address is not aligned to the region size.
+    MisalignedBase,
+    /// Subregions were disabled on a region smaller than 256 bytes, which has no subregions.
+    SubregionsNotSupported,
+    /// The memory attributes are not a valid encoding, e.g. `TEX` doesn't fit in 3 bits.
+    InvalidAttributes,
+    /// The region limit address is not the last byte of a 32-byte block, or lies below the base.
+    InvalidLimit,
+    /// The `MAIR` attribute index is out of the range 0 to 7.
+    InvalidAttributeIndex,
+    /// The MPU doesn't implement the region.
+    NoSuchRegion,
+    /// There is no MPU.
+    NotPresent,
+    /// The range to cover doesn't start and end on subregion boundaries, or lies outside of the
+    /// region.
+    InvalidSubregionRange,
+    /// A region of a region table is entirely covered by a higher numbered region with different
+    /// settings, so it never takes effect. See [`validate_table`].
+    ShadowedRegion,
+    /// The MPU would be enabled without any enabled region and without the default memory map.
+    NoEnabledRegion,
+}
+
+/// Size of a PMSAv7 MPU region.
+///
+/// The discriminant is the `RASR.SIZE` field encoding, `log2(size) - 1`.
+#[cfg(not(armv8m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+#[allow(missing_docs)]
+pub enum RegionSize {
+    Size32B = 4,
+    Size64B = 5,
+    Size128B = 6,
+    Size256B = 7,
+    Size512B = 8,
+    Size1K = 9,
+    Size2K = 10,
+    Size4K = 11,
+    Size8K = 12,
+    Size16K = 13,
+    Size32K = 14,
+    Size64K = 15,
+    Size128K = 16,
+    Size256K = 17,
+    Size512K = 18,
+    Size1M = 19,
+    Size2M = 20,
+    Size4M = 21,
+    Size8M = 22,
+    Size16M = 23,
+    Size32M = 24,
+    Size64M = 25,
+    Size128M = 26,
+    Size256M = 27,
+    Size512M = 28,
+    Size1G = 29,
+    Size2G = 30,
+    Size4G = 31,
+}
+
+#[cfg(not(armv8m))]
+impl RegionSize {
+    /// The region size in bytes.
+    ///
+    /// This is a `u64` so that [`RegionSize::Size4G`] can be represented.
+    #[inline]
+    pub const fn bytes(self) -> u64 {
+        1 << (self as u8 + 1)
+    }
+}
+
+/// Encodes a PMSAv7 region size in bytes as the `RASR.SIZE` field, `log2(size_bytes) - 1`.
+///
+/// Returns `None` unless `size_bytes` is a power of two from 32 bytes up to 4 GiB. The size is a
+/// `u64` so that 4 GiB can be represented.
+#[cfg(not(armv8m))]
+#[inline]
+pub const fn size_to_rasr_field(size_bytes: u64) -> Option<u8> {
+    if !size_bytes.is_power_of_two() || size_bytes < 32 || size_bytes > 1 << 32 {
+        return None;
+    }
+    Some(size_bytes.trailing_zeros() as u8 - 1)
+}
+
+/// Decodes a PMSAv7 `RASR.SIZE` field into the region size in bytes.
+///
+/// Returns `None` for the reserved encodings below 4 (32 bytes) and for values that don't fit in
+/// the 5-bit field.
+#[cfg(not(armv8m))]
+#[inline]
+pub const fn rasr_field_to_size(field: u8) -> Option<u64> {
+    if field < 4 || field > 31 {
+        return None;
+    }
+    Some(1 << (field + 1))
+}
+
+/// Access permissions of a PMSAv7 MPU region, the `RASR.AP` field.
+#[cfg(not(armv8m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum AccessPermission {
+    /// No access.
+    NoAccess = 0b000,
+    /// Privileged read/write, no unprivileged access.
+    PrivilegedReadWrite = 0b001,
+    /// Privileged read/write, unprivileged read-only.
+    PrivilegedReadWriteUnprivilegedReadOnly = 0b010,
+    /// Read/write for privileged and unprivileged code.
+    ReadWrite = 0b011,
+    /// Privileged read-only, no unprivileged access.
+    PrivilegedReadOnly = 0b101,
+    /// Read-only for privileged and unprivileged code.
+    ReadOnly = 0b110,
+}
+
+/// Memory type and cacheability of a PMSAv7 MPU region, the `RASR.TEX`, `S`, `C` and `B` fields.
+///
+/// See Table B3-13 of the Armv7-M Architecture Reference Manual for the encodings.
+#[cfg(not(armv8m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MemoryAttributes {
+    /// Type extension field, 3 bits.
+    pub tex: u8,
+    /// Cacheable bit.
+    pub cacheable: bool,
+    /// Bufferable bit.
+    pub bufferable: bool,
+    /// Shareable bit.
+    pub shareable: bool,
+}
+
+#[cfg(not(armv8m))]
+impl MemoryAttributes {
+    /// Normal memory, outer and inner write-back, write and read allocate.
+    ///
+    /// `TEX = 0b001`, `C = 1`, `B = 1`, `S = 0`.
+    #[inline]
+    pub const fn normal_cacheable_wb_wa() -> Self {
+        MemoryAttributes {
+            tex: 0b001,
+            cacheable: true,
+            bufferable: true,
+            shareable: false,
+        }
+    }
+
+    /// Normal memory, outer and inner write-through, no write allocate.
+    ///
+    /// `TEX = 0b000`, `C = 1`, `B = 0`, `S = 0`.
+    #[inline]
+    pub const fn normal_cacheable_wt() -> Self {
+        MemoryAttributes {
+            tex: 0b000,
+            cacheable: true,
+            bufferable: false,
+            shareable: false,
+        }
+    }
+
+    /// Normal memory, outer and inner non-cacheable.
+    ///
+    /// `TEX = 0b001`, `C = 0`, `B = 0`, `S = 0`.
+    #[inline]
+    pub const fn normal_noncacheable() -> Self {
+        MemoryAttributes {
+            tex: 0b001,
+            cacheable: false,
+            bufferable: false,
+            shareable: false,
+        }
+    }
+
+    /// Shareable device memory.
+    ///
+    /// `TEX = 0b000`, `C = 0`, `B = 1`. The `S` bit is ignored, device memory is always shareable
+    /// with this encoding.
+    #[inline]
+    pub const fn device() -> Self {
+        MemoryAttributes {
+            tex: 0b000,
+            cacheable: false,
+            bufferable: true,
+            shareable: false,
+        }
+    }
+
+    /// Strongly-ordered memory.
+    ///
+    /// `TEX = 0b000`, `C = 0`, `B = 0`. The `S` bit is ignored, strongly-ordered memory is always
+    /// shareable.
+    #[inline]
+    pub const fn strongly_ordered() -> Self {
+        MemoryAttributes {
+            tex: 0b000,
+            cacheable: false,
+            bufferable: false,
+            shareable: false,
+        }
+    }
+
+    /// Returns these attributes with the `S` bit set, for normal memory shared with other bus
+    /// masters.
+    #[inline]
+    pub const fn shareable(self) -> Self {
+        MemoryAttributes {
+            shareable: true,
+            ..self
+        }
+    }
+}
+
+/// Configuration of a PMSAv7 MPU region.
+#[cfg(not(armv8m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegionConfig {
+    /// Base address of the region, which must be aligned to `size`.
+    pub base: u32,
+    /// Size of the region.
+    pub size: RegionSize,
+    /// Access permissions.
+    pub access: AccessPermission,
+    /// Memory type and cacheability.
+    pub attributes: MemoryAttributes,
+    /// Subregion disable mask. Bit `n` set disables the `n`th eighth of the region. Must be zero
+    /// for regions smaller than 256 bytes.
+    pub subregion_disable: u8,
+    /// Forbid instruction fetches from the region.
+    pub execute_never: bool,
+}
+
+#[cfg(not(armv8m))]
+const RASR_ENABLE: u32 = 1;
+
+#[cfg(not(armv8m))]
+impl RegionConfig {
+    /// Checks the configuration against the PMSAv7 rules.
+    ///
+    /// This is a `const fn`, so static region tables can be checked at compile time:
+    ///
+    /// ```
+    /// use cortex_m::peripheral::mpu::{
+    ///     AccessPermission, MemoryAttributes, RegionConfig, RegionSize,
+    /// };
+    ///
+    /// const FLASH: RegionConfig = RegionConfig {
+    ///     base: 0x0800_0000,
+    ///     size: RegionSize::Size512K,
+    ///     access: AccessPermission::ReadOnly,
+    ///     attributes: MemoryAttributes {
+    ///         tex: 0b000,
+    ///         cacheable: true,
+    ///         bufferable: false,
+    ///         shareable: false,
+    ///     },
+    ///     subregion_disable: 0,
+    ///     execute_never: false,
+    /// };
+    ///
+    /// const _: () = assert!(FLASH.validate().is_ok());
+    /// ```
+    #[inline]
+    pub const fn validate(&self) -> Result<(), MpuError> {
+        if self.base as u64 % self.size.bytes() != 0 {
+            return Err(MpuError::MisalignedBase);
+        }
+        if self.subregion_disable != 0 && (self.size as u8) < RegionSize::Size256B as u8 {
+            return Err(MpuError::SubregionsNotSupported);
+        }
+        if self.attributes.tex > 0b111 {
+            return Err(MpuError::InvalidAttributes);
+        }
+        Ok(())
+    }
+
+    /// The `RBAR` value of the region, without the `VALID` and `REGION` fields.
+    #[inline]
+    pub const fn rbar(&self) -> u32 {
+        self.base & !0x1F
+    }
+
+    /// The `RASR` value of the region, with the `ENABLE` bit set.
+    #[inline]
+    pub const fn rasr(&self) -> u32 {
+        let attributes = &self.attributes;
+        ((self.execute_never as u32) << 28)
+            | ((self.access as u32) << 24)
+            | (((attributes.tex & 0b111) as u32) << 19)
+            | ((attributes.shareable as u32) << 18)
+            | ((attributes.cacheable as u32) << 17)
+            | ((attributes.bufferable as u32) << 16)
+            | ((self.subregion_disable as u32) << 8)
+            | ((self.size as u32) << 1)
+            | RASR_ENABLE
+    }
+
+    /// Decodes a region from its `RBAR` and `RASR` values, ignoring the `ENABLE` bit.
+    ///
+    /// Returns `None` if the `SIZE` or `AP` field holds a reserved encoding. The `AP` encoding
+    /// `0b111` decodes to [`AccessPermission::ReadOnly`], which has the same meaning.
+    #[inline]
+    pub const fn from_registers(rbar: u32, rasr: u32) -> Option<Self> {
+        let size = match region_size_from_field(((rasr >> 1) & 0x1F) as u8) {
+            Some(size) => size,
+            None => return None,
+        };
+        let access = match (rasr >> 24) & 0b111 {
+            0b000 => AccessPermission::NoAccess,
+            0b001 => AccessPermission::PrivilegedReadWrite,
+            0b010 => AccessPermission::PrivilegedReadWriteUnprivilegedReadOnly,
+            0b011 => AccessPermission::ReadWrite,
+            0b101 => AccessPermission::PrivilegedReadOnly,
+            0b110 | 0b111 => AccessPermission::ReadOnly,
+            _ => return None,
+        };
+
+        Some(RegionConfig {
+            base: rbar & !0x1F,
+            size,
+            access,
+            attributes: MemoryAttributes {
+                tex: ((rasr >> 19) & 0b111) as u8,
+                cacheable: rasr & (1 << 17) != 0,
+                bufferable: rasr & (1 << 16) != 0,
+                shareable: rasr & (1 << 18) != 0,
+            },
+            subregion_disable: (rasr >> 8) as u8,
+            execute_never: rasr & (1 << 28) != 0,
+        })
+    }
+}
+
+#[cfg(not(armv8m))]
+impl RegionConfig {
+    /// Returns this configuration as a region of `region_size` at `region_base`, with the
+    /// subregions disabled such that exactly `used_len` bytes starting at `used_start` are
+    /// covered.
+    ///
+    /// For example, 96 KiB of RAM are covered by a 128 KiB region with the top two 16 KiB
+    /// subregions disabled:
+    ///
+    /// ```
+    /// use cortex_m::peripheral::mpu::{
+    ///     AccessPermission, MemoryAttributes, RegionConfig, RegionSize,
+    /// };
+    ///
+    /// let ram = RegionConfig {
+    ///     base: 0,
+    ///     size: RegionSize::Size32B,
+    ///     access: AccessPermission::ReadWrite,
+    ///     attributes: MemoryAttributes::normal_cacheable_wb_wa(),
+    ///     subregion_disable: 0,
+    ///     execute_never: true,
+    /// }
+    /// .with_covered_range(0x2000_0000, RegionSize::Size128K, 0x2000_0000, 96 * 1024)
+    /// .unwrap();
+    ///
+    /// assert_eq!(ram.subregion_disable, 0b1100_0000);
+    /// ```
+    ///
+    /// Returns [`MpuError::MisalignedBase`] if `region_base` is not aligned to `region_size`,
+    /// [`MpuError::InvalidSubregionRange`] if the used range is empty, doesn't fit in the region,
+    /// or doesn't start and end on subregion boundaries, and
+    /// [`MpuError::SubregionsNotSupported`] if the region is smaller than 256 bytes and the range
+    /// doesn't cover all of it.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub const fn with_covered_range(
+        self,
+        region_base: u32,
+        region_size: RegionSize,
+        used_start: u32,
+        used_len: u32,
+    ) -> Result<Self, MpuError> {
+        let base = region_base as u64;
+        let size = region_size.bytes();
+        if base % size != 0 {
+            return Err(MpuError::MisalignedBase);
+        }
+
+        let start = used_start as u64;
+        let end = start + used_len as u64;
+        if used_len == 0 || start < base || end > base + size {
+            return Err(MpuError::InvalidSubregionRange);
+        }
+
+        let subregion_disable = if start == base && end == base + size {
+            0
+        } else if (region_size as u8) < RegionSize::Size256B as u8 {
+            return Err(MpuError::SubregionsNotSupported);
+        } else {
+            let sub = size / 8;
+            if (start - base) % sub != 0 || (end - base) % sub != 0 {
+                return Err(MpuError::InvalidSubregionRange);
+            }
+            let first = (start - base) / sub;
+            let count = (end - start) / sub;
+            !((((1u32 << count) - 1) << first) as u8)
+        };
+
+        Ok(RegionConfig {
+            base: region_base,
+            size: region_size,
+            subregion_disable,
+            ..self
+        })
+    }
+
+    /// Returns an iterator over the address ranges the region covers, i.e. its enabled
+    /// subregions with adjacent ones merged.
+    ///
+    /// This is the inverse of [`with_covered_range`](RegionConfig::with_covered_range), and
+    /// agrees with [`contains`](RegionConfig::contains).
+    #[inline]
+    pub fn covered_ranges(&self) -> CoveredRanges {
+        CoveredRanges {
+            config: *self,
+            next: 0,
+        }
+    }
+
+    /// Returns `true` if the region covers `address`, taking disabled subregions into account.
+    #[inline]
+    pub const fn contains(&self, address: u32) -> bool {
+        let size = self.size.bytes();
+        let offset = (address as u64).wrapping_sub(self.base as u64);
+        if address < self.base || offset >= size {
+            return false;
+        }
+        if (self.size as u8) < RegionSize::Size256B as u8 {
+            return true;
+        }
+        self.subregion_disable & (1 << (offset / (size / 8))) == 0
+    }
+}
+
+/// Iterator over the address ranges covered by a PMSAv7 region, see
+/// [`RegionConfig::covered_ranges`].
+#[cfg(not(armv8m))]
+#[derive(Debug, Clone)]
+pub struct CoveredRanges {
+    config: RegionConfig,
+    next: u8,
+}
+
+#[cfg(not(armv8m))]
+impl Iterator for CoveredRanges {
+    type Item = RangeInclusive<u32>;
+
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn next(&mut self) -> Option<Self::Item> {
+        let config = &self.config;
+        let (subregions, sub) = if (config.size as u8) < RegionSize::Size256B as u8 {
+            (1, config.size.bytes())
+        } else {
+            (8, config.size.bytes() / 8)
+        };
+        let enabled = |k: u8| subregions == 1 || config.subregion_disable & (1 << k) == 0;
+
+        while self.next < subregions && !enabled(self.next) {
+            self.next += 1;
+        }
+        if self.next >= subregions {
+            return None;
+        }
+        let first = self.next;
+        while self.next < subregions && enabled(self.next) {
+            self.next += 1;
+        }
+
+        let start = config.base as u64 + first as u64 * sub;
+        let end = config.base as u64 + self.next as u64 * sub - 1;
+        Some(start as u32..=end as u32)
+    }
+}
+
+/// Checks a table of PMSAv7 regions, where the table index is the region number.
+///
+/// Each region is [validated](RegionConfig::validate) on its own. In addition, a region must not
+/// be shadowed: naturally aligned regions either don't overlap or one contains the other, and
+/// where they overlap the higher numbered region takes effect. A region that lies entirely in
+/// the enabled subregions of a higher numbered region therefore never takes effect, which is
+/// almost certainly a mistake when their permissions or attributes differ. Returns
+/// [`MpuError::ShadowedRegion`] for that case.
+///
+/// This is a `const fn`, see [`static_mpu_table!`](crate::static_mpu_table) for checking a
+/// table at compile time.
+#[cfg(not(armv8m))]
+#[allow(clippy::missing_inline_in_public_items)]
+pub const fn validate_table(regions: &[RegionConfig]) -> Result<(), MpuError> {
+    let mut i = 0;
+    while i < regions.len() {
+        if let Err(e) = regions[i].validate() {
+            return Err(e);
+        }
+
+        let mut j = i + 1;
+        while j < regions.len() {
+            if shadows(&regions[j], &regions[i]) && !same_settings(&regions[j], &regions[i]) {
+                return Err(MpuError::ShadowedRegion);
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+/// Returns `true` if the enabled subregions of `higher` cover all of `lower`.
+#[cfg(not(armv8m))]
+const fn shadows(higher: &RegionConfig, lower: &RegionConfig) -> bool {
+    let (start, end) = (lower.base as u64, lower.base as u64 + lower.size.bytes());
+    let (hstart, hsize) = (higher.base as u64, higher.size.bytes());
+    if start < hstart || end > hstart + hsize {
+        return false;
+    }
+    if (higher.size as u8) < RegionSize::Size256B as u8 {
+        return true;
+    }
+
+    let sub = hsize / 8;
+    let mut k = (start - hstart) / sub;
+    while k * sub < end - hstart {
+        if higher.subregion_disable & (1 << k) != 0 {
+            return false;
+        }
+        k += 1;
+    }
+    true
+}
+
+#[cfg(not(armv8m))]
+const fn same_settings(a: &RegionConfig, b: &RegionConfig) -> bool {
+    a.access as u8 == b.access as u8
+        && a.execute_never == b.execute_never
+        && a.attributes.tex == b.attributes.tex
+        && a.attributes.cacheable == b.attributes.cacheable
+        && a.attributes.bufferable == b.attributes.bufferable
+        && a.attributes.shareable == b.attributes.shareable
+}
+
+#[cfg(not(armv8m))]
+const fn region_size_from_field(field: u8) -> Option<RegionSize> {
+    use RegionSize::*;
+
+    const SIZES: [RegionSize; 28] = [
+        Size32B, Size64B, Size128B, Size256B, Size512B, Size1K, Size2K, Size4K, Size8K, Size16K,
+        Size32K, Size64K, Size128K, Size256K, Size512K, Size1M, Size2M, Size4M, Size8M, Size16M,
+        Size32M, Size64M, Size128M, Size256M, Size512M, Size1G, Size2G, Size4G,
+    ];
+
+    if field < 4 || field > 31 {
+        None
+    } else {
+        Some(SIZES[field as usize - 4])
+    }
+}
+
+/// A region of the MPU, as returned by [`MPU::regions`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Region<C> {
+    /// The region number.
+    pub index: u8,
+    /// Whether the region is enabled.
+    pub enabled: bool,
+    /// The decoded region configuration, `None` if the registers hold a reserved encoding.
+    pub config: Option<C>,
+}
+
+/// Iterator over all regions of the MPU, see [`MPU::regions`].
+pub struct Regions<'a> {
+    block: &'a RegisterBlock,
+    next: u8,
+    count: u8,
+}
+
+#[cfg(not(armv8m))]
+impl<'a> Iterator for Regions<'a> {
+    type Item = Region<RegionConfig>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.count {
+            return None;
+        }
+        let index = self.next;
+        self.next += 1;
+
+        let (rbar, rasr) = self.block.read_region(index);
+        Some(Region {
+            index,
+            enabled: rasr & RASR_ENABLE != 0,
+            config: RegionConfig::from_registers(rbar, rasr),
+        })
+    }
+}
+
+#[cfg(armv8m)]
+impl<'a> Iterator for Regions<'a> {
+    type Item = Region<RegionConfigV8>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.count {
+            return None;
+        }
+        let index = self.next;
+        self.next += 1;
+
+        let (rbar, rlar) = self.block.read_region(index);
+        Some(Region {
+            index,
+            enabled: rlar & RLAR_ENABLE != 0,
+            config: Some(RegionConfigV8::from_registers(rbar, rlar)),
+        })
+    }
+}
+
+#[cfg(not(armv8m))]
+impl MPU {
+    /// Configures and enables region `index`.
+    ///
+    /// The configuration is [validated](RegionConfig::validate) first; nothing is written if it is
+    /// invalid, or if the MPU doesn't implement region `index`. The region is disabled while its
+    /// registers are rewritten, so it is never active in a half-configured state, and the new
+    /// configuration is in effect once this returns.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn configure_region(&mut self, index: u8, config: &RegionConfig) -> Result<(), MpuError> {
+        self.check_region(index)?;
+        config.validate()?;
+
+        // complete all outstanding memory accesses under the old configuration
+        crate::asm::dmb();
+        unsafe {
+            self.rnr.write(index as u32);
+            self.rasr.write(0);
+            self.rbar.write(config.rbar());
+            self.rasr.write(config.rasr());
+        }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+
+    /// Programs all `regions`, given as `(index, config)` pairs, and enables the MPU with
+    /// `options`, as one atomic update.
+    ///
+    /// All regions are checked before anything is written. Regions not listed keep their
+    /// configuration. See [atomic reprogramming] for what exception handlers observe, and
+    /// [cost of region updates] for how long interrupts are masked.
+    ///
+    /// Returns an error, without writing anything, if a region doesn't exist or its
+    /// configuration is invalid, and [`MpuError::NoEnabledRegion`] if the MPU would be enabled
+    /// without any region and without the default memory map.
+    ///
+    /// [atomic reprogramming]: crate::peripheral::mpu#atomic-reprogramming
+    /// [cost of region updates]: crate::peripheral::mpu#cost-of-region-updates
+    #[inline]
+    pub fn reprogram(
+        &mut self,
+        regions: &[(u8, RegionConfig)],
+        options: MpuEnableOptions,
+    ) -> Result<(), MpuError> {
+        self.reprogram_regions(regions, options, RegionConfig::validate, |config| {
+            (config.rbar(), config.rasr())
+        })
+    }
+
+    /// Reads back the configuration of region `index`, whether it is enabled or not.
+    ///
+    /// Returns `None` if the MPU doesn't implement the region or if its registers hold a reserved
+    /// encoding.
+    #[inline]
+    pub fn region(&mut self, index: u8) -> Option<RegionConfig> {
+        self.check_region(index).ok()?;
+        let (rbar, rasr) = self.read_region(index);
+        RegionConfig::from_registers(rbar, rasr)
+    }
+
+    /// Disables region `index`, leaving its other settings untouched.
+    #[inline]
+    pub fn disable_region(&mut self, index: u8) -> Result<(), MpuError> {
+        self.check_region(index)?;
+
+        crate::asm::dmb();
+        unsafe {
+            self.rnr.write(index as u32);
+            self.rasr.modify(|r| r & !RASR_ENABLE);
+        }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+}
+
+/// A memory attribute encoding for one of the eight `MAIR` slots of the PMSAv8 MPU.
+///
+/// See section D1.2.84 "MPU_MAIR0, MPU Memory Attribute Indirection Register 0" of the Armv8-M
+/// Architecture Reference Manual for the encodings.
+#[cfg(any(armv8m, native))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MemoryAttribute {
+    bits: u8,
+}
+
+#[cfg(any(armv8m, native))]
+impl MemoryAttribute {
+    /// Creates an attribute from its raw `MAIR` byte.
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        MemoryAttribute { bits }
+    }
+
+    /// Returns the raw `MAIR` byte.
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        self.bits
+    }
+
+    /// Device-nGnRnE memory, the equivalent of PMSAv7 strongly-ordered memory: `0x00`.
+    #[inline]
+    pub const fn device_ngnrne() -> Self {
+        MemoryAttribute { bits: 0b0000_0000 }
+    }
+
+    /// Device-nGnRE memory, the equivalent of PMSAv7 device memory: `0x04`.
+    #[inline]
+    pub const fn device_ngnre() -> Self {
+        MemoryAttribute { bits: 0b0000_0100 }
+    }
+
+    /// Device-nGRE memory: `0x08`.
+    #[inline]
+    pub const fn device_ngre() -> Self {
+        MemoryAttribute { bits: 0b0000_1000 }
+    }
+
+    /// Device-GRE memory: `0x0C`.
+    #[inline]
+    pub const fn device_gre() -> Self {
+        MemoryAttribute { bits: 0b0000_1100 }
+    }
+
+    /// Normal memory, outer and inner non-cacheable: `0x44`.
+    #[inline]
+    pub const fn normal_noncacheable() -> Self {
+        MemoryAttribute { bits: 0b0100_0100 }
+    }
+
+    /// Normal memory, outer and inner write-through non-transient, read allocate: `0xAA`.
+    #[inline]
+    pub const fn normal_wt_ralloc() -> Self {
+        MemoryAttribute { bits: 0b1010_1010 }
+    }
+
+    /// Normal memory, outer and inner write-back non-transient, read and write allocate: `0xFF`.
+    #[inline]
+    pub const fn normal_wb_rwalloc() -> Self {
+        MemoryAttribute { bits: 0b1111_1111 }
+    }
+}
+
+/// Access permissions of a PMSAv8 MPU region, the `RBAR.AP` field.
+#[cfg(any(armv8m, native))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum AccessPermissionV8 {
+    /// Privileged read/write, no unprivileged access.
+    PrivilegedReadWrite = 0b00,
+    /// Read/write for privileged and unprivileged code.
+    ReadWrite = 0b01,
+    /// Privileged read-only, no unprivileged access.
+    PrivilegedReadOnly = 0b10,
+    /// Read-only for privileged and unprivileged code.
+    ReadOnly = 0b11,
+}
+
+#[cfg(any(armv8m, native))]
+const RLAR_ENABLE: u32 = 1;
+
+/// Configuration of a PMSAv8 MPU region.
+#[cfg(any(armv8m, native))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RegionConfigV8 {
+    /// Base address of the region, which must be aligned to 32 bytes.
+    pub base: u32,
+    /// Address of the last byte of the region, inclusive. Must be the last byte of a 32-byte
+    /// block, e.g. `0x2000_7FFF`.
+    pub limit: u32,
+    /// The `MAIR` slot holding the memory attributes of the region.
+    pub attr_index: u8,
+    /// Access permissions.
+    pub access: AccessPermissionV8,
+    /// Forbid instruction fetches from the region.
+    pub execute_never: bool,
+}
+
+#[cfg(any(armv8m, native))]
+impl RegionConfigV8 {
+    /// Checks the configuration against the PMSAv8 rules.
+    #[inline]
+    pub const fn validate(&self) -> Result<(), MpuError> {
+        if self.base & 0x1F != 0 {
+            return Err(MpuError::MisalignedBase);
+        }
+        if self.limit & 0x1F != 0x1F || self.limit < self.base {
+            return Err(MpuError::InvalidLimit);
+        }
+        if self.attr_index > 7 {
+            return Err(MpuError::InvalidAttributeIndex);
+        }
+        Ok(())
+    }
+
+    /// The `RBAR` value of the region. The region is non-shareable.
+    #[inline]
+    pub const fn rbar(&self) -> u32 {
+        (self.base & !0x1F) | ((self.access as u32) << 1) | self.execute_never as u32
+    }
+
+    /// The `RLAR` value of the region, with the `EN` bit set.
+    #[inline]
+    pub const fn rlar(&self) -> u32 {
+        (self.limit & !0x1F) | (((self.attr_index & 0b111) as u32) << 1) | RLAR_ENABLE
+    }
+
+    /// Decodes a region from its `RBAR` and `RLAR` values, ignoring the `EN` bit and the
+    /// shareability.
+    #[inline]
+    pub const fn from_registers(rbar: u32, rlar: u32) -> Self {
+        RegionConfigV8 {
+            base: rbar & !0x1F,
+            limit: rlar | 0x1F,
+            attr_index: ((rlar >> 1) & 0b111) as u8,
+            access: match (rbar >> 1) & 0b11 {
+                0b00 => AccessPermissionV8::PrivilegedReadWrite,
+                0b01 => AccessPermissionV8::ReadWrite,
+                0b10 => AccessPermissionV8::PrivilegedReadOnly,
+                _ => AccessPermissionV8::ReadOnly,
+            },
+            execute_never: rbar & 1 != 0,
+        }
+    }
+
+    /// Returns `true` if the region covers `address`.
+    #[inline]
+    pub const fn contains(&self, address: u32) -> bool {
+        self.base <= address && address <= self.limit
+    }
+}
+
+#[cfg(armv8m)]
+impl MPU {
+    /// Sets the memory attribute of `MAIR` slot `index`, which regions refer to by index.
+    ///
+    /// Returns [`MpuError::InvalidAttributeIndex`] if `index` is not in the range 0 to 7, and
+    /// [`MpuError::NotPresent`] if there is no MPU.
+    #[inline]
+    pub fn set_memory_attribute(
+        &mut self,
+        index: u8,
+        attribute: MemoryAttribute,
+    ) -> Result<(), MpuError> {
+        (**self).set_memory_attribute(index, attribute)
+    }
+
+    /// Configures and enables region `index`, spanning `base` up to and including `limit`.
+    ///
+    /// `base` must be aligned to 32 bytes and `limit` must be the last byte of a 32-byte block,
+    /// e.g. `0x2000_7FFF`. `attr_index` selects the `MAIR` slot, see
+    /// [`set_memory_attribute`](MPU::set_memory_attribute). Nothing is written if any of the
+    /// arguments is invalid, or if the MPU doesn't implement region `index`. The region is disabled
+    /// while its registers are rewritten, so it is never active in a half-configured state, and
+    /// the new configuration is in effect once this returns.
+    #[inline]
+    pub fn configure_region_v8(
+        &mut self,
+        index: u8,
+        base: u32,
+        limit: u32,
+        attr_index: u8,
+        access: AccessPermissionV8,
+        execute_never: bool,
+    ) -> Result<(), MpuError> {
+        let config = RegionConfigV8 {
+            base,
+            limit,
+            attr_index,
+            access,
+            execute_never,
+        };
+        (**self).configure_region_v8(index, &config)
+    }
+
+    /// Programs all `regions`, given as `(index, config)` pairs, and enables the MPU with
+    /// `options`, as one atomic update.
+    ///
+    /// All regions are checked before anything is written. Regions not listed keep their
+    /// configuration. See [atomic reprogramming] for what exception handlers observe, and
+    /// [cost of region updates] for how long interrupts are masked.
+    ///
+    /// Returns an error, without writing anything, if a region doesn't exist or its
+    /// configuration is invalid, and [`MpuError::NoEnabledRegion`] if the MPU would be enabled
+    /// without any region and without the default memory map.
+    ///
+    /// [atomic reprogramming]: crate::peripheral::mpu#atomic-reprogramming
+    /// [cost of region updates]: crate::peripheral::mpu#cost-of-region-updates
+    #[inline]
+    pub fn reprogram(
+        &mut self,
+        regions: &[(u8, RegionConfigV8)],
+        options: MpuEnableOptions,
+    ) -> Result<(), MpuError> {
+        (**self).reprogram(regions, options)
+    }
+
+    /// Reads back the configuration of region `index`, whether it is enabled or not.
+    ///
+    /// Returns `None` if the MPU doesn't implement the region.
+    #[inline]
+    pub fn region(&mut self, index: u8) -> Option<RegionConfigV8> {
+        (**self).region(index)
+    }
+
+    /// Disables region `index`, leaving its other settings untouched.
+    #[inline]
+    pub fn disable_region(&mut self, index: u8) -> Result<(), MpuError> {
+        (**self).disable_region(index)
+    }
+}
+
+#[cfg(armv8m)]
+impl RegisterBlock {
+    fn set_memory_attribute(&self, index: u8, attribute: MemoryAttribute) -> Result<(), MpuError> {
+        if !self.is_present() {
+            return Err(MpuError::NotPresent);
+        }
+        if index > 7 {
+            return Err(MpuError::InvalidAttributeIndex);
+        }
+
+        let shift = (index % 4) * 8;
+        unsafe {
+            self.mair[usize::from(index / 4)]
+                .modify(|r| (r & !(0xFF << shift)) | ((attribute.bits() as u32) << shift));
+        }
+
+        Ok(())
+    }
+
+    fn configure_region_v8(&self, index: u8, config: &RegionConfigV8) -> Result<(), MpuError> {
+        self.check_region(index)?;
+        config.validate()?;
+        let (rbar, rlar) = (config.rbar(), config.rlar());
+
+        // complete all outstanding memory accesses under the old configuration
+        crate::asm::dmb();
+        unsafe {
+            self.rnr.write(index as u32);
+            self.rlar.write(0);
+            self.rbar.write(rbar);
+            self.rlar.write(rlar);
+        }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+
+    fn install_stack_guard(
+        &self,
+        guard_start: *const u8,
+        size: GuardSize,
+        region_index: u8,
+    ) -> Result<(), MpuError> {
+        let config = RegionConfigV8::stack_guard(guard_start as u32, size)?;
+        self.configure_region_v8(region_index, &config)
+    }
+
+    fn reprogram(
+        &self,
+        regions: &[(u8, RegionConfigV8)],
+        options: MpuEnableOptions,
+    ) -> Result<(), MpuError> {
+        self.reprogram_regions(regions, options, RegionConfigV8::validate, |config| {
+            (config.rbar(), config.rlar())
+        })
+    }
+
+    fn region(&self, index: u8) -> Option<RegionConfigV8> {
+        self.check_region(index).ok()?;
+        let (rbar, rlar) = self.read_region(index);
+        Some(RegionConfigV8::from_registers(rbar, rlar))
+    }
+
+    fn disable_region(&self, index: u8) -> Result<(), MpuError> {
+        self.check_region(index)?;
+
+        crate::asm::dmb();
+        unsafe {
+            self.rnr.write(index as u32);
+            self.rlar.modify(|r| r & !RLAR_ENABLE);
+        }
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+}
+
+#[cfg(armv8m)]
+impl MPU {
+    /// Pointer to the Non-secure alias of the register block, at `0xE002_ED90`
+    pub const PTR_NS: *const RegisterBlock = 0xE002_ED90 as *const _;
+
+    /// Returns a pointer to the Non-secure alias of the register block
+    #[inline(always)]
+    pub const fn ptr_ns() -> *const RegisterBlock {
+        Self::PTR_NS
+    }
+
+    /// Returns a handle to the Non-secure MPU, for Secure code setting it up on behalf of the
+    /// Non-secure image.
+    ///
+    /// The handle borrows the MPU, so at most one of the two banks is being programmed at a time,
+    /// and code holding only an `MPU` can't accidentally program the Non-secure bank (or vice
+    /// versa).
+    ///
+    /// *NOTE* Only Secure code can access the alias. From Non-secure state it is RES0: reads
+    /// return zero and writes are ignored, so the Non-secure MPU appears to have no regions.
+    #[inline]
+    pub fn non_secure(&mut self) -> MpuNs<'_> {
+        MpuNs { _mpu: PhantomData }
+    }
+}
+
+/// The Non-secure MPU, accessed through its alias from Secure state, see [`MPU::non_secure`].
+///
+/// The region configuration API is the same as that of [`MPU`].
+#[cfg(armv8m)]
+pub struct MpuNs<'a> {
+    _mpu: PhantomData<&'a mut MPU>,
+}
+
+#[cfg(armv8m)]
+impl ops::Deref for MpuNs<'_> {
+    type Target = RegisterBlock;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        unsafe { &*MPU::PTR_NS }
+    }
+}
+
+#[cfg(armv8m)]
+impl MpuNs<'_> {
+    /// See [`MPU::mpu_type`].
+    #[inline]
+    pub fn mpu_type(&self) -> Type {
+        (**self).mpu_type()
+    }
+
+    /// See [`MPU::region_count`].
+    #[inline]
+    pub fn region_count(&self) -> u8 {
+        (**self).region_count()
+    }
+
+    /// See [`MPU::is_present`].
+    #[inline]
+    pub fn is_present(&self) -> bool {
+        (**self).is_present()
+    }
+
+    /// See [`MPU::enable`].
+    #[inline]
+    pub fn enable(&mut self, options: MpuEnableOptions) -> Result<(), MpuError> {
+        (**self).enable(options)
+    }
+
+    /// See [`MPU::disable`].
+    #[inline]
+    pub fn disable(&mut self) {
+        (**self).disable()
+    }
+
+    /// See [`MPU::is_enabled`].
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        (**self).is_enabled()
+    }
+
+    /// Runs `f` with the Non-secure MPU disabled, then restores its previous `MPU_CTRL_NS`
+    /// settings. See [`MPU::reconfigure`].
+    #[inline]
+    pub fn reconfigure<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        // NOTE(unsafe) `f` gets `self`, the register block is only used around it
+        let block = unsafe { &*MPU::PTR_NS };
+        block.reconfigure(|| f(self))
+    }
+
+    /// See [`MPU::set_region_enabled`].
+    #[inline]
+    pub fn set_region_enabled(&mut self, index: u8, enabled: bool) -> Result<(), MpuError> {
+        (**self).set_region_enabled(index, enabled)
+    }
+
+    /// See [`MPU::update_regions`].
+    #[inline]
+    pub fn update_regions(&mut self, changes: &[(u8, bool)]) -> Result<(), MpuError> {
+        (**self).update_regions(changes)
+    }
+
+    /// See [`MPU::regions`].
+    #[inline]
+    pub fn regions(&mut self) -> Regions<'_> {
+        (**self).regions()
+    }
+
+    /// See [`MPU::set_memory_attribute`].
+    #[inline]
+    pub fn set_memory_attribute(
+        &mut self,
+        index: u8,
+        attribute: MemoryAttribute,
+    ) -> Result<(), MpuError> {
+        (**self).set_memory_attribute(index, attribute)
+    }
+
+    /// Configures and enables region `index`, see [`MPU::configure_region_v8`].
+    #[inline]
+    pub fn configure_region(&mut self, index: u8, config: &RegionConfigV8) -> Result<(), MpuError> {
+        (**self).configure_region_v8(index, config)
+    }
+
+    /// See [`MPU::reprogram`].
+    #[inline]
+    pub fn reprogram(
+        &mut self,
+        regions: &[(u8, RegionConfigV8)],
+        options: MpuEnableOptions,
+    ) -> Result<(), MpuError> {
+        (**self).reprogram(regions, options)
+    }
+
+    /// See [`MPU::region`].
+    #[inline]
+    pub fn region(&mut self, index: u8) -> Option<RegionConfigV8> {
+        (**self).region(index)
+    }
+
+    /// See [`MPU::disable_region`].
+    #[inline]
+    pub fn disable_region(&mut self, index: u8) -> Result<(), MpuError> {
+        (**self).disable_region(index)
+    }
+
+    /// Programs Non-secure MPU region `region_index` as a stack guard, see
+    /// [`install_stack_guard`].
+    #[inline]
+    pub fn install_stack_guard(
+        &mut self,
+        guard_start: *const u8,
+        size: GuardSize,
+        region_index: u8,
+    ) -> Result<(), MpuError> {
+        (**self).install_stack_guard(guard_start, size, region_index)
+    }
+}
+
+/// Size of a stack guard region, see [`install_stack_guard`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[allow(missing_docs)]
+pub enum GuardSize {
+    Size32B,
+    Size64B,
+    Size128B,
+    Size256B,
+    Size512B,
+    Size1K,
+    Size2K,
+    Size4K,
+}
+
+impl GuardSize {
+    /// The guard size in bytes.
+    #[inline]
+    pub const fn bytes(self) -> u32 {
+        32 << self as u32
+    }
+}
+
+#[cfg(not(armv8m))]
+impl RegionConfig {
+    /// A no-access, execute-never region covering `size` bytes starting at `start`.
+    ///
+    /// If `start` is aligned to `size`, this is a region of exactly that size. Otherwise a region
+    /// 8 times the size of the largest power of two `start` is aligned to, down to 32 bytes, is
+    /// used, with all subregions outside of the guard disabled. Returns
+    /// [`MpuError::MisalignedBase`] if no such region exists.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub const fn stack_guard(start: u32, size: GuardSize) -> Result<Self, MpuError> {
+        let guard = size.bytes();
+
+        let mut config = RegionConfig {
+            base: start,
+            size: RegionSize::Size32B,
+            access: AccessPermission::NoAccess,
+            // the attributes don't matter for a no-access region
+            attributes: MemoryAttributes::normal_noncacheable(),
+            subregion_disable: 0,
+            execute_never: true,
+        };
+
+        if start % guard == 0 {
+            config.size = match region_size_from_field(guard.trailing_zeros() as u8 - 1) {
+                Some(size) => size,
+                None => return Err(MpuError::MisalignedBase),
+            };
+            return Ok(config);
+        }
+
+        // the guard has to span whole subregions of a single region
+        let mut subregion = guard / 2;
+        while subregion >= 32 && subregion * 8 >= guard {
+            if start % subregion == 0 {
+                let region = subregion * 8;
+                let base = start & !(region - 1);
+                let offset = start - base;
+                if offset + guard <= region {
+                    let first = offset / subregion;
+                    let count = guard / subregion;
+                    let enabled = ((1u32 << count) - 1) << first;
+
+                    config.base = base;
+                    config.size = match region_size_from_field(region.trailing_zeros() as u8 - 1) {
+                        Some(size) => size,
+                        None => return Err(MpuError::MisalignedBase),
+                    };
+                    config.subregion_disable = !(enabled as u8);
+                    return Ok(config);
+                }
+            }
+            subregion /= 2;
+        }
+
+        Err(MpuError::MisalignedBase)
+    }
+}
+
+#[cfg(any(armv8m, native))]
+impl RegionConfigV8 {
+    /// A read-only, execute-never region covering `size` bytes starting at `start`, using the
+    /// memory attributes in `MAIR` slot 0.
+    ///
+    /// PMSAv8 can't deny privileged reads, so the guard only catches writes, and unprivileged
+    /// reads. Stack overflows are caught anyway, as they start with a push. Returns
+    /// [`MpuError::MisalignedBase`] if `start` isn't aligned to 32 bytes.
+    #[inline]
+    pub const fn stack_guard(start: u32, size: GuardSize) -> Result<Self, MpuError> {
+        let config = RegionConfigV8 {
+            base: start,
+            limit: start.wrapping_add(size.bytes() - 1),
+            attr_index: 0,
+            access: AccessPermissionV8::PrivilegedReadOnly,
+            execute_never: true,
+        };
+        match config.validate() {
+            Ok(()) => Ok(config),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Programs MPU region `region_index` as a guard of `size` bytes at `guard_start`, to catch stack
+/// overflows.
+///
+/// On Armv7-M the guard denies all accesses, see [`RegionConfig::stack_guard`] for the alignment
+/// rules. On Armv8-M it denies writes, see [`RegionConfigV8::stack_guard`], and the limit
+/// registers (`MSPLIM`/`PSPLIM`) of Armv8-M Mainline are an alternative. The MPU itself still has
+/// to be [enabled](MPU::enable), typically with the default memory map as background region.
+///
+/// # Placing the guard
+///
+/// With `cortex-m-rt` the stack grows down from `_stack_start` towards the end of the static
+/// data, `__sheap`. The guard goes right after `__sheap`, rounded up to its alignment:
+///
+/// ``` no_run
+/// use cortex_m::peripheral::mpu::{install_stack_guard, GuardSize};
+///
+/// extern "C" {
+///     static __sheap: u8;
+/// }
+///
+/// let mut mpu = cortex_m::Peripherals::take().unwrap().MPU;
+/// let size = GuardSize::Size64B;
+/// let start = unsafe { &__sheap as *const u8 as usize };
+/// let guard = (start + size.bytes() as usize - 1) & !(size.bytes() as usize - 1);
+/// install_stack_guard(&mut mpu, guard as *const u8, size, 0).unwrap();
+/// ```
+///
+/// # Identifying a guard hit
+///
+/// A guard hit raises a MemManage fault (or a HardFault if MemManage is disabled). `MMFSR` then
+/// has `DACCVIOL` and `MMARVALID` set, with `MMFAR` inside the guard. If the overflow happened
+/// while the processor was stacking an exception frame, `MSTKERR` is set instead and `MMFAR` is
+/// not valid. Any other MemManage cause, or a `MMFAR` outside the guard, is not a stack overflow.
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn install_stack_guard(
+    mpu: &mut MPU,
+    guard_start: *const u8,
+    size: GuardSize,
+    region_index: u8,
+) -> Result<(), MpuError> {
+    #[cfg(not(armv8m))]
+    {
+        let config = RegionConfig::stack_guard(guard_start as u32, size)?;
+        mpu.configure_region(region_index, &config)
+    }
+
+    #[cfg(armv8m)]
+    {
+        (**mpu).install_stack_guard(guard_start, size, region_index)
+    }
+}
+
+/// What caused a MemManage fault, see [`diagnose_fault`].
+#[cfg(not(armv6m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FaultCause {
+    /// No MemManage fault is recorded in `MMFSR`.
+    NoFault,
+    /// The fault address is not known, so the regions can't be correlated. This is the case for
+    /// instruction access violations, and faults during exception stacking and unstacking or lazy
+    /// floating-point state preservation. The faulting instruction is the stacked PC.
+    UnknownAddress,
+    /// The MPU is disabled, so the access violated the default memory map, e.g. an instruction
+    /// fetch from an execute-never part of the address space.
+    Background,
+    /// No enabled region covers the address, and `PRIVDEFENA` is clear, so no access there is
+    /// allowed.
+    Gap,
+    /// No enabled region covers the address, but `PRIVDEFENA` is set, so privileged accesses
+    /// there use the default memory map. Either the access was unprivileged, or it was a
+    /// privileged access the default memory map doesn't allow, as for
+    /// [`Background`](FaultCause::Background). The hardware doesn't record which.
+    UnprivilegedOrBackground,
+    /// The address lies in [`FaultDiagnosis::region`], whose access permissions or execute-never
+    /// attribute deny the access.
+    Permission,
+    /// The address is covered by more than one enabled region, which always faults on Armv8-M.
+    Overlap,
+}
+
+/// The result of [`diagnose_fault`].
+///
+/// Implements `Debug`, and `defmt::Format` with the `defmt` feature, for crash reports.
+#[cfg(not(armv6m))]
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FaultDiagnosis {
+    /// The fault status at the time of the diagnosis.
+    pub status: Cfsr,
+    /// The faulting address from `MMFAR`, if valid.
+    pub address: Option<u32>,
+    /// Whether the MPU was enabled.
+    pub mpu_enabled: bool,
+    /// The classification of the fault.
+    pub cause: FaultCause,
+    /// The region that covers the address. With overlapping regions, the highest numbered one.
+    #[cfg(not(armv8m))]
+    pub region: Option<Region<RegionConfig>>,
+    /// The region that covers the address. With overlapping regions, the highest numbered one.
+    #[cfg(armv8m)]
+    pub region: Option<Region<RegionConfigV8>>,
+}
+
+/// Correlates the last MemManage fault with the MPU configuration.
+///
+/// Reads the MemManage status and fault address from `scb`, then walks the regions of `mpu` to
+/// find the ones covering the address. Call this from the MemManage (or HardFault) handler,
+/// before the status is cleared.
+///
+/// The faulting access type isn't recorded by the hardware, so a fault in a region is reported
+/// as [`FaultCause::Permission`] without saying which permission was missing.
+#[cfg(not(armv6m))]
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn diagnose_fault(mpu: &mut MPU, scb: &SCB) -> FaultDiagnosis {
+    let status = scb.fault_status();
+    let address = scb.mem_manage_fault_address();
+    let mpu_enabled = mpu.is_enabled();
+
+    let mut diagnosis = FaultDiagnosis {
+        status,
+        address,
+        mpu_enabled,
+        cause: FaultCause::NoFault,
+        region: None,
+    };
+
+    let mmfsr = status.iaccviol()
+        || status.daccviol()
+        || status.munstkerr()
+        || status.mstkerr()
+        || status.mlsperr();
+    let address = match address {
+        _ if !mmfsr => return diagnosis,
+        Some(address) => address,
+        None => {
+            diagnosis.cause = FaultCause::UnknownAddress;
+            return diagnosis;
+        }
+    };
+    if !mpu_enabled {
+        diagnosis.cause = FaultCause::Background;
+        return diagnosis;
+    }
+
+    let mut matches = 0;
+    for region in mpu.regions() {
+        let covers = match region.config {
+            Some(config) => region.enabled && config.contains(address),
+            None => false,
+        };
+        if covers {
+            matches += 1;
+            diagnosis.region = Some(region);
+        }
+    }
+
+    let privileged_default_map = mpu.ctrl.read() & MPU_CTRL_PRIVDEFENA != 0;
+    diagnosis.cause = match matches {
+        0 if privileged_default_map => FaultCause::UnprivilegedOrBackground,
+        0 => FaultCause::Gap,
+        // on Armv7-M the highest numbered region takes priority
+        _ if cfg!(not(armv8m)) => FaultCause::Permission,
+        1 => FaultCause::Permission,
+        _ => FaultCause::Overlap,
+    };
+    diagnosis
+}
+
+/// The number of regions recorded by an [`MpuDump`].
+pub const MPU_DUMP_REGIONS: usize = 16;
+
+#[cfg(not(armv8m))]
+type DumpConfig = RegionConfig;
+#[cfg(armv8m)]
+type DumpConfig = RegionConfigV8;
+
+/// A snapshot of the MPU configuration, for bug reports and crash logs.
+///
+/// The `Debug` (and `defmt::Format`) output lists `MPU_CTRL` and every region on its own line,
+/// with sizes in human units and the privileged and unprivileged permissions as `rwx` strings:
+///
+/// ```text
+/// MPU enabled PRIVDEFENA, 8 regions
+/// #0: 0x20000000 32KiB priv rw- unpriv r-- tex=1 C B
+/// #1: disabled
+/// ```
+#[derive(Clone)]
+pub struct MpuDump {
+    /// The raw `MPU_CTRL` value.
+    pub ctrl: u32,
+    /// The number of regions the MPU implements.
+    pub region_count: u8,
+    /// The raw `MAIR0` and `MAIR1` values.
+    #[cfg(armv8m)]
+    pub mair: [u32; 2],
+    /// The regions, in order. Only the first `region_count` entries (at most
+    /// [`MPU_DUMP_REGIONS`]) are meaningful, see [`MpuDump::regions`].
+    pub regions: [Region<DumpConfig>; MPU_DUMP_REGIONS],
+}
+
+impl MpuDump {
+    /// Reads the configuration of `mpu`: the [`MPU`] or, from Secure state on Armv8-M, the
+    /// Non-secure MPU returned by `MPU::non_secure`.
+    ///
+    /// Regions past the first [`MPU_DUMP_REGIONS`] are not recorded.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn capture<M>(mpu: &mut M) -> Self
+    where
+        M: core::ops::Deref<Target = RegisterBlock>,
+    {
+        let mpu: &RegisterBlock = mpu;
+        let mut dump = MpuDump {
+            ctrl: mpu.ctrl.read(),
+            region_count: mpu.region_count(),
+            #[cfg(armv8m)]
+            mair: [mpu.mair[0].read(), mpu.mair[1].read()],
+            regions: [Region {
+                index: 0,
+                enabled: false,
+                config: None,
+            }; MPU_DUMP_REGIONS],
+        };
+        for region in mpu.regions().take(MPU_DUMP_REGIONS) {
+            dump.regions[usize::from(region.index)] = region;
+        }
+        dump
+    }
+
+    /// The recorded regions.
+    #[inline]
+    pub fn regions(&self) -> &[Region<DumpConfig>] {
+        let count = usize::from(self.region_count).min(MPU_DUMP_REGIONS);
+        &self.regions[..count]
+    }
+
+    /// The `MAIR` attribute of slot `index`, which must be less than 8.
+    #[cfg(armv8m)]
+    #[inline]
+    pub fn memory_attribute(&self, index: u8) -> MemoryAttribute {
+        let mair = self.mair[usize::from(index / 4)];
+        MemoryAttribute::from_bits((mair >> ((index % 4) * 8)) as u8)
+    }
+
+    fn ctrl_flags(&self) -> (&'static str, &'static str, &'static str) {
+        (
+            if self.ctrl & MPU_CTRL_ENABLE != 0 {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            if self.ctrl & MPU_CTRL_PRIVDEFENA != 0 {
+                " PRIVDEFENA"
+            } else {
+                ""
+            },
+            if self.ctrl & MPU_CTRL_HFNMIENA != 0 {
+                " HFNMIENA"
+            } else {
+                ""
+            },
+        )
+    }
+}
+
+/// Renders read, write and execute permissions as a `rwx` string, e.g. `r-x`.
+const fn rwx(read: bool, write: bool, execute: bool) -> &'static str {
+    const STRS: [&str; 8] = ["---", "--x", "-w-", "-wx", "r--", "r-x", "rw-", "rwx"];
+    STRS[(read as usize) << 2 | (write as usize) << 1 | execute as usize]
+}
+
+/// The privileged and unprivileged `rwx` strings of a region.
+///
+/// `access` is the (privileged, unprivileged) read and write permissions. Instruction fetches
+/// need read permission.
+const fn permission_strs(access: [(bool, bool); 2], execute_never: bool) -> [&'static str; 2] {
+    let (pr, pw) = access[0];
+    let (ur, uw) = access[1];
+    [
+        rwx(pr, pw, pr && !execute_never),
+        rwx(ur, uw, ur && !execute_never),
+    ]
+}
+
+#[cfg(not(armv8m))]
+impl AccessPermission {
+    /// (privileged, unprivileged) read and write permissions.
+    const fn read_write(self) -> [(bool, bool); 2] {
+        match self {
+            AccessPermission::NoAccess => [(false, false), (false, false)],
+            AccessPermission::PrivilegedReadWrite => [(true, true), (false, false)],
+            AccessPermission::PrivilegedReadWriteUnprivilegedReadOnly => {
+                [(true, true), (true, false)]
+            }
+            AccessPermission::ReadWrite => [(true, true), (true, true)],
+            AccessPermission::PrivilegedReadOnly => [(true, false), (false, false)],
+            AccessPermission::ReadOnly => [(true, false), (true, false)],
+        }
+    }
+}
+
+#[cfg(armv8m)]
+impl AccessPermissionV8 {
+    /// (privileged, unprivileged) read and write permissions.
+    const fn read_write(self) -> [(bool, bool); 2] {
+        match self {
+            AccessPermissionV8::PrivilegedReadWrite => [(true, true), (false, false)],
+            AccessPermissionV8::ReadWrite => [(true, true), (true, true)],
+            AccessPermissionV8::PrivilegedReadOnly => [(true, false), (false, false)],
+            AccessPermissionV8::ReadOnly => [(true, false), (true, false)],
+        }
+    }
+}
+
+/// A size in bytes, rendered in the largest binary unit that divides it, e.g. `32KiB`.
+#[derive(Copy, Clone)]
+struct HumanSize(u64);
+
+impl HumanSize {
+    const fn split(self) -> (u64, &'static str) {
+        const UNITS: [(u32, &str); 3] = [(30, "GiB"), (20, "MiB"), (10, "KiB")];
+        let mut i = 0;
+        while i < UNITS.len() {
+            let (shift, unit) = UNITS[i];
+            if self.0 != 0 && self.0 & ((1 << shift) - 1) == 0 {
+                return (self.0 >> shift, unit);
+            }
+            i += 1;
+        }
+        (self.0, "B")
+    }
+}
+
+impl core::fmt::Display for HumanSize {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (value, unit) = self.split();
+        write!(f, "{}{}", value, unit)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for HumanSize {
+    fn format(&self, f: defmt::Formatter) {
+        let (value, unit) = self.split();
+        defmt::write!(f, "{=u64}{=str}", value, unit)
+    }
+}
+
+#[cfg(not(armv8m))]
+impl core::fmt::Debug for MpuDump {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (enabled, privdefena, hfnmiena) = self.ctrl_flags();
+        write!(
+            f,
+            "MPU {}{}{}, {} regions",
+            enabled, privdefena, hfnmiena, self.region_count
+        )?;
+        for region in self.regions() {
+            write!(f, "\n#{}: ", region.index)?;
+            let config = match (region.enabled, region.config) {
+                (false, _) => {
+                    f.write_str("disabled")?;
+                    continue;
+                }
+                (true, None) => {
+                    f.write_str("reserved encoding")?;
+                    continue;
+                }
+                (true, Some(config)) => config,
+            };
+            let [privileged, unprivileged] =
+                permission_strs(config.access.read_write(), config.execute_never);
+            let attributes = config.attributes;
+            write!(
+                f,
+                "{:#010x} {} priv {} unpriv {} tex={}{}{}{}",
+                config.base,
+                HumanSize(config.size.bytes()),
+                privileged,
+                unprivileged,
+                attributes.tex,
+                if attributes.cacheable { " C" } else { "" },
+                if attributes.bufferable { " B" } else { "" },
+                if attributes.shareable { " S" } else { "" },
+            )?;
+            if config.subregion_disable != 0 {
+                write!(f, " srd={:#010b}", config.subregion_disable)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(not(armv8m), feature = "defmt"))]
+impl defmt::Format for MpuDump {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn format(&self, f: defmt::Formatter) {
+        let (enabled, privdefena, hfnmiena) = self.ctrl_flags();
+        defmt::write!(
+            f,
+            "MPU {=str}{=str}{=str}, {=u8} regions",
+            enabled,
+            privdefena,
+            hfnmiena,
+            self.region_count
+        );
+        for region in self.regions() {
+            defmt::write!(f, "\n#{=u8}: ", region.index);
+            let config = match (region.enabled, region.config) {
+                (false, _) => {
+                    defmt::write!(f, "disabled");
+                    continue;
+                }
+                (true, None) => {
+                    defmt::write!(f, "reserved encoding");
+                    continue;
+                }
+                (true, Some(config)) => config,
+            };
+            let [privileged, unprivileged] =
+                permission_strs(config.access.read_write(), config.execute_never);
+            let attributes = config.attributes;
+            defmt::write!(
+                f,
+                "{=u32:#010x} {} priv {=str} unpriv {=str} tex={=u8}{=str}{=str}{=str}",
+                config.base,
+                HumanSize(config.size.bytes()),
+                privileged,
+                unprivileged,
+                attributes.tex,
+                if attributes.cacheable { " C" } else { "" },
+                if attributes.bufferable { " B" } else { "" },
+                if attributes.shareable { " S" } else { "" },
+            );
+            if config.subregion_disable != 0 {
+                defmt::write!(f, " srd={=u8:#010b}", config.subregion_disable);
+            }
+        }
+    }
+}
+
+#[cfg(armv8m)]
+impl core::fmt::Debug for MpuDump {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (enabled, privdefena, hfnmiena) = self.ctrl_flags();
+        write!(
+            f,
+            "MPU {}{}{}, {} regions",
+            enabled, privdefena, hfnmiena, self.region_count
+        )?;
+        for region in self.regions() {
+            write!(f, "\n#{}: ", region.index)?;
+            let config = match (region.enabled, region.config) {
+                (true, Some(config)) => config,
+                _ => {
+                    f.write_str("disabled")?;
+                    continue;
+                }
+            };
+            let [privileged, unprivileged] =
+                permission_strs(config.access.read_write(), config.execute_never);
+            write!(
+                f,
+                "{:#010x}..={:#010x} {} priv {} unpriv {} attr {} ({:#04x})",
+                config.base,
+                config.limit,
+                HumanSize(u64::from(config.limit.wrapping_sub(config.base)) + 1),
+                privileged,
+                unprivileged,
+                config.attr_index,
+                self.memory_attribute(config.attr_index).bits(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(armv8m, feature = "defmt"))]
+impl defmt::Format for MpuDump {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn format(&self, f: defmt::Formatter) {
+        let (enabled, privdefena, hfnmiena) = self.ctrl_flags();
+        defmt::write!(
+            f,
+            "MPU {=str}{=str}{=str}, {=u8} regions",
+            enabled,
+            privdefena,
+            hfnmiena,
+            self.region_count
+        );
+        for region in self.regions() {
+            defmt::write!(f, "\n#{=u8}: ", region.index);
+            let config = match (region.enabled, region.config) {
+                (true, Some(config)) => config,
+                _ => {
+                    defmt::write!(f, "disabled");
+                    continue;
+                }
+            };
+            let [privileged, unprivileged] =
+                permission_strs(config.access.read_write(), config.execute_never);
+            defmt::write!(
+                f,
+                "{=u32:#010x}..={=u32:#010x} {} priv {=str} unpriv {=str} attr {=u8} ({=u8:#04x})",
+                config.base,
+                config.limit,
+                HumanSize(u64::from(config.limit.wrapping_sub(config.base)) + 1),
+                privileged,
+                unprivileged,
+                config.attr_index,
+                self.memory_attribute(config.attr_index).bits(),
+            );
+        }
+    }
+}
@@ -82,6 +82,78 @@ pub struct RegisterBlock {
     pub stir: WO<u32>,
 }
 
+/// An interrupt priority, as written to an Interrupt Priority Register
+///
+/// NVIC priorities live in the most significant bits of a byte, and a device only implements
+/// some of them: with 4 implemented bits, the logical levels `0` to `15` are written as `0x00`,
+/// `0x10`, ..., `0xF0`. Passing a logical level where the shifted byte is expected is a common
+/// mistake, which this type rules out: construct it with [`Priority::from_level`] from a level
+/// and the number of implemented bits, or with [`Priority::from_raw`] from a byte that is
+/// already shifted. Lower values are more urgent.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Priority(u8);
+
+impl Priority {
+    /// The most urgent priority, `0`.
+    pub const HIGHEST: Priority = Priority(0);
+
+    /// Creates a priority from a logical `level` of a device implementing `bits` priority bits.
+    ///
+    /// `bits` is usually a constant from the device crate (`NVIC_PRIO_BITS`), or can be probed at
+    /// runtime with [`SCB::priority_bits`](crate::peripheral::SCB::priority_bits).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` isn't between 1 and 8, or if `level` doesn't fit in `bits` bits.
+    #[inline]
+    pub const fn from_level(level: u8, bits: u8) -> Self {
+        assert!(bits >= 1 && bits <= 8, "invalid number of priority bits");
+        assert!(
+            (level as u16) < 1 << bits,
+            "level doesn't fit in the priority bits"
+        );
+        Priority(((level as u16) << (8 - bits)) as u8)
+    }
+
+    /// Creates a priority from the raw byte of an Interrupt Priority Register.
+    ///
+    /// The bits the device doesn't implement are ignored by the hardware.
+    #[inline]
+    pub const fn from_raw(raw: u8) -> Self {
+        Priority(raw)
+    }
+
+    /// Returns the byte to write to an Interrupt Priority Register.
+    #[inline]
+    pub const fn raw(self) -> u8 {
+        self.0
+    }
+
+    /// Returns the logical level of this priority on a device implementing `bits` priority bits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` isn't between 1 and 8.
+    #[inline]
+    pub const fn level(self, bits: u8) -> u8 {
+        assert!(bits >= 1 && bits <= 8, "invalid number of priority bits");
+        ((self.0 as u16) >> (8 - bits)) as u8
+    }
+}
+
+/// Treats the byte as the raw Interrupt Priority Register value, like [`Priority::from_raw`]
+///
+/// This only exists so that [`NVIC::set_priority`] keeps accepting a `u8`, which is deprecated:
+/// use [`Priority::from_level`] or [`Priority::from_raw`] instead. (`#[deprecated]` has no effect
+/// on trait impls, so the compiler can't warn about it.)
+impl From<u8> for Priority {
+    #[inline]
+    fn from(raw: u8) -> Self {
+        Priority::from_raw(raw)
+    }
+}
+
 impl NVIC {
     /// Request an IRQ in software
     ///
@@ -206,10 +278,24 @@ impl NVIC {
         unsafe { (*Self::PTR).ispr[usize::from(nr / 32)].write(1 << (nr % 32)) }
     }
 
-    /// Sets the "priority" of `interrupt` to `prio`
+    /// Returns the priority of `interrupt`
+    ///
+    /// This is [`get_priority`](NVIC::get_priority) as a [`Priority`].
+    #[inline]
+    pub fn priority<I>(interrupt: I) -> Priority
+    where
+        I: InterruptNumber,
+    {
+        Priority::from_raw(Self::get_priority(interrupt))
+    }
+
+    /// Sets the priority of `interrupt` to `prio`
+    ///
+    /// See [`Priority`] for how NVIC priorities work, e.g.
+    /// `Priority::from_level(2, 4)` for level 2 of 16 on a device with 4 priority bits.
     ///
-    /// *NOTE* See [`get_priority`](struct.NVIC.html#method.get_priority) method for an explanation
-    /// of how NVIC priorities work.
+    /// A raw `u8` is still accepted, as the byte written to the Interrupt Priority Register, but
+    /// this is deprecated: it's easily given an unshifted level.
     ///
     /// On ARMv6-M, updating an interrupt priority requires a read-modify-write operation. On
     /// ARMv7-M, the operation is performed in a single atomic write operation.
@@ -219,10 +305,13 @@ impl NVIC {
     /// Changing priority levels can break priority-based critical sections (see
     /// [`register::basepri`](crate::register::basepri)) and compromise memory safety.
     #[inline]
-    pub unsafe fn set_priority<I>(&mut self, interrupt: I, prio: u8)
+    pub unsafe fn set_priority<I, P>(&mut self, interrupt: I, prio: P)
     where
         I: InterruptNumber,
+        P: Into<Priority>,
     {
+        let prio = prio.into().raw();
+
         #[cfg(not(armv6m))]
         {
             let nr = interrupt.number();
@@ -240,6 +329,42 @@ impl NVIC {
         }
     }
 
+    /// Sets the priority of `interrupt` to `prio`, then enables it
+    ///
+    /// This is the usual way to set up an interrupt at init time. The order matters: an
+    /// interrupt that is enabled first can fire, if it is already pending, at its reset priority
+    /// (`0`, the most urgent) before the priority is set, and preempt code that it shouldn't. The
+    /// NVIC registers are Strongly-ordered memory, so the priority write takes effect before the
+    /// interrupt is enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use cortex_m::interrupt::InterruptNumber;
+    /// use cortex_m::peripheral::nvic::Priority;
+    /// # #[derive(Clone, Copy)]
+    /// # struct Uart0;
+    /// # unsafe impl InterruptNumber for Uart0 {
+    /// #     fn number(self) -> u16 { 0 }
+    /// # }
+    ///
+    /// let mut p = cortex_m::Peripherals::take().unwrap();
+    /// // level 2 of 16 on a device with 4 priority bits
+    /// unsafe { p.NVIC.configure(Uart0, Priority::from_level(2, 4)) };
+    /// ```
+    ///
+    /// # Unsafety
+    ///
+    /// See [`set_priority`](NVIC::set_priority) and [`unmask`](NVIC::unmask).
+    #[inline]
+    pub unsafe fn configure<I>(&mut self, interrupt: I, prio: Priority)
+    where
+        I: InterruptNumber,
+    {
+        self.set_priority(interrupt, prio);
+        Self::unmask(interrupt);
+    }
+
     /// Clears `interrupt`'s pending state
     #[inline]
     pub fn unpend<I>(interrupt: I)
@@ -252,6 +377,54 @@ impl NVIC {
         unsafe { (*Self::PTR).icpr[usize::from(nr / 32)].write(1 << (nr % 32)) }
     }
 
+    /// Clears `interrupt`'s pending state, and waits until the clear has taken effect
+    ///
+    /// "Pending" is NVIC state, not peripheral state: the NVIC latches a request when it sees the
+    /// interrupt line asserted (or on a [`pend`](NVIC::pend)), and doesn't know whether the source
+    /// is level or pulse triggered. For a level-sensitive source that still asserts its line, the
+    /// NVIC sets the pending state again right after it is cleared, so clear the condition in the
+    /// peripheral first. For a pulse, clearing discards the latched request, including one that
+    /// arrived just before the clear.
+    ///
+    /// Unlike [`unpend`](NVIC::unpend), this is followed by a `DSB` and an `ISB`, so once it
+    /// returns the interrupt can no longer be taken because of the discarded request, e.g. when
+    /// interrupts are re-enabled right afterwards. Clearing the pending state doesn't affect a
+    /// handler that is already active or was dispatched before the clear; see
+    /// [`clear_pending_if_not_active`](NVIC::clear_pending_if_not_active).
+    #[inline]
+    pub fn clear_pending<I>(interrupt: I)
+    where
+        I: InterruptNumber,
+    {
+        Self::unpend(interrupt);
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+
+    /// Clears `interrupt`'s pending state unless its handler is active
+    ///
+    /// Returns `true` if the pending state was cleared, and `false` if the handler is active or
+    /// preempted. The check and the clear run in a critical section, so the interrupt can't be
+    /// taken in between. See [`clear_pending`](NVIC::clear_pending) for what the pending state
+    /// means.
+    ///
+    /// This method is not available on ARMv6-M chips, which don't implement the active bits.
+    #[cfg(not(armv6m))]
+    #[inline]
+    pub fn clear_pending_if_not_active<I>(interrupt: I) -> bool
+    where
+        I: InterruptNumber,
+    {
+        crate::interrupt::free(|| {
+            if Self::is_active(interrupt) {
+                false
+            } else {
+                Self::clear_pending(interrupt);
+                true
+            }
+        })
+    }
+
     #[cfg(armv6m)]
     #[inline]
     fn ipr_index<I>(interrupt: I) -> usize
@@ -1,16 +1,19 @@
 //! System Control Block
 
+#[cfg(not(armv6m))]
+use core::fmt;
 use core::ptr;
 
 use volatile_register::RW;
 
 #[cfg(not(armv6m))]
-use super::cpuid::CsselrCacheType;
+use super::cpuid::{set_way_iter, CacheDescription, CacheKind, CsselrCacheType, SetWay};
 #[cfg(not(armv6m))]
 use super::CBP;
 #[cfg(not(armv6m))]
 use super::CPUID;
 use super::SCB;
+use bitfield::bitfield;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -166,6 +169,84 @@ impl SCB {
     }
 }
 
+/// Coprocessor access rights, see [`SCB::set_coprocessor_access`]
+#[cfg(not(armv6m))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CpAccess {
+    /// Any access faults
+    Denied = 0b00,
+    /// Accessible in Privileged mode only
+    Privileged = 0b01,
+    /// Accessible in Privileged and User mode
+    Full = 0b11,
+}
+
+/// Returns `cpacr` with the access rights of coprocessor `cp` set to `access`.
+#[cfg(not(armv6m))]
+pub(crate) const fn cpacr_with_access(cpacr: u32, cp: u8, access: CpAccess) -> u32 {
+    let shift = 2 * cp as u32;
+    (cpacr & !(0b11 << shift)) | ((access as u32) << shift)
+}
+
+/// Returns the access rights of coprocessor `cp` in `cpacr`.
+#[cfg(not(armv6m))]
+pub(crate) const fn cpacr_access(cpacr: u32, cp: u8) -> CpAccess {
+    match (cpacr >> (2 * cp as u32)) & 0b11 {
+        0b01 => CpAccess::Privileged,
+        0b11 => CpAccess::Full,
+        // 0b10 is reserved and behaves as `Denied`
+        _ => CpAccess::Denied,
+    }
+}
+
+#[cfg(not(armv6m))]
+impl SCB {
+    /// Sets the access rights of coprocessor `cp`, CP0 to CP7
+    ///
+    /// These coprocessors are implementation defined, e.g. the Arm Custom Datapath Extension
+    /// (CDE) or a vendor coprocessor: they have to be enabled here before use. The FPU is CP10 and
+    /// CP11, see [`set_fpu_access_mode`](SCB::set_fpu_access_mode).
+    ///
+    /// Executing an instruction for a coprocessor without access rights, or one that isn't
+    /// implemented, raises a UsageFault with [`Cfsr::nocp`] set, escalated to HardFault if the
+    /// UsageFault exception is disabled. The write is followed by a barrier, so the new access
+    /// rights apply to the instructions after this call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cp` is greater than 7.
+    #[inline]
+    pub fn set_coprocessor_access(&mut self, cp: u8, access: CpAccess) {
+        assert!(
+            cp <= 7,
+            "only CP0 to CP7 are implementation defined coprocessors"
+        );
+        let cpacr = cpacr_with_access(self.cpacr.read(), cp, access);
+        unsafe { self.cpacr.write(cpacr) };
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+
+    /// Returns the access rights of coprocessor `cp`, CP0 to CP7
+    ///
+    /// The access rights of a coprocessor that isn't implemented read as
+    /// [`CpAccess::Denied`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cp` is greater than 7.
+    #[inline]
+    pub fn coprocessor_access(cp: u8) -> CpAccess {
+        assert!(
+            cp <= 7,
+            "only CP0 to CP7 are implementation defined coprocessors"
+        );
+        // NOTE(unsafe) atomic read with no side effects
+        cpacr_access(unsafe { (*Self::PTR).cpacr.read() }, cp)
+    }
+}
+
 impl SCB {
     /// Returns the active exception number
     #[inline]
@@ -241,7 +322,7 @@ impl Exception {
     ///
     /// The return value is always within the closed range `[-1, -14]`
     #[inline]
-    pub fn irqn(self) -> i8 {
+    pub const fn irqn(self) -> i8 {
         match self {
             Exception::NonMaskableInt => -14,
             Exception::HardFault => -13,
@@ -316,6 +397,26 @@ mod scb_consts {
 #[cfg(not(armv6m))]
 use self::scb_consts::*;
 
+/// Returns the set/way pairs of the L1 data cache, none if there is no data cache.
+///
+/// Only the L1 data cache CCSIDR is selected and read, not the whole
+/// [`cache_hierarchy`](CPUID::cache_hierarchy): this is on the path of every whole-cache
+/// maintenance operation.
+#[cfg(not(armv6m))]
+#[inline]
+fn l1_dcache_set_ways(cpuid: &mut CPUID) -> impl Iterator<Item = SetWay> {
+    // CLIDR.Ctype1: 0b010 data only, 0b011 separate instruction and data, 0b100 unified
+    let dcache = match cpuid.clidr.read() & 0b111 {
+        0b010..=0b100 => Some(cpuid.with_cache_selected(
+            0,
+            CsselrCacheType::DataOrUnified,
+            |ccsidr| CacheDescription::decode(1, CacheKind::Data, ccsidr.bits()),
+        )),
+        _ => None,
+    };
+    dcache.into_iter().flat_map(|dcache| set_way_iter(&dcache))
+}
+
 #[cfg(not(armv6m))]
 impl SCB {
     /// Enables I-cache if currently disabled.
@@ -461,16 +562,11 @@ impl SCB {
     #[inline]
     unsafe fn invalidate_dcache(&mut self, cpuid: &mut CPUID) {
         // NOTE(unsafe): No races as all CBP registers are write-only and stateless
-        let mut cbp = CBP::new();
-
-        // Read number of sets and ways
-        let (sets, ways) = cpuid.cache_num_sets_ways(0, CsselrCacheType::DataOrUnified);
+        let cbp = CBP::new();
 
         // Invalidate entire D-cache
-        for set in 0..sets {
-            for way in 0..ways {
-                cbp.dcisw(set, way);
-            }
+        for set_way in l1_dcache_set_ways(cpuid) {
+            cbp.dcisw.write(set_way.encode());
         }
 
         crate::asm::dsb();
@@ -484,15 +580,10 @@ impl SCB {
     #[inline]
     pub fn clean_dcache(&mut self, cpuid: &mut CPUID) {
         // NOTE(unsafe): No races as all CBP registers are write-only and stateless
-        let mut cbp = unsafe { CBP::new() };
-
-        // Read number of sets and ways
-        let (sets, ways) = cpuid.cache_num_sets_ways(0, CsselrCacheType::DataOrUnified);
+        let cbp = unsafe { CBP::new() };
 
-        for set in 0..sets {
-            for way in 0..ways {
-                cbp.dccsw(set, way);
-            }
+        for set_way in l1_dcache_set_ways(cpuid) {
+            unsafe { cbp.dccsw.write(set_way.encode()) };
         }
 
         crate::asm::dsb();
@@ -507,15 +598,10 @@ impl SCB {
     #[inline]
     pub fn clean_invalidate_dcache(&mut self, cpuid: &mut CPUID) {
         // NOTE(unsafe): No races as all CBP registers are write-only and stateless
-        let mut cbp = unsafe { CBP::new() };
+        let cbp = unsafe { CBP::new() };
 
-        // Read number of sets and ways
-        let (sets, ways) = cpuid.cache_num_sets_ways(0, CsselrCacheType::DataOrUnified);
-
-        for set in 0..sets {
-            for way in 0..ways {
-                cbp.dccisw(set, way);
-            }
+        for set_way in l1_dcache_set_ways(cpuid) {
+            unsafe { cbp.dccisw.write(set_way.encode()) };
         }
 
         crate::asm::dsb();
@@ -836,6 +922,10 @@ const SCB_SCR_SEVONPEND: u32 = 0x1 << 4;
 
 impl SCB {
     /// Set the SEVONPEND bit in the SCR register
+    ///
+    /// With `SEVONPEND` set, any interrupt that transitions to pending, including disabled ones and
+    /// ones whose priority doesn't allow them to preempt, sets the event register and so wakes up
+    /// [`wfe`](crate::asm::wfe). See [`send_event`](crate::asm::send_event).
     #[inline]
     pub fn set_sevonpend(&mut self) {
         unsafe {
@@ -852,6 +942,84 @@ impl SCB {
     }
 }
 
+bitfield! {
+    /// System Control Register, see [`SCB::scr`] and [`SCB::set_scr`].
+    ///
+    /// # Sleep and wake-up
+    ///
+    /// `SLEEPDEEP` selects what [`wfi`](crate::asm::wfi) and [`wfe`](crate::asm::wfe) enter:
+    /// sleep, where the core clock stops but the NVIC keeps running, or deep sleep, where the
+    /// chip may also stop the clocks and power of the NVIC. What deep sleep turns off is
+    /// vendor-specific, see the reference manual of the part.
+    ///
+    /// Which interrupts end the sleep depends on the instruction, not on `SLEEPDEEP`:
+    ///
+    /// - `wfi` returns when an interrupt becomes pending that has enough priority to preempt the
+    ///   current execution, with `PRIMASK` ignored. An interrupt masked by `BASEPRI`, or of a lower
+    ///   priority than the running handler, stays pending and doesn't wake the core.
+    /// - `wfe` returns on an event. With `SEVONPEND` set, every interrupt that *transitions* to
+    ///   pending is an event, including disabled interrupts and interrupts masked by `BASEPRI`.
+    ///   An interrupt that is already pending doesn't raise a new event.
+    ///
+    /// In deep sleep the NVIC may not be clocked to notice the interrupt at all. Parts that
+    /// implement a Wake-up Interrupt Controller (WIC) hand the set of enabled interrupts to it on
+    /// the way in, and the WIC restarts the core when one of them is signalled; the rules above
+    /// are then applied as usual. The WIC only knows the enabled interrupts and not their
+    /// priorities, and it doesn't know about disabled ones: so in deep sleep, a disabled
+    /// interrupt may not wake `wfe` even with `SEVONPEND` set, and whether it does depends on the
+    /// part. Keep the interrupts meant to wake the core enabled in the NVIC, and mask them with
+    /// `BASEPRI` or `PRIMASK` instead if they must not run.
+    ///
+    /// `SLEEPONEXIT` makes the core go back to sleep, as if `wfi` was executed, when it returns
+    /// from the last active handler to Thread mode.
+    #[repr(C)]
+    #[derive(Clone, Copy, PartialEq, Eq, Default)]
+    pub struct Scr(u32);
+    impl Debug;
+    /// Sleep again on return from the last active handler to Thread mode (`SLEEPONEXIT`)
+    pub sleeponexit, set_sleeponexit: 1;
+    /// Enter deep sleep instead of sleep (`SLEEPDEEP`)
+    pub sleepdeep, set_sleepdeep: 2;
+    /// Raise an event whenever an interrupt becomes pending (`SEVONPEND`)
+    pub sevonpend, set_sevonpend: 4;
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Scr {
+    #[inline]
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Scr({=u32:#04x})", self.0)
+    }
+}
+
+impl SCB {
+    /// Reads the System Control Register.
+    #[inline]
+    pub fn scr(&self) -> Scr {
+        Scr(self.scr.read())
+    }
+
+    /// Writes all fields of the System Control Register at once.
+    ///
+    /// Unlike the `set_*`/`clear_*` functions of the individual bits, this configures sleep with
+    /// a single store, so the register never holds a mix of the old and the new settings. Bits
+    /// that `Scr` has no field for are written as they are in `scr`, so start from
+    /// [`SCB::scr`] to keep them.
+    ///
+    /// ```no_run
+    /// # use cortex_m::peripheral::Peripherals;
+    /// # let mut scb = Peripherals::take().unwrap().SCB;
+    /// let mut scr = scb.scr();
+    /// scr.set_sleepdeep(true);
+    /// scr.set_sevonpend(true);
+    /// scb.set_scr(scr);
+    /// ```
+    #[inline]
+    pub fn set_scr(&mut self, scr: Scr) {
+        unsafe { self.scr.write(scr.0) }
+    }
+}
+
 const SCB_AIRCR_VECTKEY: u32 = 0x05FA << 16;
 const SCB_AIRCR_PRIGROUP_MASK: u32 = 0x7 << 8;
 const SCB_AIRCR_SYSRESETREQ: u32 = 1 << 2;
@@ -878,6 +1046,129 @@ impl SCB {
     }
 }
 
+/// Possible error values returned when relocating the vector table.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[non_exhaustive]
+pub enum VectorTableError {
+    /// The number of entries is not in the architecturally valid range of 16 to 512.
+    InvalidEntryCount,
+    /// The destination is too small to hold the requested number of entries.
+    DestinationTooSmall,
+    /// The table address does not meet the VTOR alignment requirement, see
+    /// [`vector_table_alignment`].
+    Misaligned,
+}
+
+/// Returns the alignment, in bytes, that VTOR requires for a vector table of `num_entries` words
+///
+/// The table has to be aligned to its size rounded up to the next power of two, and to at least
+/// 128 bytes (32 entries).
+#[inline]
+pub const fn vector_table_alignment(num_entries: usize) -> usize {
+    let size = (num_entries * 4).next_power_of_two();
+    if size < 128 {
+        128
+    } else {
+        size
+    }
+}
+
+impl SCB {
+    /// Copies the active vector table to RAM and points VTOR at the copy
+    ///
+    /// The first `num_entries` words of the table VTOR currently points at are copied into `dest`,
+    /// then VTOR is set to `dest`, followed by `DSB` and `ISB` barriers. Individual handlers can
+    /// afterwards be redirected by writing to `dest`.
+    ///
+    /// `num_entries` is the number of words of the table: 16 system entries plus one per
+    /// external interrupt. `dest` must be at least that long and must be aligned to
+    /// [`vector_table_alignment(num_entries)`](vector_table_alignment), e.g. by placing it in a
+    /// `#[repr(align(N))]` wrapper. Otherwise an error is returned and nothing is changed.
+    ///
+    /// # Safety
+    ///
+    /// VTOR must currently point at a valid vector table of at least `num_entries` entries. The
+    /// copy is identical to the original, so interrupts taken during the switch are dispatched
+    /// correctly, but any entries written to `dest` later must be valid handlers.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub unsafe fn relocate_vector_table_to_ram(
+        &mut self,
+        dest: &'static mut [u32],
+        num_entries: usize,
+    ) -> Result<(), VectorTableError> {
+        if !(16..=512).contains(&num_entries) {
+            return Err(VectorTableError::InvalidEntryCount);
+        }
+        if dest.len() < num_entries {
+            return Err(VectorTableError::DestinationTooSmall);
+        }
+        let addr = dest.as_mut_ptr() as usize;
+        if addr % vector_table_alignment(num_entries) != 0 {
+            return Err(VectorTableError::Misaligned);
+        }
+
+        let src = self.vtor.read() as *const u32;
+        ptr::copy_nonoverlapping(src, dest.as_mut_ptr(), num_entries);
+
+        // make sure the copy has completed before the table is used
+        crate::asm::dsb();
+        self.vtor.write(addr as u32);
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        Ok(())
+    }
+
+    /// Returns the address of the active vector table, read from VTOR
+    #[inline]
+    pub fn active_vector_table() -> *const u32 {
+        // NOTE(unsafe) atomic read with no side effects
+        unsafe { (*Self::PTR).vtor.read() as *const u32 }
+    }
+
+    /// Points VTOR at the vector table at `new`, e.g. to switch between a table in flash and a
+    /// copy in RAM made by [`relocate_vector_table_to_ram`](SCB::relocate_vector_table_to_ram)
+    ///
+    /// `new` must be aligned to [`vector_table_alignment(num_entries)`](vector_table_alignment),
+    /// where `num_entries` is the number of words of the table, otherwise an error is returned
+    /// and VTOR is left unchanged. The write happens in a critical section, preceded by a `DSB`
+    /// so that earlier writes to the new table are complete, and followed by `DSB` and `ISB`
+    /// barriers so that exceptions taken afterwards use the new table.
+    ///
+    /// The critical section only makes the switch itself atomic. An exception that is already
+    /// being taken, or that is pending and taken right after the critical section, may have its
+    /// handler fetched from either table, so both tables should dispatch the exceptions that
+    /// can occur around the switch in a compatible way, or those exceptions should be masked
+    /// for its duration.
+    ///
+    /// # Safety
+    ///
+    /// `new` must point at a valid vector table of at least `num_entries` entries, which stays
+    /// valid for as long as VTOR points at it.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub unsafe fn switch_vector_table(
+        &mut self,
+        new: *const u32,
+        num_entries: usize,
+    ) -> Result<(), VectorTableError> {
+        if !(16..=512).contains(&num_entries) {
+            return Err(VectorTableError::InvalidEntryCount);
+        }
+        if new as usize % vector_table_alignment(num_entries) != 0 {
+            return Err(VectorTableError::Misaligned);
+        }
+
+        crate::interrupt::free(|| {
+            crate::asm::dsb();
+            self.vtor.write(new as u32);
+            crate::asm::dsb();
+            crate::asm::isb();
+        });
+
+        Ok(())
+    }
+}
+
 const SCB_ICSR_PENDSVSET: u32 = 1 << 28;
 const SCB_ICSR_PENDSVCLR: u32 = 1 << 27;
 
@@ -1018,6 +1309,33 @@ impl SCB {
     /// [`register::basepri`](crate::register::basepri)) and compromise memory safety.
     #[inline]
     pub unsafe fn set_priority(&mut self, system_handler: SystemHandler, prio: u8) {
+        Self::write_priority(system_handler, prio)
+    }
+
+    /// Returns the number of priority bits implemented by the device
+    ///
+    /// Priorities use the most significant bits of the 8-bit priority fields, and the bits that
+    /// aren't implemented read as zero. This writes `0xFF` to the PendSV priority, reads it back
+    /// and counts the bits that stuck, so it doesn't depend on the NVIC: it works before any
+    /// interrupt is configured, and on devices without external interrupts. The original PendSV
+    /// priority is saved before the probe and written back after it, with interrupts disabled
+    /// throughout, so no handler runs with the probe value.
+    #[inline]
+    pub fn priority_bits() -> u8 {
+        crate::interrupt::free(|| {
+            let previous = Self::get_priority(SystemHandler::PendSV);
+            // NOTE(unsafe) the original priority is restored before interrupts are enabled again
+            unsafe {
+                Self::write_priority(SystemHandler::PendSV, 0xFF);
+                let probed = Self::get_priority(SystemHandler::PendSV);
+                Self::write_priority(SystemHandler::PendSV, previous);
+                probed.count_ones() as u8
+            }
+        })
+    }
+
+    #[inline]
+    unsafe fn write_priority(system_handler: SystemHandler, prio: u8) {
         let index = system_handler as u8;
 
         #[cfg(not(armv6m))]
@@ -1122,3 +1440,262 @@ impl SCB {
         }
     }
 }
+
+#[cfg(not(armv6m))]
+bitfield! {
+    /// Configurable Fault Status Register, the combined MemManage (`MMFSR`), BusFault (`BFSR`) and
+    /// UsageFault (`UFSR`) status registers.
+    #[repr(C)]
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct Cfsr(u32);
+    impl Debug;
+    /// MemManage Fault Status Register
+    pub u8, mmfsr, _: 7, 0;
+    /// Instruction access violation
+    pub iaccviol, _: 0;
+    /// Data access violation, the address is in `MMFAR`
+    pub daccviol, _: 1;
+    /// MemManage fault on unstacking for a return from exception
+    pub munstkerr, _: 3;
+    /// MemManage fault on stacking for exception entry
+    pub mstkerr, _: 4;
+    /// MemManage fault during lazy floating-point state preservation
+    pub mlsperr, _: 5;
+    /// `MMFAR` holds a valid fault address
+    pub mmarvalid, _: 7;
+    /// BusFault Status Register
+    pub u8, bfsr, _: 15, 8;
+    /// Instruction bus error
+    pub ibuserr, _: 8;
+    /// Precise data bus error, the address is in `BFAR`
+    pub preciserr, _: 9;
+    /// Imprecise data bus error
+    pub impreciserr, _: 10;
+    /// BusFault on unstacking for a return from exception
+    pub unstkerr, _: 11;
+    /// BusFault on stacking for exception entry
+    pub stkerr, _: 12;
+    /// BusFault during lazy floating-point state preservation
+    pub lsperr, _: 13;
+    /// `BFAR` holds a valid fault address
+    pub bfarvalid, _: 15;
+    /// UsageFault Status Register
+    pub u16, ufsr, _: 31, 16;
+    /// Undefined instruction
+    pub undefinstr, _: 16;
+    /// Invalid state, e.g. an attempt to switch to ARM state
+    pub invstate, _: 17;
+    /// Invalid `EXC_RETURN` value on exception return
+    pub invpc, _: 18;
+    /// Attempt to access a disabled or absent coprocessor
+    pub nocp, _: 19;
+    /// Stack pointer limit violation (Armv8-M Mainline only)
+    pub stkof, _: 20;
+    /// Unaligned access
+    pub unaligned, _: 24;
+    /// Division by zero
+    pub divbyzero, _: 25;
+}
+
+#[cfg(not(armv6m))]
+impl Cfsr {
+    /// Returns `true` if the fault happened while stacking the context on exception entry
+    /// (`MSTKERR` or `STKERR`).
+    ///
+    /// The exception frame is then incomplete or missing, so the stacked PC and the other stacked
+    /// registers must not be trusted. The stack pointer itself is the usual suspect, e.g. a stack
+    /// overflow into a protected or unmapped region.
+    #[inline]
+    pub fn is_stacking_fault(&self) -> bool {
+        self.mstkerr() || self.stkerr()
+    }
+
+    /// Returns `true` if the fault happened while unstacking the context on exception return
+    /// (`MUNSTKERR` or `UNSTKERR`).
+    ///
+    /// The handler that faulted had already completed, so the stacked PC doesn't point at a
+    /// faulting instruction; the frame being returned to was corrupted or its stack became
+    /// inaccessible.
+    #[inline]
+    pub fn is_unstacking_fault(&self) -> bool {
+        self.munstkerr() || self.unstkerr()
+    }
+}
+
+#[cfg(not(armv6m))]
+impl Cfsr {
+    /// The names of the status bits, in bit order
+    const FLAGS: [(u32, &'static str); 20] = [
+        (0, "IACCVIOL"),
+        (1, "DACCVIOL"),
+        (3, "MUNSTKERR"),
+        (4, "MSTKERR"),
+        (5, "MLSPERR"),
+        (7, "MMARVALID"),
+        (8, "IBUSERR"),
+        (9, "PRECISERR"),
+        (10, "IMPRECISERR"),
+        (11, "UNSTKERR"),
+        (12, "STKERR"),
+        (13, "LSPERR"),
+        (15, "BFARVALID"),
+        (16, "UNDEFINSTR"),
+        (17, "INVSTATE"),
+        (18, "INVPC"),
+        (19, "NOCP"),
+        (20, "STKOF"),
+        (24, "UNALIGNED"),
+        (25, "DIVBYZERO"),
+    ];
+}
+
+/// Writes a one line crash report to `w`
+///
+/// The report holds the stacked registers of `frame`, the fault status `cfsr` with the names of
+/// the bits set, and the fault addresses `mmfar` and `bfar` when `cfsr` says they're valid:
+///
+/// ```text
+/// R0=0x00000000 R1=0x00000001 R2=0x00000002 R3=0x00000003 R12=0x0000000c LR=0x08000101
+/// PC=0x08000200 xPSR=0x01000000 CFSR=0x00000082 [DACCVIOL MMARVALID] MMFAR=0x20001000
+/// ```
+///
+/// (wrapped here), as space separated `NAME=0x%08x` fields, followed by a newline. The fault
+/// address fields are left out when not valid. This neither allocates nor panics, so it can run in
+/// a fault handler; it only fails if `w` does.
+///
+/// # Example
+///
+/// ```
+/// use cortex_m::peripheral::scb::{write_crash_report, Cfsr};
+/// use cortex_m::register::frame::ExceptionFrame;
+///
+/// // e.g. a fixed size buffer, a UART, or an ITM stimulus port
+/// let mut report = String::new();
+/// let frame = ExceptionFrame {
+///     pc: 0x0800_0200,
+///     ..Default::default()
+/// };
+/// // in the HardFault handler, `scb.fault_status()`, `scb.mmfar.read()` and `scb.bfar.read()`
+/// let cfsr = Cfsr(1 << 25);
+/// write_crash_report(&mut report, &frame, cfsr, 0, 0).unwrap();
+/// assert!(report.contains("PC=0x08000200"));
+/// assert!(report.ends_with("CFSR=0x02000000 [DIVBYZERO]\n"));
+/// ```
+#[cfg(not(armv6m))]
+#[allow(clippy::missing_inline_in_public_items)]
+pub fn write_crash_report<W>(
+    w: &mut W,
+    frame: &crate::register::frame::ExceptionFrame,
+    cfsr: Cfsr,
+    mmfar: u32,
+    bfar: u32,
+) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    write!(
+        w,
+        "R0={:#010x} R1={:#010x} R2={:#010x} R3={:#010x} R12={:#010x} ",
+        frame.r0, frame.r1, frame.r2, frame.r3, frame.r12
+    )?;
+    write!(
+        w,
+        "LR={:#010x} PC={:#010x} xPSR={:#010x} CFSR={:#010x} [",
+        frame.lr, frame.pc, frame.xpsr, cfsr.0
+    )?;
+    let mut separator = "";
+    for (bit, name) in Cfsr::FLAGS {
+        if cfsr.0 & (1 << bit) != 0 {
+            w.write_str(separator)?;
+            w.write_str(name)?;
+            separator = " ";
+        }
+    }
+    w.write_str("]")?;
+    if cfsr.mmarvalid() {
+        write!(w, " MMFAR={:#010x}", mmfar)?;
+    }
+    if cfsr.bfarvalid() {
+        write!(w, " BFAR={:#010x}", bfar)?;
+    }
+    w.write_str("\n")
+}
+
+#[cfg(all(not(armv6m), feature = "defmt"))]
+impl defmt::Format for Cfsr {
+    #[inline]
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Cfsr({=u32:#010x})", self.0)
+    }
+}
+
+#[cfg(not(armv6m))]
+bitfield! {
+    /// Debug Fault Status Register, the debug events that halted the core or raised the
+    /// `DebugMonitor` exception.
+    #[repr(C)]
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    pub struct Dfsr(u32);
+    impl Debug;
+    /// Halt or step request, including a monitor mode step (`DEMCR.MON_STEP`)
+    pub halted, _: 0;
+    /// Breakpoint, from a `BKPT` instruction or an FPB comparator
+    pub bkpt, _: 1;
+    /// DWT watchpoint or trace event
+    pub dwttrap, _: 2;
+    /// Vector catch
+    pub vcatch, _: 3;
+    /// External debug request (`EDBGRQ`)
+    pub external, _: 4;
+}
+
+#[cfg(all(not(armv6m), feature = "defmt"))]
+impl defmt::Format for Dfsr {
+    #[inline]
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Dfsr({=u32:#04x})", self.0)
+    }
+}
+
+#[cfg(not(armv6m))]
+impl SCB {
+    /// Reads the Configurable Fault Status Register.
+    #[inline]
+    pub fn fault_status(&self) -> Cfsr {
+        Cfsr(self.cfsr.read())
+    }
+
+    /// Clears the fault status bits set in `status`.
+    ///
+    /// The CFSR bits are write-one-to-clear, so passing the value returned by
+    /// [`SCB::fault_status`] clears exactly the faults that were reported.
+    #[inline]
+    pub fn clear_fault_status(&mut self, status: Cfsr) {
+        unsafe { self.cfsr.write(status.0) }
+    }
+
+    /// Reads the Debug Fault Status Register.
+    #[inline]
+    pub fn debug_fault_status(&self) -> Dfsr {
+        Dfsr(self.dfsr.read())
+    }
+
+    /// Clears the debug event bits set in `status`.
+    ///
+    /// The DFSR bits are write-one-to-clear and stay set until cleared, so a `DebugMonitor`
+    /// handler should clear the events it handled, or they show up again at the next entry.
+    #[inline]
+    pub fn clear_debug_fault_status(&mut self, status: Dfsr) {
+        unsafe { self.dfsr.write(status.0) }
+    }
+
+    /// Returns the address of the last MemManage fault, if `MMFAR` holds a valid one.
+    #[inline]
+    pub fn mem_manage_fault_address(&self) -> Option<u32> {
+        if self.fault_status().mmarvalid() {
+            Some(self.mmfar.read())
+        } else {
+            None
+        }
+    }
+}
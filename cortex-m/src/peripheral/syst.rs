@@ -13,10 +13,20 @@
 //!
 //! let syst_value: u32 = SYST::get_current();
 //! ```
+//!
+//! # Clock source
+//!
+//! The counter runs either on the processor clock or on an implementation defined reference clock
+//! ([`SystClkSource::External`]). The reference clock is often much slower: on STM32 parts it's
+//! the processor clock divided by 8, so timing computed for the processor clock while the
+//! counter is on the reference clock comes out 8 times too long. The reset value of the clock
+//! source bit is implementation defined, so set it explicitly, and use the frequency of the
+//! selected source, see [`SYST::clock_hz`] and [`SYST::reference_clock_hz`].
 
 use volatile_register::{RO, RW};
 
 use crate::peripheral::SYST;
+use bitfield::bitfield;
 
 /// Register block
 #[repr(C)]
@@ -31,6 +41,22 @@ pub struct RegisterBlock {
     pub calib: RO<u32>,
 }
 
+bitfield! {
+    /// Control and Status register, see [`SYST::modify_csr`].
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Csr(u32);
+    impl Debug;
+    /// Counter enable
+    pub enable, set_enable: 0;
+    /// SysTick exception request enable
+    pub tickint, set_tickint: 1;
+    /// Clock source: `true` for the processor clock, `false` for the external reference clock
+    pub clksource, set_clksource: 2;
+    /// Whether the counter wrapped to zero since the last read
+    pub countflag, _: 16;
+}
+
 /// SysTick clock source
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SystClkSource {
@@ -40,8 +66,43 @@ pub enum SystClkSource {
     External,
 }
 
+/// The counter and interrupt state captured by [`SYST::suspend`], to hand to [`SYST::resume`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SystState {
+    enabled: bool,
+    tickint: bool,
+    current: u32,
+}
+
+impl SystState {
+    /// Whether the counter was enabled
+    #[inline]
+    pub fn is_counter_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether the SysTick interrupt was enabled
+    #[inline]
+    pub fn is_interrupt_enabled(&self) -> bool {
+        self.tickint
+    }
+
+    /// The current value of the stopped counter
+    #[inline]
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+}
+
 const SYST_COUNTER_MASK: u32 = 0x00ff_ffff;
 
+/// Remaining counts below which [`SYST::resume`] pends the tick instead of resuming mid-period.
+const SYST_RESUME_MIN_TICKS: u32 = 64;
+
+/// Number of times [`SYST::resume`] polls the counter for the captured value to be loaded.
+const SYST_RESUME_MAX_POLLS: u32 = 256;
+
 const SYST_CSR_ENABLE: u32 = 1 << 0;
 const SYST_CSR_TICKINT: u32 = 1 << 1;
 const SYST_CSR_CLKSOURCE: u32 = 1 << 2;
@@ -50,6 +111,70 @@ const SYST_CSR_COUNTFLAG: u32 = 1 << 16;
 const SYST_CALIB_SKEW: u32 = 1 << 30;
 const SYST_CALIB_NOREF: u32 = 1 << 31;
 
+/// Possible error values returned by [`SYST::set_period_ms_calibrated`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[non_exhaustive]
+pub enum CalibError {
+    /// The calibration value is not populated (`TENMS` is zero) or there is no reference clock
+    /// (`NOREF` is set).
+    Unavailable,
+    /// The requested period is zero or doesn't fit in the 24-bit reload value.
+    OutOfRange,
+}
+
+/// How the frequency of the reference clock is derived, see [`SYST::reference_clock_hz`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReferenceClockHint {
+    /// From `CALIB.TENMS`, the vendor-provided reload value for 10 ms on the reference clock.
+    ///
+    /// Some vendors compute `TENMS` for one particular processor clock frequency, typically the
+    /// maximum, so it's only right at that frequency.
+    Calibration,
+    /// The reference clock is the processor clock divided by this, e.g. `8` on STM32 parts.
+    CoreDivider(u32),
+}
+
+/// Estimates the frequency of the reference clock from a `CALIB` register value.
+pub(crate) const fn estimate_reference_clock(
+    calib: u32,
+    core_clk_hz: u32,
+    hint: ReferenceClockHint,
+) -> Option<u32> {
+    if calib & SYST_CALIB_NOREF != 0 {
+        return None;
+    }
+    match hint {
+        ReferenceClockHint::Calibration => {
+            let tenms = calib & SYST_COUNTER_MASK;
+            if tenms == 0 {
+                None
+            } else {
+                // `TENMS` is the reload value, one less than the number of ticks in 10 ms
+                Some((tenms + 1) * 100)
+            }
+        }
+        ReferenceClockHint::CoreDivider(0) => None,
+        ReferenceClockHint::CoreDivider(divider) => Some(core_clk_hz / divider),
+    }
+}
+
+/// Computes the reload value for a period of `ms` milliseconds from a `CALIB` register value.
+pub(crate) const fn calibrated_reload(calib: u32, ms: u32) -> Result<u32, CalibError> {
+    let tenms = calib & SYST_COUNTER_MASK;
+    if tenms == 0 || calib & SYST_CALIB_NOREF != 0 {
+        return Err(CalibError::Unavailable);
+    }
+
+    // `TENMS` is the reload value, one less than the number of ticks in 10 ms
+    let ticks = ((tenms as u64 + 1) * ms as u64 + 5) / 10;
+    if ticks == 0 || ticks - 1 > SYST_COUNTER_MASK as u64 {
+        Err(CalibError::OutOfRange)
+    } else {
+        Ok(ticks as u32 - 1)
+    }
+}
+
 impl SYST {
     /// Clears current value to 0
     ///
@@ -59,16 +184,48 @@ impl SYST {
         unsafe { self.cvr.write(0) }
     }
 
+    /// Modifies the Control and Status register with a single read-modify-write
+    ///
+    /// Use this to change several bits together, e.g. the clock source, interrupt enable and
+    /// counter enable, in one store:
+    ///
+    /// ```no_run
+    /// # let mut syst = cortex_m::Peripherals::take().unwrap().SYST;
+    /// syst.modify_csr(|mut csr| {
+    ///     csr.set_clksource(true);
+    ///     csr.set_tickint(true);
+    ///     csr.set_enable(true);
+    ///     csr
+    /// });
+    /// ```
+    ///
+    /// *NOTE* Reading the register clears `COUNTFLAG`, so the read half of the read-modify-write
+    /// consumes a pending wrap that [`has_wrapped`](SYST::has_wrapped) would otherwise report. The
+    /// `countflag` value passed to `f` is the one that was consumed.
+    #[inline]
+    pub fn modify_csr<F>(&mut self, f: F)
+    where
+        F: FnOnce(Csr) -> Csr,
+    {
+        unsafe { self.csr.modify(|r| f(Csr(r)).0) }
+    }
+
     /// Disables counter
     #[inline]
     pub fn disable_counter(&mut self) {
-        unsafe { self.csr.modify(|v| v & !SYST_CSR_ENABLE) }
+        self.modify_csr(|mut csr| {
+            csr.set_enable(false);
+            csr
+        })
     }
 
     /// Disables SysTick interrupt
     #[inline]
     pub fn disable_interrupt(&mut self) {
-        unsafe { self.csr.modify(|v| v & !SYST_CSR_TICKINT) }
+        self.modify_csr(|mut csr| {
+            csr.set_tickint(false);
+            csr
+        })
     }
 
     /// Enables counter
@@ -85,13 +242,19 @@ impl SYST {
     /// The sequence translates to `self.set_reload(x); self.clear_current(); self.enable_counter()`
     #[inline]
     pub fn enable_counter(&mut self) {
-        unsafe { self.csr.modify(|v| v | SYST_CSR_ENABLE) }
+        self.modify_csr(|mut csr| {
+            csr.set_enable(true);
+            csr
+        })
     }
 
     /// Enables SysTick interrupt
     #[inline]
     pub fn enable_interrupt(&mut self) {
-        unsafe { self.csr.modify(|v| v | SYST_CSR_TICKINT) }
+        self.modify_csr(|mut csr| {
+            csr.set_tickint(true);
+            csr
+        })
     }
 
     /// Gets clock source
@@ -133,6 +296,39 @@ impl SYST {
         unsafe { (*Self::PTR).calib.read() & SYST_COUNTER_MASK }
     }
 
+    /// Estimates the frequency of the external reference clock in Hz
+    ///
+    /// `hint` tells how the reference clock relates to the processor clock, which runs at
+    /// `core_clk_hz`, on this chip. The architecture leaves the reference clock implementation
+    /// defined, so consult the reference manual of the chip. With
+    /// [`ReferenceClockHint::Calibration`] the frequency is derived from `CALIB.TENMS`, and is
+    /// only approximate if [`SYST::is_precise`] returns `false`.
+    ///
+    /// Returns `None` if there is no reference clock (`CALIB.NOREF` is set), or if the frequency
+    /// can't be derived with `hint`.
+    #[inline]
+    pub fn reference_clock_hz(core_clk_hz: u32, hint: ReferenceClockHint) -> Option<u32> {
+        // NOTE(unsafe) atomic read with no side effects
+        let calib = unsafe { (*Self::PTR).calib.read() };
+        estimate_reference_clock(calib, core_clk_hz, hint)
+    }
+
+    /// Returns the frequency in Hz of the selected clock source, see
+    /// [`get_clock_source`](SYST::get_clock_source)
+    ///
+    /// This is `core_clk_hz` for the processor clock, and the estimate of
+    /// [`reference_clock_hz`](SYST::reference_clock_hz) for the external reference clock. Use it
+    /// to compute reload values, so they're right whichever source is selected.
+    ///
+    /// *NOTE* This takes `&mut self` for the same reason as `get_clock_source`.
+    #[inline]
+    pub fn clock_hz(&mut self, core_clk_hz: u32, hint: ReferenceClockHint) -> Option<u32> {
+        match self.get_clock_source() {
+            SystClkSource::Core => Some(core_clk_hz),
+            SystClkSource::External => Self::reference_clock_hz(core_clk_hz, hint),
+        }
+    }
+
     /// Checks if an external reference clock is available
     #[inline]
     pub fn has_reference_clock() -> bool {
@@ -181,10 +377,114 @@ impl SYST {
     /// Sets clock source
     #[inline]
     pub fn set_clock_source(&mut self, clk_source: SystClkSource) {
-        match clk_source {
-            SystClkSource::External => unsafe { self.csr.modify(|v| v & !SYST_CSR_CLKSOURCE) },
-            SystClkSource::Core => unsafe { self.csr.modify(|v| v | SYST_CSR_CLKSOURCE) },
+        self.modify_csr(|mut csr| {
+            csr.set_clksource(clk_source == SystClkSource::Core);
+            csr
+        })
+    }
+
+    /// Stops the counter and its interrupt, and returns the state to [`resume`](SYST::resume)
+    /// from
+    ///
+    /// Use it around low-power sequences that must not take SysTick interrupts. The counter keeps
+    /// its current value, so resuming continues the interrupted period rather than starting a
+    /// new one.
+    ///
+    /// *NOTE* Like [`modify_csr`](SYST::modify_csr), this consumes a pending `COUNTFLAG`. A
+    /// SysTick exception that was already pended stays pending.
+    #[inline]
+    pub fn suspend(&mut self) -> SystState {
+        let mut state = SystState {
+            enabled: false,
+            tickint: false,
+            current: 0,
+        };
+        self.modify_csr(|mut csr| {
+            state.enabled = csr.enable();
+            state.tickint = csr.tickint();
+            csr.set_enable(false);
+            csr.set_tickint(false);
+            csr
+        });
+        state.current = self.cvr.read() & SYST_COUNTER_MASK;
+        state
+    }
+
+    /// Restores the counter and interrupt state captured by [`suspend`](SYST::suspend)
+    ///
+    /// Writing the current value register always clears it, so the counter is restarted from the
+    /// captured value by loading it through the reload register, and the reload value is
+    /// restored once the counter has picked it up. The tick after resuming therefore neither
+    /// drops nor lengthens the period by more than the few cycles this sequence takes. If fewer
+    /// than 64 counts of the period were left, the SysTick exception is pended right away (when
+    /// its interrupt was enabled) and a full period is started instead.
+    ///
+    /// The counter loads the captured value on its first clock after being enabled: right away with
+    /// the processor clock, but possibly many core cycles later with the external reference clock.
+    /// This polls for it at most 256 times, in a critical section. If the counter still hasn't
+    /// loaded it, e.g. because the reference clock is much slower than the core, the reload value
+    /// is restored anyway: the counter then starts a full period instead of the captured remainder,
+    /// so the next tick is late, but it is never duplicated.
+    ///
+    /// The clock source and the reload value are assumed to be unchanged since the call to
+    /// `suspend`.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn resume(&mut self, state: SystState) {
+        if !state.enabled {
+            self.modify_csr(|mut csr| {
+                csr.set_tickint(state.tickint);
+                csr
+            });
+            return;
         }
+
+        crate::interrupt::free(|| {
+            let reload = self.rvr.read();
+            if state.current < SYST_RESUME_MIN_TICKS {
+                if state.tickint {
+                    crate::peripheral::SCB::set_pendst();
+                }
+            } else {
+                unsafe { self.rvr.write(state.current) };
+            }
+            self.clear_current();
+            self.modify_csr(|mut csr| {
+                csr.set_tickint(state.tickint);
+                csr.set_enable(true);
+                csr
+            });
+            if state.current >= SYST_RESUME_MIN_TICKS {
+                // the counter loads the reload value on the first clock after enabling it
+                for _ in 0..SYST_RESUME_MAX_POLLS {
+                    if self.cvr.read() != 0 {
+                        break;
+                    }
+                }
+                unsafe { self.rvr.write(reload) };
+            }
+        });
+    }
+
+    /// Configures the counter to wrap every `ms` milliseconds using only the calibration value
+    ///
+    /// The reload value is derived from `CALIB.TENMS`, the vendor-provided reload value for 10 ms
+    /// on the reference clock, so the core clock frequency doesn't need to be known. The counter
+    /// is switched to the external reference clock, restarted from the new reload value and
+    /// enabled; the SysTick interrupt enable is left unchanged.
+    ///
+    /// Returns [`CalibError::Unavailable`] if `TENMS` is zero or there is no reference clock, and
+    /// [`CalibError::OutOfRange`] if `ms` is zero or the period doesn't fit in 24 bits. Check
+    /// [`SYST::is_precise`] if the accuracy of the period matters.
+    #[inline]
+    pub fn set_period_ms_calibrated(&mut self, ms: u32) -> Result<(), CalibError> {
+        let reload = calibrated_reload(self.calib.read(), ms)?;
+
+        self.disable_counter();
+        self.set_clock_source(SystClkSource::External);
+        self.set_reload(reload);
+        self.clear_current();
+        self.enable_counter();
+        Ok(())
     }
 
     /// Sets reload value
@@ -54,6 +54,142 @@ fn dwt() {
     assert_eq!(address(&dwt.lsr), 0xE000_1FB4);
 }
 
+#[test]
+fn dwt_watchpoint_encoding() {
+    use crate::peripheral::dwt::{
+        AccessType, DataSize, DwtError, WatchpointAction, WatchpointConfig, WatchpointEncoding,
+        WatchpointMatch,
+    };
+
+    let write_word = WatchpointConfig {
+        matches: WatchpointMatch::DataAddress {
+            address: 0x2000_0100,
+            size: DataSize::Word,
+            access_type: AccessType::WriteOnly,
+        },
+        action: WatchpointAction::DebugEvent,
+    };
+    assert_eq!(
+        write_word.encode_v7m(),
+        Ok(WatchpointEncoding {
+            comp: 0x2000_0100,
+            mask: 2,
+            function: 0b0110,
+        })
+    );
+    assert_eq!(
+        write_word.encode_v8m(),
+        Ok(WatchpointEncoding {
+            comp: 0x2000_0100,
+            mask: 0,
+            function: 0b10 << 10 | 0b01 << 4 | 0b0101,
+        })
+    );
+
+    let instruction = WatchpointConfig {
+        matches: WatchpointMatch::InstructionAddress {
+            address: 0x0800_0400,
+        },
+        action: WatchpointAction::CompareMatchEvent,
+    };
+    assert_eq!(instruction.encode_v7m().unwrap().function, 0b1000);
+    assert_eq!(
+        instruction.encode_v8m().unwrap().function,
+        0b01 << 10 | 0b0010
+    );
+
+    let read_value = WatchpointConfig {
+        matches: WatchpointMatch::DataValue {
+            value: 0xdead_beef,
+            size: DataSize::Word,
+            access_type: AccessType::ReadOnly,
+        },
+        action: WatchpointAction::DebugEvent,
+    };
+    assert_eq!(
+        read_value.encode_v7m(),
+        Err(DwtError::UnsupportedOnArchitecture)
+    );
+    assert_eq!(
+        read_value.encode_v8m(),
+        Ok(WatchpointEncoding {
+            comp: 0xdead_beef,
+            mask: 0,
+            function: 0b10 << 10 | 0b01 << 4 | 0b1010,
+        })
+    );
+
+    let misaligned = WatchpointConfig {
+        matches: WatchpointMatch::DataAddress {
+            address: 0x2000_0101,
+            size: DataSize::Halfword,
+            access_type: AccessType::ReadWrite,
+        },
+        action: WatchpointAction::DebugEvent,
+    };
+    assert_eq!(misaligned.encode_v7m(), Err(DwtError::MisalignedAddress));
+    assert_eq!(misaligned.encode_v8m(), Err(DwtError::MisalignedAddress));
+}
+
+#[test]
+#[cfg(not(armv6m))]
+fn dwt_profiling_delta() {
+    use crate::peripheral::dwt::ProfilingSnapshot;
+
+    let earlier = ProfilingSnapshot {
+        cycles: 0xFFFF_FF00,
+        cpi: 250,
+        exception: 10,
+        sleep: 0,
+        lsu: 5,
+        fold: 1,
+    };
+    let later = ProfilingSnapshot {
+        cycles: 0x0000_0300,
+        cpi: 20,
+        exception: 50,
+        sleep: 3,
+        lsu: 55,
+        fold: 2,
+    };
+
+    let delta = later.delta(&earlier);
+    assert_eq!(delta.cycles, 0x400);
+    assert_eq!(delta.cpi, 26);
+    assert_eq!(delta.exception, 40);
+    assert_eq!(delta.sleep, 3);
+    assert_eq!(delta.lsu, 50);
+    assert_eq!(delta.fold, 1);
+    assert_eq!(delta.stall_fraction(), 76.0 / 1024.0);
+    assert_eq!(delta.exception_overhead_percent(), 40.0 / 1024.0 * 100.0);
+
+    assert_eq!(earlier.delta(&earlier).stall_fraction(), 0.0);
+}
+
+#[test]
+#[cfg(not(armv6m))]
+fn dwt_idle_meter() {
+    use crate::peripheral::dwt::IdleMeter;
+
+    let mut meter = IdleMeter::new(0xFFFF_FF00);
+    assert_eq!(meter.idle_percent(), 0.0);
+
+    // the cycle counter wraps
+    meter.record_busy(0x0000_0100);
+    assert_eq!(meter.total_cycles(), 0x200);
+    assert_eq!(meter.idle_cycles(), 0);
+
+    // idle intervals longer than the 8-bit SLEEPCNT range are counted in full
+    meter.record_idle(0x0000_0300);
+    assert_eq!(meter.total_cycles(), 0x400);
+    assert_eq!(meter.idle_cycles(), 0x200);
+    assert_eq!(meter.idle_percent(), 50.0);
+
+    meter.record_busy(0x0000_0400);
+    assert_eq!(meter.idle_cycles(), 0x200);
+    assert_eq!(meter.idle_percent(), 40.0);
+}
+
 #[test]
 fn fpb() {
     let fpb = unsafe { &*crate::peripheral::FPB::PTR };
@@ -107,6 +243,1725 @@ fn mpu() {
     assert_eq!(address(&mpu.rasr_a3), 0xE000EDB8);
 }
 
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_region_config_v7() {
+    use crate::peripheral::mpu::{
+        AccessPermission, MemoryAttributes, MpuError, RegionConfig, RegionSize,
+    };
+
+    let sram = RegionConfig {
+        base: 0x2000_0000,
+        size: RegionSize::Size128K,
+        access: AccessPermission::ReadWrite,
+        attributes: MemoryAttributes {
+            tex: 0b001,
+            cacheable: true,
+            bufferable: true,
+            shareable: false,
+        },
+        subregion_disable: 0b1100_0000,
+        execute_never: true,
+    };
+    assert_eq!(sram.validate(), Ok(()));
+    assert_eq!(sram.rbar(), 0x2000_0000);
+    assert_eq!(
+        sram.rasr(),
+        1 << 28 | 0b011 << 24 | 0b001 << 19 | 1 << 17 | 1 << 16 | 0b1100_0000 << 8 | 16 << 1 | 1
+    );
+
+    assert_eq!(RegionSize::Size32B.bytes(), 32);
+    assert_eq!(RegionSize::Size4G.bytes(), 1 << 32);
+
+    let misaligned = RegionConfig {
+        base: 0x2000_1000,
+        ..sram
+    };
+    assert_eq!(misaligned.validate(), Err(MpuError::MisalignedBase));
+
+    let small = RegionConfig {
+        base: 0x2000_0000,
+        size: RegionSize::Size128B,
+        ..sram
+    };
+    assert_eq!(small.validate(), Err(MpuError::SubregionsNotSupported));
+
+    let bad_tex = RegionConfig {
+        attributes: MemoryAttributes {
+            tex: 8,
+            ..sram.attributes
+        },
+        ..sram
+    };
+    assert_eq!(bad_tex.validate(), Err(MpuError::InvalidAttributes));
+}
+
+#[test]
+fn mpu_region_encoding_v8() {
+    use crate::peripheral::mpu::{AccessPermissionV8, MpuError, RegionConfigV8};
+
+    let sram = RegionConfigV8 {
+        base: 0x2000_0000,
+        limit: 0x2000_7FFF,
+        attr_index: 3,
+        access: AccessPermissionV8::ReadWrite,
+        execute_never: true,
+    };
+    assert_eq!(sram.validate(), Ok(()));
+    assert_eq!(sram.rbar(), 0x2000_0000 | 0b01 << 1 | 1);
+    assert_eq!(sram.rlar(), 0x2000_7FE0 | 3 << 1 | 1);
+
+    let misaligned = RegionConfigV8 {
+        base: 0x2000_0010,
+        ..sram
+    };
+    assert_eq!(misaligned.validate(), Err(MpuError::MisalignedBase));
+    let bad_limit = RegionConfigV8 {
+        limit: 0x2000_8000,
+        ..sram
+    };
+    assert_eq!(bad_limit.validate(), Err(MpuError::InvalidLimit));
+    let empty = RegionConfigV8 {
+        base: 0x2000_0020,
+        limit: 0x2000_001F,
+        ..sram
+    };
+    assert_eq!(empty.validate(), Err(MpuError::InvalidLimit));
+    let bad_attr = RegionConfigV8 {
+        attr_index: 8,
+        ..sram
+    };
+    assert_eq!(bad_attr.validate(), Err(MpuError::InvalidAttributeIndex));
+}
+
+#[test]
+fn mpu_region_round_trip_v8() {
+    use crate::peripheral::mpu::{AccessPermissionV8, RegionConfigV8};
+
+    let configs = [
+        RegionConfigV8 {
+            base: 0x0000_0000,
+            limit: 0x0007_FFFF,
+            attr_index: 0,
+            access: AccessPermissionV8::ReadOnly,
+            execute_never: false,
+        },
+        RegionConfigV8 {
+            base: 0x2000_0000,
+            limit: 0x2001_7FFF,
+            attr_index: 1,
+            access: AccessPermissionV8::ReadWrite,
+            execute_never: true,
+        },
+        RegionConfigV8 {
+            base: 0x4000_0000,
+            limit: 0x5FFF_FFFF,
+            attr_index: 7,
+            access: AccessPermissionV8::PrivilegedReadWrite,
+            execute_never: true,
+        },
+        RegionConfigV8 {
+            base: 0xFFFF_FFE0,
+            limit: 0xFFFF_FFFF,
+            attr_index: 4,
+            access: AccessPermissionV8::PrivilegedReadOnly,
+            execute_never: false,
+        },
+    ];
+
+    for config in &configs {
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            RegionConfigV8::from_registers(config.rbar(), config.rlar()),
+            *config
+        );
+    }
+}
+
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_region_round_trip_v7() {
+    use crate::peripheral::mpu::{AccessPermission, MemoryAttributes, RegionConfig, RegionSize};
+
+    let normal = MemoryAttributes {
+        tex: 0b001,
+        cacheable: true,
+        bufferable: true,
+        shareable: false,
+    };
+    let device = MemoryAttributes {
+        tex: 0b000,
+        cacheable: false,
+        bufferable: true,
+        shareable: true,
+    };
+    let configs = [
+        RegionConfig {
+            base: 0x0000_0000,
+            size: RegionSize::Size4G,
+            access: AccessPermission::NoAccess,
+            attributes: device,
+            subregion_disable: 0,
+            execute_never: true,
+        },
+        RegionConfig {
+            base: 0x0800_0000,
+            size: RegionSize::Size1M,
+            access: AccessPermission::ReadOnly,
+            attributes: normal,
+            subregion_disable: 0,
+            execute_never: false,
+        },
+        RegionConfig {
+            base: 0x2000_0000,
+            size: RegionSize::Size128K,
+            access: AccessPermission::ReadWrite,
+            attributes: normal,
+            subregion_disable: 0b1100_0000,
+            execute_never: true,
+        },
+        RegionConfig {
+            base: 0x2000_0100,
+            size: RegionSize::Size32B,
+            access: AccessPermission::PrivilegedReadOnly,
+            attributes: normal,
+            subregion_disable: 0,
+            execute_never: true,
+        },
+        RegionConfig {
+            base: 0x4000_0000,
+            size: RegionSize::Size512M,
+            access: AccessPermission::PrivilegedReadWriteUnprivilegedReadOnly,
+            attributes: device,
+            subregion_disable: 0b0000_0001,
+            execute_never: true,
+        },
+        RegionConfig {
+            base: 0x6000_0000,
+            size: RegionSize::Size256M,
+            access: AccessPermission::PrivilegedReadWrite,
+            attributes: MemoryAttributes {
+                tex: 0b111,
+                cacheable: true,
+                bufferable: false,
+                shareable: true,
+            },
+            subregion_disable: 0xFF,
+            execute_never: false,
+        },
+    ];
+
+    for config in &configs {
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            RegionConfig::from_registers(config.rbar(), config.rasr()),
+            Some(*config)
+        );
+    }
+
+    // reserved SIZE and AP encodings
+    assert_eq!(RegionConfig::from_registers(0, 3 << 1), None);
+    assert_eq!(RegionConfig::from_registers(0, 0b100 << 24 | 4 << 1), None);
+}
+
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_rasr_size_field() {
+    use crate::peripheral::mpu::{rasr_field_to_size, size_to_rasr_field, RegionSize};
+
+    assert_eq!(size_to_rasr_field(32), Some(4));
+    assert_eq!(size_to_rasr_field(1024), Some(9));
+    assert_eq!(size_to_rasr_field(1 << 32), Some(31));
+    assert_eq!(size_to_rasr_field(0), None);
+    assert_eq!(size_to_rasr_field(16), None);
+    assert_eq!(size_to_rasr_field(96 * 1024), None);
+    assert_eq!(size_to_rasr_field(1 << 33), None);
+
+    assert_eq!(rasr_field_to_size(3), None);
+    assert_eq!(rasr_field_to_size(4), Some(32));
+    assert_eq!(rasr_field_to_size(31), Some(1 << 32));
+    assert_eq!(rasr_field_to_size(32), None);
+
+    for field in 4..=31 {
+        assert_eq!(
+            size_to_rasr_field(rasr_field_to_size(field).unwrap()),
+            Some(field)
+        );
+    }
+    assert_eq!(
+        size_to_rasr_field(RegionSize::Size64K.bytes()),
+        Some(RegionSize::Size64K as u8)
+    );
+}
+
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_stack_guard_v7() {
+    use crate::peripheral::mpu::{AccessPermission, GuardSize, MpuError, RegionConfig, RegionSize};
+
+    // naturally aligned
+    let guard = RegionConfig::stack_guard(0x2000_0400, GuardSize::Size64B).unwrap();
+    assert_eq!(guard.base, 0x2000_0400);
+    assert_eq!(guard.size, RegionSize::Size64B);
+    assert_eq!(guard.subregion_disable, 0);
+    assert_eq!(guard.access, AccessPermission::NoAccess);
+    assert!(guard.execute_never);
+    assert_eq!(guard.validate(), Ok(()));
+
+    // 32-byte aligned 64-byte guard: subregions 3 and 4 of a 256-byte region
+    let guard = RegionConfig::stack_guard(0x2000_0460, GuardSize::Size64B).unwrap();
+    assert_eq!(guard.base, 0x2000_0400);
+    assert_eq!(guard.size, RegionSize::Size256B);
+    assert_eq!(guard.subregion_disable, !0b0001_1000);
+    assert_eq!(guard.validate(), Ok(()));
+
+    // 256-byte aligned 1 KiB guard: subregions 1 to 4 of a 2 KiB region
+    let guard = RegionConfig::stack_guard(0x2000_0900, GuardSize::Size1K).unwrap();
+    assert_eq!(guard.base, 0x2000_0800);
+    assert_eq!(guard.size, RegionSize::Size2K);
+    assert_eq!(guard.subregion_disable, !0b0001_1110);
+
+    // crosses the boundary of every candidate region
+    assert_eq!(
+        RegionConfig::stack_guard(0x2000_04E0, GuardSize::Size64B),
+        Err(MpuError::MisalignedBase)
+    );
+    assert_eq!(
+        RegionConfig::stack_guard(0x2000_0410, GuardSize::Size32B),
+        Err(MpuError::MisalignedBase)
+    );
+}
+
+#[test]
+fn mpu_stack_guard_v8() {
+    use crate::peripheral::mpu::{AccessPermissionV8, GuardSize, MpuError, RegionConfigV8};
+
+    let guard = RegionConfigV8::stack_guard(0x2000_0460, GuardSize::Size64B).unwrap();
+    assert_eq!(guard.base, 0x2000_0460);
+    assert_eq!(guard.limit, 0x2000_049F);
+    assert_eq!(guard.access, AccessPermissionV8::PrivilegedReadOnly);
+    assert!(guard.execute_never);
+
+    assert_eq!(
+        RegionConfigV8::stack_guard(0x2000_0410, GuardSize::Size32B),
+        Err(MpuError::MisalignedBase)
+    );
+}
+
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_memory_attributes_v7() {
+    use crate::peripheral::mpu::{AccessPermission, MemoryAttributes, RegionConfig, RegionSize};
+
+    // Armv7-M ARM, Table B3-13 "TEX, C, B, and S encoding"; RASR.S is bit 18, C 17, B 16
+    // and TEX 21:19
+    let rasr = |attributes| {
+        RegionConfig {
+            base: 0,
+            size: RegionSize::Size32B,
+            access: AccessPermission::NoAccess,
+            attributes,
+            subregion_disable: 0,
+            execute_never: false,
+        }
+        .rasr()
+            & 0x003F_0000
+    };
+    let enc = |tex: u32, s: u32, c: u32, b: u32| tex << 19 | s << 18 | c << 17 | b << 16;
+
+    assert_eq!(
+        rasr(MemoryAttributes::strongly_ordered()),
+        enc(0b000, 0, 0, 0)
+    );
+    assert_eq!(rasr(MemoryAttributes::device()), enc(0b000, 0, 0, 1));
+    assert_eq!(
+        rasr(MemoryAttributes::normal_cacheable_wt()),
+        enc(0b000, 0, 1, 0)
+    );
+    assert_eq!(
+        rasr(MemoryAttributes::normal_noncacheable()),
+        enc(0b001, 0, 0, 0)
+    );
+    assert_eq!(
+        rasr(MemoryAttributes::normal_cacheable_wb_wa()),
+        enc(0b001, 0, 1, 1)
+    );
+    assert_eq!(
+        rasr(MemoryAttributes::normal_cacheable_wb_wa().shareable()),
+        enc(0b001, 1, 1, 1)
+    );
+}
+
+#[test]
+fn mpu_memory_attributes_v8() {
+    use crate::peripheral::mpu::MemoryAttribute;
+
+    // Armv8-M ARM, MPU_MAIR0 Attr<n> encodings
+    assert_eq!(MemoryAttribute::device_ngnrne().bits(), 0x00);
+    assert_eq!(MemoryAttribute::device_ngnre().bits(), 0x04);
+    assert_eq!(MemoryAttribute::device_ngre().bits(), 0x08);
+    assert_eq!(MemoryAttribute::device_gre().bits(), 0x0C);
+    assert_eq!(MemoryAttribute::normal_noncacheable().bits(), 0x44);
+    assert_eq!(MemoryAttribute::normal_wt_ralloc().bits(), 0xAA);
+    assert_eq!(MemoryAttribute::normal_wb_rwalloc().bits(), 0xFF);
+    assert_eq!(MemoryAttribute::from_bits(0x4F).bits(), 0x4F);
+}
+
+#[test]
+fn cpuid_features() {
+    use crate::peripheral::cpuid::{Features, IdRegisters};
+
+    // Cortex-M4F: ID_ISAR0 = 0x0110_1110, ID_ISAR3 = 0x0111_1131, MVFR0 = 0x1011_0021
+    let mut id = IdRegisters {
+        pfr: [0x0000_0030, 0x0000_0200],
+        isar: [
+            0x0110_1110,
+            0x0211_1000,
+            0x2111_2231,
+            0x0111_1131,
+            0x0131_0132,
+        ],
+        ..IdRegisters::default()
+    };
+    assert_eq!(
+        Features::decode(&id, 0x1011_0021),
+        Features {
+            has_dsp: true,
+            has_security_ext: false,
+            has_fp: true,
+            has_fp_double: false,
+            has_divide: true,
+        }
+    );
+
+    // Cortex-M3: SIMD_instrs = 0b0001, only SSAT and USAT
+    id.isar[3] = 0x0111_1110;
+    assert!(!Features::decode(&id, 0).has_dsp);
+    assert!(!Features::decode(&id, 0).has_fp);
+
+    // Cortex-M33 with the Security Extension and a double precision FPU
+    id.pfr[1] = 0x0000_0210;
+    let features = Features::decode(&id, 0x1011_0221);
+    assert!(features.has_security_ext);
+    assert!(features.has_fp_double);
+
+    // a reserved SIMD_instrs encoding isn't reported as DSP, as in `IsaFeatures`
+    id.isar[3] = 0x0111_1171;
+    assert!(!Features::decode(&id, 0).has_dsp);
+}
+
+#[test]
+fn cpuid_isa_features() {
+    use crate::peripheral::cpuid::{ArchProfile, FeatureSupport, IdRegisters, IsaFeatures};
+
+    // Cortex-M4
+    let mut id = IdRegisters {
+        pfr: [0x0000_0030, 0x0000_0200],
+        mmfr: [0x0010_0030, 0, 0x0100_0000, 0],
+        isar: [
+            0x0110_1110,
+            0x0211_1000,
+            0x2111_2231,
+            0x0111_1131,
+            0x0131_0132,
+        ],
+        ..IdRegisters::default()
+    };
+    assert_eq!(
+        IsaFeatures::decode(&id),
+        IsaFeatures {
+            dsp: FeatureSupport::Present,
+            divide: FeatureSupport::Present,
+            security_extension: FeatureSupport::Absent,
+            mpu: FeatureSupport::Present,
+            bitfield: FeatureSupport::Present,
+            exclusive_access: FeatureSupport::Present,
+        }
+    );
+
+    // Cortex-M3 has no DSP extension
+    id.isar[3] = 0x0111_1110;
+    assert_eq!(IsaFeatures::decode(&id).dsp, FeatureSupport::Absent);
+
+    // Cortex-M33 with the Security Extension and PMSAv8
+    id.pfr[1] = 0x0000_0210;
+    id.mmfr[0] = 0x0010_1F40;
+    let features = IsaFeatures::decode(&id);
+    assert!(features.has_security_extension());
+    assert!(features.has_mpu());
+
+    // Reserved encodings are never reported as present
+    id.isar[0] = 0x0710_1110;
+    id.isar[3] = 0x0111_1171;
+    let features = IsaFeatures::decode(&id);
+    assert_eq!(features.divide, FeatureSupport::Unknown(7));
+    assert_eq!(features.dsp, FeatureSupport::Unknown(7));
+    assert!(!features.has_divide() && !features.has_dsp());
+
+    // An Armv8-M Baseline core can't have the DSP extension, whatever the ID registers say
+    id.isar[0] = 0x0110_1110;
+    id.isar[3] = 0x0111_1131;
+    let features = IsaFeatures::decode(&id);
+    assert!(features.has_dsp());
+    let checked = features.cross_check(ArchProfile::Armv8MBase);
+    assert_eq!(checked.dsp, FeatureSupport::Absent);
+    assert!(checked.has_divide() && checked.has_security_extension());
+    assert!(!features
+        .cross_check(ArchProfile::Armv7M)
+        .has_security_extension());
+    assert_eq!(features.cross_check(ArchProfile::Unknown(0xF)), features);
+
+    // BitField_instrs is ID_ISAR0[11:8], not CmpBranch_instrs at [15:12]
+    id.isar[0] = 0x0110_1010;
+    assert_eq!(IsaFeatures::decode(&id).bitfield, FeatureSupport::Absent);
+    id.isar[0] = 0x0110_0110;
+    assert_eq!(IsaFeatures::decode(&id).bitfield, FeatureSupport::Present);
+}
+
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_region_contains_v7() {
+    use crate::peripheral::mpu::{AccessPermission, MemoryAttributes, RegionConfig, RegionSize};
+
+    let mut region = RegionConfig {
+        base: 0x2000_0000,
+        size: RegionSize::Size1K,
+        access: AccessPermission::ReadWrite,
+        attributes: MemoryAttributes::normal_noncacheable(),
+        subregion_disable: 0,
+        execute_never: true,
+    };
+    assert!(!region.contains(0x1FFF_FFFF));
+    assert!(region.contains(0x2000_0000));
+    assert!(region.contains(0x2000_03FF));
+    assert!(!region.contains(0x2000_0400));
+
+    // disable the second 128-byte subregion
+    region.subregion_disable = 0b0000_0010;
+    assert!(region.contains(0x2000_007F));
+    assert!(!region.contains(0x2000_0080));
+    assert!(!region.contains(0x2000_00FF));
+    assert!(region.contains(0x2000_0100));
+
+    region.base = 0;
+    region.size = RegionSize::Size4G;
+    region.subregion_disable = 0;
+    assert!(region.contains(0xFFFF_FFFF));
+}
+
+#[test]
+fn mpu_region_contains_v8() {
+    use crate::peripheral::mpu::{AccessPermissionV8, RegionConfigV8};
+
+    let region = RegionConfigV8 {
+        base: 0x2000_0000,
+        limit: 0x2000_03FF,
+        attr_index: 0,
+        access: AccessPermissionV8::ReadWrite,
+        execute_never: true,
+    };
+    assert!(!region.contains(0x1FFF_FFFF));
+    assert!(region.contains(0x2000_0000));
+    assert!(region.contains(0x2000_03FF));
+    assert!(!region.contains(0x2000_0400));
+}
+
+#[test]
+fn scb_cfsr() {
+    use crate::peripheral::scb::Cfsr;
+
+    // MemManage data access violation with a valid MMFAR, and a division by zero
+    let cfsr = Cfsr(0x0200_0082);
+    assert_eq!(cfsr.mmfsr(), 0x82);
+    assert!(cfsr.daccviol() && cfsr.mmarvalid());
+    assert!(!cfsr.iaccviol() && !cfsr.mstkerr());
+    assert_eq!(cfsr.bfsr(), 0);
+    assert_eq!(cfsr.ufsr(), 0x0200);
+    assert!(cfsr.divbyzero());
+}
+
+#[test]
+fn scb_cpacr_access() {
+    use crate::peripheral::scb::{cpacr_access, cpacr_with_access, CpAccess};
+
+    // FPU (CP10, CP11) fully enabled
+    let fpu = 0b1111 << 20;
+
+    let cpacr = cpacr_with_access(fpu, 0, CpAccess::Full);
+    assert_eq!(cpacr, fpu | 0b11);
+    let cpacr = cpacr_with_access(cpacr, 7, CpAccess::Privileged);
+    assert_eq!(cpacr, fpu | 0b01 << 14 | 0b11);
+    assert_eq!(cpacr_access(cpacr, 0), CpAccess::Full);
+    assert_eq!(cpacr_access(cpacr, 7), CpAccess::Privileged);
+    assert_eq!(cpacr_access(cpacr, 3), CpAccess::Denied);
+
+    // the other coprocessors are left alone
+    let cpacr = cpacr_with_access(cpacr, 0, CpAccess::Denied);
+    assert_eq!(cpacr, fpu | 0b01 << 14);
+
+    // reserved encoding
+    assert_eq!(cpacr_access(0b10 << 2, 1), CpAccess::Denied);
+}
+
+#[test]
+fn dcb_monitor_core_register_access() {
+    use crate::peripheral::dcb::monitor::{read_core_register, write_core_register, CoreRegister};
+    use crate::peripheral::dcb::RegisterBlock;
+
+    // DHCSR, DCRSR, DCRDR and DEMCR of a fake core
+    let mut regs = [0u32; 4];
+    let dcb = regs.as_mut_ptr() as *const RegisterBlock;
+
+    // running: nothing is accessed
+    regs[2] = 0x1234;
+    assert_eq!(unsafe { read_core_register(dcb, CoreRegister::Pc) }, None);
+    assert_eq!(
+        unsafe { write_core_register(dcb, CoreRegister::Pc, 0) },
+        None
+    );
+    assert_eq!(regs[1], 0);
+
+    // halted, with S_REGRDY set
+    regs[0] = 0b11 << 16;
+    assert_eq!(
+        unsafe { read_core_register(dcb, CoreRegister::Pc) },
+        Some(0x1234)
+    );
+    assert_eq!(regs[1], 15);
+    assert_eq!(
+        unsafe { read_core_register(dcb, CoreRegister::R(13)) },
+        None
+    );
+
+    assert_eq!(
+        unsafe { write_core_register(dcb, CoreRegister::Psp, 0x2000_0100) },
+        Some(())
+    );
+    assert_eq!(regs[1], 18 | 1 << 16);
+    assert_eq!(regs[2], 0x2000_0100);
+}
+
+#[test]
+fn dcb_monitor_registers() {
+    use crate::peripheral::dcb::monitor::{
+        read_callee_saved_register, read_stacked_register, CoreRegister,
+    };
+    use crate::register::frame::{CoreRegisters, ExceptionFrame};
+
+    let frame = ExceptionFrame {
+        r0: 0,
+        r1: 1,
+        r2: 2,
+        r3: 3,
+        r12: 12,
+        lr: 0x0800_0101,
+        pc: 0x0800_0200,
+        xpsr: 0x0100_0000,
+    };
+    let regs = CoreRegisters {
+        r4: 4,
+        r5: 5,
+        r6: 6,
+        r7: 7,
+        r8: 8,
+        r9: 9,
+        r10: 10,
+        r11: 11,
+    };
+
+    // R0-R12 come from either the frame or the callee-saved registers, never both
+    for n in 0..=12 {
+        let reg = CoreRegister::R(n);
+        let stacked = read_stacked_register(&frame, reg);
+        let saved = read_callee_saved_register(&regs, reg);
+        assert!(stacked.is_some() != saved.is_some());
+        assert_eq!(stacked.or(saved), Some(u32::from(n)));
+        assert_eq!(reg.selector(), Some(u32::from(n)));
+    }
+    assert_eq!(
+        read_stacked_register(&frame, CoreRegister::Pc),
+        Some(0x0800_0200)
+    );
+    assert_eq!(
+        read_stacked_register(&frame, CoreRegister::Lr),
+        Some(0x0800_0101)
+    );
+    assert_eq!(
+        read_stacked_register(&frame, CoreRegister::Xpsr),
+        Some(0x0100_0000)
+    );
+    assert_eq!(read_stacked_register(&frame, CoreRegister::Sp), None);
+    assert_eq!(read_stacked_register(&frame, CoreRegister::R(13)), None);
+
+    assert_eq!(CoreRegister::R(13).selector(), None);
+    assert_eq!(CoreRegister::Special.selector(), Some(20));
+    assert_eq!(CoreRegister::Fpscr.selector(), Some(33));
+    assert_eq!(CoreRegister::S(31).selector(), Some(95));
+    assert_eq!(CoreRegister::S(32).selector(), None);
+}
+
+#[test]
+fn dcb_debug_status() {
+    use crate::peripheral::dcb::DebugStatus;
+
+    assert_eq!(DebugStatus::from_bits(0), DebugStatus::default());
+
+    // running after a reset, with a debugger attached
+    let status = DebugStatus::from_bits(0x0300_0001);
+    assert!(status.debug_enabled && status.retired_since_read && status.reset_since_read);
+    assert!(!status.halted && !status.locked_up && !status.sleeping);
+
+    // locked up, nothing retired since
+    let status = DebugStatus::from_bits(0x0008_0000);
+    assert!(status.locked_up);
+    assert!(!status.retired_since_read && !status.debug_enabled);
+
+    // halted in Debug state with a register transfer done
+    let status = DebugStatus::from_bits(0x0003_0003);
+    assert!(status.halted && status.register_ready && status.debug_enabled);
+    assert!(!status.sleeping);
+
+    assert!(DebugStatus::from_bits(0x0004_0000).sleeping);
+}
+
+#[test]
+fn dcb_vector_catch() {
+    use crate::peripheral::dcb::VectorCatch;
+
+    let events = VectorCatch::CORE_RESET | VectorCatch::HARD_FAULT;
+    assert_eq!(events.bits(), 1 << 10 | 1 << 0);
+    assert!(events.contains(VectorCatch::HARD_FAULT));
+    assert!(!events.contains(VectorCatch::HARD_FAULT | VectorCatch::BUS_FAULT));
+    assert!(events.intersects(VectorCatch::HARD_FAULT | VectorCatch::BUS_FAULT));
+    assert!(VectorCatch::ALL.contains(events | VectorCatch::SECURE_FAULT));
+
+    // TRCENA and MON_EN aren't vector catch events
+    let demcr = 1 << 24 | 1 << 16 | 1 << 8;
+    assert_eq!(VectorCatch::from_bits(demcr), VectorCatch::BUS_FAULT);
+    assert!(VectorCatch::from_bits(1 << 24).is_empty());
+}
+
+#[test]
+fn scb_scr() {
+    use crate::peripheral::scb::Scr;
+
+    let mut scr = Scr::default();
+    scr.set_sleepdeep(true);
+    scr.set_sevonpend(true);
+    assert_eq!(scr, Scr(0b1_0100));
+    assert!(!scr.sleeponexit());
+
+    let mut scr = Scr(0b1_1110);
+    scr.set_sleepdeep(false);
+    assert!(scr.sleeponexit() && scr.sevonpend());
+    // bits without a field are kept
+    assert_eq!(scr, Scr(0b1_1010));
+}
+
+#[test]
+fn scb_dfsr() {
+    use crate::peripheral::scb::Dfsr;
+
+    // FPB breakpoint hit while a monitor mode step was pending
+    let dfsr = Dfsr(0b0_0011);
+    assert!(dfsr.halted() && dfsr.bkpt());
+    assert!(!dfsr.dwttrap() && !dfsr.vcatch() && !dfsr.external());
+
+    let dfsr = Dfsr(0b1_1100);
+    assert!(!dfsr.halted() && !dfsr.bkpt());
+    assert!(dfsr.dwttrap() && dfsr.vcatch() && dfsr.external());
+}
+
+#[test]
+fn scb_crash_report() {
+    extern crate std;
+    use std::string::String;
+
+    use crate::peripheral::scb::{write_crash_report, Cfsr};
+    use crate::register::frame::ExceptionFrame;
+
+    let frame = ExceptionFrame {
+        r0: 0,
+        r1: 1,
+        r2: 2,
+        r3: 3,
+        r12: 12,
+        lr: 0x0800_0101,
+        pc: 0x0800_0200,
+        xpsr: 0x0100_0000,
+    };
+
+    let mut report = String::new();
+    write_crash_report(
+        &mut report,
+        &frame,
+        Cfsr(0x0000_0082),
+        0x2000_1000,
+        0x4000_0000,
+    )
+    .unwrap();
+    assert_eq!(
+        report,
+        "R0=0x00000000 R1=0x00000001 R2=0x00000002 R3=0x00000003 R12=0x0000000c \
+         LR=0x08000101 PC=0x08000200 xPSR=0x01000000 CFSR=0x00000082 [DACCVIOL MMARVALID] \
+         MMFAR=0x20001000\n"
+    );
+
+    // precise bus fault, the stale MMFAR is left out
+    let mut report = String::new();
+    write_crash_report(
+        &mut report,
+        &frame,
+        Cfsr(0x0000_8200),
+        0x2000_1000,
+        0x4000_0000,
+    )
+    .unwrap();
+    assert!(report.ends_with("CFSR=0x00008200 [PRECISERR BFARVALID] BFAR=0x40000000\n"));
+
+    // every flag has a name, the reserved bits none
+    let mut report = String::new();
+    write_crash_report(&mut report, &frame, Cfsr(!0), 0, 0).unwrap();
+    let flags = report.split('[').nth(1).unwrap().split(']').next().unwrap();
+    assert_eq!(flags.split(' ').count(), 20);
+
+    let mut report = String::new();
+    write_crash_report(&mut report, &frame, Cfsr(0), 0, 0).unwrap();
+    assert!(report.ends_with("CFSR=0x00000000 []\n"));
+}
+
+#[test]
+fn scb_cfsr_stacking() {
+    use crate::peripheral::scb::Cfsr;
+
+    // MSTKERR, STKERR
+    for bits in [0x0000_0010, 0x0000_1000] {
+        let cfsr = Cfsr(bits);
+        assert!(cfsr.is_stacking_fault());
+        assert!(!cfsr.is_unstacking_fault());
+    }
+
+    // MUNSTKERR, UNSTKERR
+    for bits in [0x0000_0008, 0x0000_0800] {
+        let cfsr = Cfsr(bits);
+        assert!(!cfsr.is_stacking_fault());
+        assert!(cfsr.is_unstacking_fault());
+    }
+
+    // Both, e.g. a BusFault on unstacking followed by one on stacking for the fault handler
+    let cfsr = Cfsr(0x0000_1800);
+    assert!(cfsr.is_stacking_fault() && cfsr.is_unstacking_fault());
+
+    // Precise data bus error, lazy FP state preservation error, and DACCVIOL
+    for bits in [0x0000_8200, 0x0000_2000, 0x0000_0082, 0] {
+        let cfsr = Cfsr(bits);
+        assert!(!cfsr.is_stacking_fault());
+        assert!(!cfsr.is_unstacking_fault());
+    }
+}
+
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_validate_table() {
+    use crate::peripheral::mpu::{
+        validate_table, AccessPermission, MemoryAttributes, MpuError, RegionConfig, RegionSize,
+    };
+
+    const BACKGROUND: RegionConfig = RegionConfig {
+        base: 0,
+        size: RegionSize::Size4G,
+        access: AccessPermission::ReadWrite,
+        attributes: MemoryAttributes::strongly_ordered(),
+        subregion_disable: 0,
+        execute_never: true,
+    };
+    const SRAM: RegionConfig = RegionConfig {
+        base: 0x2000_0000,
+        size: RegionSize::Size64K,
+        access: AccessPermission::ReadWrite,
+        attributes: MemoryAttributes::normal_cacheable_wb_wa(),
+        subregion_disable: 0,
+        execute_never: true,
+    };
+    const _: () = assert!(validate_table(&[BACKGROUND, SRAM]).is_ok());
+
+    // the background region would hide the SRAM region
+    assert_eq!(
+        validate_table(&[SRAM, BACKGROUND]),
+        Err(MpuError::ShadowedRegion)
+    );
+    // unless the subregion containing SRAM is disabled
+    let background = RegionConfig {
+        subregion_disable: 0b0000_0010,
+        ..BACKGROUND
+    };
+    assert_eq!(validate_table(&[SRAM, background]), Ok(()));
+    // identical settings are harmless
+    let sram = RegionConfig {
+        base: 0x2000_4000,
+        size: RegionSize::Size16K,
+        ..SRAM
+    };
+    assert_eq!(validate_table(&[sram, SRAM]), Ok(()));
+
+    let misaligned = RegionConfig {
+        base: 0x2000_1000,
+        ..SRAM
+    };
+    assert_eq!(
+        validate_table(&[BACKGROUND, misaligned]),
+        Err(MpuError::MisalignedBase)
+    );
+}
+
+#[test]
+#[cfg(not(armv8m))]
+fn mpu_covered_range() {
+    use crate::peripheral::mpu::{
+        AccessPermission, MemoryAttributes, MpuError, RegionConfig, RegionSize,
+    };
+
+    let config = RegionConfig {
+        base: 0,
+        size: RegionSize::Size32B,
+        access: AccessPermission::ReadWrite,
+        attributes: MemoryAttributes::normal_cacheable_wb_wa(),
+        subregion_disable: 0,
+        execute_never: true,
+    };
+
+    // 96 KiB of RAM in a 128 KiB region
+    let ram = config
+        .with_covered_range(0x2000_0000, RegionSize::Size128K, 0x2000_0000, 96 * 1024)
+        .unwrap();
+    assert_eq!(ram.base, 0x2000_0000);
+    assert_eq!(ram.size, RegionSize::Size128K);
+    assert_eq!(ram.subregion_disable, 0b1100_0000);
+    assert_eq!(ram.access, AccessPermission::ReadWrite);
+    assert!(ram.covered_ranges().eq([0x2000_0000..=0x2001_7FFF]));
+
+    // a range in the middle
+    let middle = config
+        .with_covered_range(0x2000_0000, RegionSize::Size1K, 0x2000_0080, 0x200)
+        .unwrap();
+    assert_eq!(middle.subregion_disable, !0b0001_1110);
+    assert!(middle.covered_ranges().eq([0x2000_0080..=0x2000_027F]));
+
+    // whole regions, also below 256 bytes
+    let whole = config
+        .with_covered_range(0x2000_0000, RegionSize::Size64B, 0x2000_0000, 64)
+        .unwrap();
+    assert_eq!(whole.subregion_disable, 0);
+    assert!(whole.covered_ranges().eq([0x2000_0000..=0x2000_003F]));
+    // the length can't express all of a 4 GiB region
+    let all = config
+        .with_covered_range(0, RegionSize::Size4G, 0, u32::MAX)
+        .unwrap_err();
+    assert_eq!(all, MpuError::InvalidSubregionRange);
+
+    assert_eq!(
+        config.with_covered_range(0x2000_0000, RegionSize::Size1K, 0x2000_0010, 0x80),
+        Err(MpuError::InvalidSubregionRange)
+    );
+    assert_eq!(
+        config.with_covered_range(0x2000_0000, RegionSize::Size1K, 0x2000_0000, 0x800),
+        Err(MpuError::InvalidSubregionRange)
+    );
+    assert_eq!(
+        config.with_covered_range(0x2000_0100, RegionSize::Size1K, 0x2000_0100, 0x80),
+        Err(MpuError::MisalignedBase)
+    );
+    assert_eq!(
+        config.with_covered_range(0x2000_0000, RegionSize::Size128B, 0x2000_0000, 0x40),
+        Err(MpuError::SubregionsNotSupported)
+    );
+
+    // the inverse, with split ranges
+    let split = RegionConfig {
+        base: 0,
+        size: RegionSize::Size4G,
+        subregion_disable: 0b0110_0001,
+        ..config
+    };
+    assert!(split
+        .covered_ranges()
+        .eq([0x2000_0000..=0x9FFF_FFFF, 0xE000_0000..=0xFFFF_FFFF]));
+    for range in split.covered_ranges() {
+        assert!(split.contains(*range.start()) && split.contains(*range.end()));
+    }
+    assert!(!split.contains(0x1FFF_FFFF) && !split.contains(0xA000_0000));
+}
+
+#[test]
+fn mpu_dump() {
+    extern crate std;
+
+    use crate::peripheral::mpu::{
+        AccessPermission, MemoryAttributes, MpuDump, Region, RegionConfig, RegionSize,
+        MPU_DUMP_REGIONS,
+    };
+
+    let mut regions = [Region {
+        index: 0,
+        enabled: false,
+        config: None,
+    }; MPU_DUMP_REGIONS];
+    for (i, region) in regions.iter_mut().enumerate() {
+        region.index = i as u8;
+    }
+    regions[0] = Region {
+        index: 0,
+        enabled: true,
+        config: Some(RegionConfig {
+            base: 0x2000_0000,
+            size: RegionSize::Size32K,
+            access: AccessPermission::PrivilegedReadWriteUnprivilegedReadOnly,
+            attributes: MemoryAttributes::normal_cacheable_wb_wa(),
+            subregion_disable: 0,
+            execute_never: true,
+        }),
+    };
+    regions[2] = Region {
+        index: 2,
+        enabled: true,
+        config: Some(RegionConfig {
+            base: 0x0800_0000,
+            size: RegionSize::Size1M,
+            access: AccessPermission::ReadOnly,
+            attributes: MemoryAttributes::normal_cacheable_wt(),
+            subregion_disable: 0b1100_0000,
+            execute_never: false,
+        }),
+    };
+    regions[3].enabled = true;
+
+    let dump = MpuDump {
+        ctrl: 0b101,
+        region_count: 4,
+        regions,
+    };
+    assert_eq!(dump.regions().len(), 4);
+    assert_eq!(
+        std::format!("{:?}", dump),
+        "MPU enabled PRIVDEFENA, 4 regions\n\
+         #0: 0x20000000 32KiB priv rw- unpriv r-- tex=1 C B\n\
+         #1: disabled\n\
+         #2: 0x08000000 1MiB priv r-x unpriv r-x tex=0 C srd=0b11000000\n\
+         #3: reserved encoding"
+    );
+}
+
+#[test]
+fn fpu_fpccr() {
+    use crate::peripheral::fpu::Fpccr;
+
+    // reset value: ASPEN and LSPEN set
+    let fpccr = Fpccr(0xC000_0000);
+    assert!(fpccr.aspen());
+    assert!(fpccr.lspen());
+    assert!(!fpccr.lspact());
+
+    // lazy preservation pending for a privileged Thread mode context, all faults ready
+    let fpccr = Fpccr(0xC000_0179);
+    assert!(fpccr.lspact());
+    assert!(!fpccr.user());
+    assert!(fpccr.thread());
+    assert!(fpccr.hfrdy());
+    assert!(fpccr.mmrdy());
+    assert!(fpccr.bfrdy());
+    assert!(fpccr.monrdy());
+    assert!(!fpccr.splimviol());
+}
+
+#[test]
+fn fpu_features() {
+    use crate::peripheral::fpu::FpuFeatures;
+
+    // Cortex-M4F, single precision
+    let m4 = FpuFeatures::decode([0x1011_0021, 0x1100_0011, 0]);
+    assert!(m4.single_precision);
+    assert!(!m4.double_precision);
+    assert!(m4.divide);
+    assert!(m4.square_root);
+    assert!(m4.fused_multiply_accumulate);
+    assert!(m4.half_precision_conversion);
+
+    // Cortex-M7, double precision
+    let m7 = FpuFeatures::decode([0x1011_0221, 0x1200_0011, 0x0000_0040]);
+    assert!(m7.single_precision);
+    assert!(m7.double_precision);
+
+    // no FPU
+    assert_eq!(FpuFeatures::decode([0; 3]), FpuFeatures::default());
+}
+
+#[test]
+#[cfg(not(armv6m))]
+fn itm_timestamp_prescaler() {
+    use crate::peripheral::itm::{LocalTimestampOptions, TimestampConfig};
+
+    // 64 MHz: 15.625 ns per cycle
+    assert_eq!(
+        LocalTimestampOptions::for_resolution(64_000_000, 1),
+        LocalTimestampOptions::Enabled
+    );
+    assert_eq!(
+        LocalTimestampOptions::for_resolution(64_000_000, 100),
+        LocalTimestampOptions::EnabledDiv4
+    );
+    assert_eq!(
+        LocalTimestampOptions::for_resolution(64_000_000, 250),
+        LocalTimestampOptions::EnabledDiv16
+    );
+    assert_eq!(
+        LocalTimestampOptions::for_resolution(64_000_000, 1_000),
+        LocalTimestampOptions::EnabledDiv64
+    );
+    assert_eq!(LocalTimestampOptions::EnabledDiv16.divider(), Some(16));
+    assert_eq!(LocalTimestampOptions::Disabled.divider(), None);
+
+    let config = TimestampConfig {
+        prescaler: LocalTimestampOptions::EnabledDiv16,
+        tick_hz: 4_000_000,
+    };
+    assert_eq!(config.resolution_ns(), 250);
+    let config = TimestampConfig {
+        prescaler: LocalTimestampOptions::Enabled,
+        tick_hz: 48_000_000,
+    };
+    assert_eq!(config.resolution_ns(), 21);
+
+    // TSENA | SWOENA | TSPrescale = 0b10
+    assert_eq!(
+        LocalTimestampOptions::applied(0b10_0001_0011),
+        LocalTimestampOptions::EnabledDiv16
+    );
+    // the processor clock isn't prescaled
+    assert_eq!(
+        LocalTimestampOptions::applied(0b10_0000_0011),
+        LocalTimestampOptions::Enabled
+    );
+    // TSENA RAZ/WI
+    assert_eq!(
+        LocalTimestampOptions::applied(0b10_0001_0001),
+        LocalTimestampOptions::Disabled
+    );
+}
+
+#[test]
+fn cpuid_identity() {
+    extern crate std;
+
+    use crate::peripheral::cpuid::{ArchProfile, CoreIdentity, CorePart, Implementer};
+
+    let m7 = CoreIdentity::decode(0x411F_C272);
+    assert_eq!(m7.implementer, Implementer::Arm);
+    assert_eq!(m7.architecture, ArchProfile::Armv7M);
+    assert_eq!(m7.part, CorePart::CortexM7);
+    assert_eq!((m7.variant, m7.revision), (1, 2));
+    assert_eq!(std::format!("{}", m7), "Cortex-M7 r1p2");
+    assert!(!m7.is_cm7_r0p1());
+    assert!(CoreIdentity::decode(0x410F_C271).is_cm7_r0p1());
+
+    let m0plus = CoreIdentity::decode(0x410C_C601);
+    assert_eq!(m0plus.part, CorePart::CortexM0Plus);
+    assert_eq!(std::format!("{}", m0plus), "Cortex-M0+ r0p1");
+
+    let m33 = CoreIdentity::decode(0x410F_D214);
+    assert_eq!(m33.part, CorePart::CortexM33);
+
+    assert_eq!(m33.architecture, ArchProfile::Armv8MMain);
+    assert_eq!(
+        CoreIdentity::decode(0x410C_C601).architecture,
+        ArchProfile::Armv6M
+    );
+    assert_eq!(
+        CoreIdentity::decode(0x411C_D203).architecture,
+        ArchProfile::Armv8MBase
+    );
+    assert_eq!(
+        CoreIdentity::decode(0x411F_D220).architecture,
+        ArchProfile::Armv8_1M
+    );
+
+    let other = CoreIdentity::decode(0x5A0F_0010);
+    assert_eq!(other.implementer, Implementer::Unknown(0x5A));
+    assert_eq!(other.implementer.code(), 0x5A);
+    assert_eq!(other.architecture, ArchProfile::Unknown(0xF));
+    assert!(!CoreIdentity::decode(0x5A0F_C271).is_cm7_r0p1());
+    assert_eq!(other.part, CorePart::Unknown(0x001));
+    assert_eq!(std::format!("{}", other), "part 0x001 r0p0");
+}
+
+#[test]
+fn cpuid_revision_errata() {
+    use crate::errata;
+    use crate::peripheral::cpuid::CoreIdentity;
+
+    let r1p2 = CoreIdentity::decode(0x411F_C272);
+    assert!(r1p2.revision_at_least(0, 0));
+    assert!(r1p2.revision_at_least(0, 9));
+    assert!(r1p2.revision_at_least(1, 2));
+    assert!(!r1p2.revision_at_least(1, 3));
+    assert!(!r1p2.revision_at_least(2, 0));
+
+    assert!(errata::needs_basepri_workaround(&CoreIdentity::decode(
+        0x410F_C271
+    )));
+    assert!(!errata::needs_basepri_workaround(&r1p2));
+    // Cortex-M4 r0p1
+    assert!(!errata::needs_basepri_workaround(&CoreIdentity::decode(
+        0x410F_C241
+    )));
+}
+
+#[test]
+fn cpuid_cache_hierarchy() {
+    extern crate std;
+
+    use crate::peripheral::cpuid::{
+        CacheDescription, CacheHierarchy, CacheKind, CcsidrView, CsselrCacheType,
+    };
+
+    // Cortex-M7 with 16 KiB 2-way I-cache and 16 KiB 4-way D-cache, 32-byte lines
+    let mut selected = std::vec::Vec::new();
+    let hierarchy = CacheHierarchy::walk(0x0900_0003, |level, ind| {
+        selected.push((level, ind));
+        match ind {
+            CsselrCacheType::Instruction => 0xF01F_E009,
+            CsselrCacheType::DataOrUnified => 0xF00F_E019,
+        }
+    });
+    assert_eq!(
+        selected,
+        [
+            (0, CsselrCacheType::Instruction),
+            (0, CsselrCacheType::DataOrUnified)
+        ]
+    );
+
+    assert_eq!(hierarchy.levels().len(), 1);
+    assert!(hierarchy.level(0).is_none());
+    assert!(hierarchy.level(2).is_none());
+    let l1 = hierarchy.level(1).unwrap();
+    assert_eq!(
+        l1.instruction,
+        Some(CacheDescription {
+            level: 1,
+            kind: CacheKind::Instruction,
+            sets: 256,
+            ways: 2,
+            line_size_bytes: 32,
+            size_bytes: 16 * 1024,
+        })
+    );
+    assert_eq!(
+        l1.data,
+        Some(CacheDescription {
+            level: 1,
+            kind: CacheKind::Data,
+            sets: 128,
+            ways: 4,
+            line_size_bytes: 32,
+            size_bytes: 16 * 1024,
+        })
+    );
+
+    let ccsidr = CcsidrView(0xF00F_E019);
+    assert_eq!((ccsidr.sets(), ccsidr.ways()), (128, 4));
+    assert_eq!(ccsidr.line_size_bytes(), 32);
+    assert_eq!(ccsidr.size_bytes(), 16 * 1024);
+    assert!(ccsidr.write_through() && ccsidr.write_back());
+    assert!(ccsidr.read_allocate() && ccsidr.write_allocate());
+    assert!(!CcsidrView(0x200F_E019).write_back());
+
+    // A unified L2 below separate L1 caches
+    let hierarchy = CacheHierarchy::walk(0b100_011, |_, _| 0xF00F_E019);
+    assert_eq!(hierarchy.levels().len(), 2);
+    let l2 = hierarchy.level(2).unwrap();
+    assert_eq!(l2.instruction, None);
+    assert_eq!(l2.data.unwrap().kind, CacheKind::Unified);
+
+    // No caches at all
+    assert!(CacheHierarchy::walk(0, |_, _| unreachable!()).is_empty());
+}
+
+#[test]
+fn syst_calibrated_reload() {
+    use crate::peripheral::syst::{calibrated_reload, CalibError};
+
+    // 1 MHz reference clock: 10_000 ticks per 10 ms
+    let calib = 10_000 - 1;
+    assert_eq!(calibrated_reload(calib, 10), Ok(9_999));
+    assert_eq!(calibrated_reload(calib, 1), Ok(999));
+    assert_eq!(calibrated_reload(calib, 1_000), Ok(999_999));
+    assert_eq!(calibrated_reload(calib, 16_777), Ok(16_776_999));
+    assert_eq!(
+        calibrated_reload(calib, 16_778),
+        Err(CalibError::OutOfRange)
+    );
+    assert_eq!(calibrated_reload(calib, 0), Err(CalibError::OutOfRange));
+
+    // SKEW doesn't prevent calibration
+    assert_eq!(calibrated_reload(1 << 30 | calib, 1), Ok(999));
+
+    // TENMS not populated, or NOREF set
+    assert_eq!(calibrated_reload(0, 1), Err(CalibError::Unavailable));
+    assert_eq!(
+        calibrated_reload(1 << 31 | calib, 1),
+        Err(CalibError::Unavailable)
+    );
+}
+
+#[test]
+fn syst_reference_clock_estimate() {
+    use crate::peripheral::syst::{estimate_reference_clock, ReferenceClockHint};
+
+    const CORE_HZ: u32 = 72_000_000;
+    // 9 MHz reference clock: 90_000 ticks per 10 ms
+    let calib = 90_000 - 1;
+
+    assert_eq!(
+        estimate_reference_clock(calib, CORE_HZ, ReferenceClockHint::Calibration),
+        Some(9_000_000)
+    );
+    assert_eq!(
+        estimate_reference_clock(calib, CORE_HZ, ReferenceClockHint::CoreDivider(8)),
+        Some(9_000_000)
+    );
+    // the divider follows the processor clock, TENMS doesn't
+    assert_eq!(
+        estimate_reference_clock(calib, 16_000_000, ReferenceClockHint::CoreDivider(8)),
+        Some(2_000_000)
+    );
+    // SKEW only makes the estimate approximate
+    assert_eq!(
+        estimate_reference_clock(1 << 30 | calib, CORE_HZ, ReferenceClockHint::Calibration),
+        Some(9_000_000)
+    );
+
+    // TENMS not populated, no divider, or NOREF set
+    assert_eq!(
+        estimate_reference_clock(0, CORE_HZ, ReferenceClockHint::Calibration),
+        None
+    );
+    assert_eq!(
+        estimate_reference_clock(calib, CORE_HZ, ReferenceClockHint::CoreDivider(0)),
+        None
+    );
+    assert_eq!(
+        estimate_reference_clock(1 << 31 | calib, CORE_HZ, ReferenceClockHint::CoreDivider(8)),
+        None
+    );
+}
+
+#[test]
+fn cpuid_set_way_encoding() {
+    extern crate std;
+
+    use crate::peripheral::cpuid::{set_way_iter, CacheDescription, CacheKind};
+
+    // Cortex-M7 32 KiB 4-way D-cache with 32-byte lines: way in [31:30], set in [12:5]
+    let dcache = CacheDescription::decode(1, CacheKind::Data, 0xF01F_E019);
+    assert_eq!((dcache.sets, dcache.ways), (256, 4));
+    let all = set_way_iter(&dcache);
+    assert_eq!(all.clone().count(), 1024);
+
+    let set_ways: std::vec::Vec<_> = all.map(|sw| (sw.set(), sw.way(), sw.encode())).collect();
+    assert_eq!(set_ways[0], (0, 0, 0x0000_0000));
+    assert_eq!(set_ways[1], (0, 1, 0x4000_0000));
+    assert_eq!(set_ways[3], (0, 3, 0xC000_0000));
+    assert_eq!(set_ways[4], (1, 0, 0x0000_0020));
+    assert_eq!(set_ways[1023], (255, 3, 0xC000_1FE0));
+
+    // 4 KiB 4-way D-cache: set in [9:5]
+    let dcache = CacheDescription::decode(1, CacheKind::Data, 0xF003_E019);
+    assert_eq!(dcache.sets, 32);
+    assert_eq!(set_way_iter(&dcache).last().unwrap().encode(), 0xC000_03E0);
+
+    // A 2-way cache uses bit 31 for the way, L2 is encoded in the level field
+    let l2 = CacheDescription::decode(2, CacheKind::Unified, 0xF01F_E009);
+    let last = set_way_iter(&l2).last().unwrap();
+    assert_eq!((last.set(), last.way(), last.level()), (255, 1, 2));
+    assert_eq!(last.encode(), 0x8000_1FE2);
+
+    // A direct-mapped cache has no way field
+    let direct = CacheDescription::decode(1, CacheKind::Data, 0xF000_0001);
+    assert_eq!(direct.ways, 1);
+    assert_eq!(set_way_iter(&direct).last().unwrap().encode(), 0);
+}
+
+#[test]
+fn vector_table_layout() {
+    use crate::interrupt::{Handler, VectorTable};
+    use crate::peripheral::scb::Exception;
+
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    // distinct bodies, so that the handlers aren't merged into one function
+    static CALLED: AtomicUsize = AtomicUsize::new(0);
+    unsafe extern "C" fn reset() -> ! {
+        unreachable!()
+    }
+    unsafe extern "C" fn default_handler() {
+        CALLED.store(1, Ordering::Relaxed);
+    }
+    unsafe extern "C" fn systick() {
+        CALLED.store(2, Ordering::Relaxed);
+    }
+    unsafe extern "C" fn irq5() {
+        CALLED.store(3, Ordering::Relaxed);
+    }
+
+    let table = VectorTable::<8>::new(0x2000_4000 as *const u32, reset, default_handler)
+        .exception(Exception::SysTick, systick)
+        .interrupt(5, irq5);
+    let words: [usize; 16 + 8] = unsafe { core::mem::transmute(table) };
+    let reset: unsafe extern "C" fn() -> ! = reset;
+    let [default_handler, systick, irq5] =
+        [default_handler, systick, irq5].map(|h: Handler| h as usize);
+    assert!(default_handler != systick && default_handler != irq5);
+    assert_eq!(CALLED.load(Ordering::Relaxed), 0);
+
+    assert_eq!(words[0], 0x2000_4000);
+    assert_eq!(words[1], reset as usize);
+    // NMI, HardFault, then MemManage, BusFault, UsageFault and SecureFault on the host
+    for word in &words[2..8] {
+        assert_eq!(*word, default_handler);
+    }
+    // reserved
+    for word in &words[8..11] {
+        assert_eq!(*word, 0);
+    }
+    assert_eq!(words[11], default_handler); // SVCall
+    assert_eq!(words[12], default_handler); // DebugMonitor
+    assert_eq!(words[13], 0);
+    assert_eq!(words[14], default_handler); // PendSV
+    assert_eq!(words[15], systick);
+    for (i, word) in words[16..].iter().enumerate() {
+        let handler = if i == 5 { irq5 } else { default_handler };
+        assert_eq!(*word, handler);
+    }
+}
+
+#[test]
+fn nvic_priority() {
+    use crate::peripheral::nvic::Priority;
+
+    assert_eq!(Priority::from_level(0, 4), Priority::HIGHEST);
+    assert_eq!(Priority::from_level(1, 4).raw(), 0x10);
+    assert_eq!(Priority::from_level(15, 4).raw(), 0xF0);
+    assert_eq!(Priority::from_level(3, 2).raw(), 0xC0);
+    assert_eq!(Priority::from_level(255, 8).raw(), 0xFF);
+    assert_eq!(Priority::from_level(1, 1).raw(), 0x80);
+
+    assert_eq!(Priority::from_raw(0xA0).level(3), 5);
+    assert_eq!(Priority::from_raw(0xA0).level(8), 0xA0);
+    // the deprecated `u8` argument of `NVIC::set_priority` is the raw byte
+    assert_eq!(Priority::from(0xA0), Priority::from_raw(0xA0));
+    assert_eq!(Priority::from_level(9, 4).level(4), 9);
+}
+
+#[test]
+#[should_panic(expected = "level doesn't fit in the priority bits")]
+fn nvic_priority_level_too_large() {
+    crate::peripheral::nvic::Priority::from_level(16, 4);
+}
+
+#[test]
+#[should_panic(expected = "invalid number of priority bits")]
+fn nvic_priority_no_bits() {
+    crate::peripheral::nvic::Priority::from_level(0, 0);
+}
+
+#[test]
+fn system_info_banner() {
+    extern crate std;
+
+    use crate::peripheral::cpuid::{CacheHierarchy, CoreIdentity, CsselrCacheType, Features};
+    use crate::peripheral::fpb::FpbCapabilities;
+    use crate::peripheral::fpu::FpuFeatures;
+    use crate::SystemInfo;
+
+    let mut info = SystemInfo {
+        identity: CoreIdentity::decode(0x411F_C272),
+        features: Features {
+            has_dsp: true,
+            has_divide: true,
+            has_fp: true,
+            has_fp_double: true,
+            ..Features::default()
+        },
+        fpu: FpuFeatures {
+            single_precision: true,
+            double_precision: true,
+            fused_multiply_accumulate: true,
+            ..FpuFeatures::default()
+        },
+        caches: CacheHierarchy::walk(0x0900_0003, |_, ind| match ind {
+            CsselrCacheType::Instruction => 0xF01F_E009,
+            CsselrCacheType::DataOrUnified => 0xF00F_E019,
+        }),
+        mpu_regions: 16,
+        fpb: FpbCapabilities::decode(0x1000_0080),
+        priority_bits: 3,
+        systick_calib: 0x4001_869F,
+    };
+    assert_eq!(
+        std::format!("{}", info),
+        "core:    Cortex-M7 r1p2, Armv7-M, Arm\n\
+         isa:     DSP divide\n\
+         fpu:     SP DP FMA\n\
+         caches:  L1 I 16 KiB 2-way 32 B lines, L1 D 16 KiB 4-way 32 B lines\n\
+         mpu:     16 regions\n\
+         fpb:     8 breakpoints, 0 literal comparators, v2\n\
+         nvic:    3 priority bits\n\
+         systick: 10 ms = 100000 ticks (inexact), reference clock"
+    );
+
+    // a bare core: no optional blocks, no calibration
+    info.identity = CoreIdentity::decode(0x410F_D200);
+    info.features = Features::default();
+    info.fpu = FpuFeatures::default();
+    info.caches = CacheHierarchy::walk(0, |_, _| 0);
+    info.mpu_regions = 0;
+    info.fpb = FpbCapabilities::decode(0x0000_0260);
+    info.systick_calib = 0x8000_0000;
+    assert_eq!(
+        std::format!("{}", info),
+        "core:    Cortex-M23 r0p0, Armv8-M Baseline, Arm\n\
+         isa:     baseline\n\
+         fpu:     none\n\
+         caches:  none\n\
+         mpu:     none\n\
+         fpb:     6 breakpoints, 2 literal comparators, v1\n\
+         nvic:    3 priority bits\n\
+         systick: no calibration, no reference clock"
+    );
+}
+
+#[test]
+fn fpb_capabilities() {
+    use crate::peripheral::fpb::{FpbCapabilities, FpbRevision};
+
+    // Cortex-M4: 6 instruction and 2 literal comparators
+    let m4 = FpbCapabilities::decode(0x0000_0260);
+    assert_eq!(m4.code_comparators, 6);
+    assert_eq!(m4.literal_comparators, 2);
+    assert_eq!(m4.revision, FpbRevision::V1);
+    assert_eq!(m4.comparators(), 8);
+
+    // Cortex-M7: 8 instruction comparators, FPB v2
+    let m7 = FpbCapabilities::decode(0x1000_0080);
+    assert_eq!(m7.code_comparators, 8);
+    assert_eq!(m7.literal_comparators, 0);
+    assert_eq!(m7.revision, FpbRevision::V2);
+
+    // NUM_CODE2 holds the upper bits of the instruction comparator count
+    assert_eq!(FpbCapabilities::decode(0x0000_1020).code_comparators, 0x12);
+    assert_eq!(
+        FpbCapabilities::decode(0x3000_0000).revision,
+        FpbRevision::Unknown(3)
+    );
+}
+
+#[test]
+fn fpb_encode_breakpoint() {
+    use crate::peripheral::fpb::{encode_breakpoint, FpbError, FpbRevision};
+
+    // v1 compares words and picks the halfword with REPLACE
+    assert_eq!(
+        encode_breakpoint(FpbRevision::V1, 0x0800_1234),
+        Ok(0x4800_1235)
+    );
+    assert_eq!(
+        encode_breakpoint(FpbRevision::V1, 0x0800_1236),
+        Ok(0x8800_1235)
+    );
+    assert_eq!(
+        encode_breakpoint(FpbRevision::V1, 0x2000_0000),
+        Err(FpbError::AddressNotBreakable)
+    );
+
+    // v2 compares the halfword address anywhere in the memory map
+    assert_eq!(
+        encode_breakpoint(FpbRevision::V2, 0x0800_1236),
+        Ok(0x0800_1237)
+    );
+    assert_eq!(
+        encode_breakpoint(FpbRevision::V2, 0x2000_0100),
+        Ok(0x2000_0101)
+    );
+
+    assert_eq!(
+        encode_breakpoint(FpbRevision::V2, 0x0800_1235),
+        Err(FpbError::MisalignedAddress)
+    );
+    assert_eq!(
+        encode_breakpoint(FpbRevision::Unknown(2), 0x0800_1234),
+        Err(FpbError::UnsupportedRevision)
+    );
+}
+
+#[test]
+fn fpb_encode_remap() {
+    use crate::peripheral::fpb::{encode_remap, FpbError, FpbRevision, RemapTable};
+
+    assert_eq!(encode_remap(FpbRevision::V1, 0x0800_1234), Ok(0x0800_1235));
+    assert_eq!(encode_remap(FpbRevision::V2, 0x0800_1234), Ok(0x8800_1234));
+    assert_eq!(
+        encode_remap(FpbRevision::V1, 0x0800_1236),
+        Err(FpbError::AddressNotRemappable)
+    );
+    assert_eq!(
+        encode_remap(FpbRevision::V1, 0x2000_0000),
+        Err(FpbError::AddressNotRemappable)
+    );
+
+    static TABLE: RemapTable = RemapTable::new([0; 8]);
+    assert_eq!(TABLE.address() % 32, 0);
+}
+
+#[test]
+fn tpiu_swo_prescaler() {
+    use crate::peripheral::tpiu::{swo_prescaler, TpiuError};
+
+    assert_eq!(swo_prescaler(72_000_000, 2_000_000), Ok(35));
+    // 64 MHz / 10.67 Mbaud = 5.99, which rounds to a divider of 6
+    assert_eq!(swo_prescaler(64_000_000, 10_666_667), Ok(5));
+    assert_eq!(swo_prescaler(64_000_000, 64_000_000), Ok(0));
+    assert_eq!(swo_prescaler(65_536, 1), Ok(0xFFFF));
+
+    assert_eq!(swo_prescaler(65_537, 1), Err(TpiuError::BaudOutOfRange));
+    assert_eq!(swo_prescaler(1_000_000, 0), Err(TpiuError::BaudOutOfRange));
+    assert_eq!(
+        swo_prescaler(1_000_000, 3_000_000),
+        Err(TpiuError::BaudOutOfRange)
+    );
+}
+
+#[test]
+fn itm_cycle_count_before_sync() {
+    use crate::peripheral::dwt::SyncTap;
+    use crate::peripheral::itm::cycle_count_before_sync;
+
+    assert_eq!(
+        cycle_count_before_sync(0x0000_1234, SyncTap::Cycles2Pow24),
+        0x00FF_FFF0
+    );
+    // the tapped bit is cleared, so that it goes from 0 to 1
+    assert_eq!(
+        cycle_count_before_sync(0x0180_0000, SyncTap::Cycles2Pow24),
+        0x00FF_FFF0
+    );
+    assert_eq!(
+        cycle_count_before_sync(0x1234_5678, SyncTap::Cycles2Pow26),
+        0x13FF_FFF0
+    );
+    assert_eq!(
+        cycle_count_before_sync(0xF000_0000, SyncTap::Cycles2Pow28),
+        0xEFFF_FFF0
+    );
+}
+
+#[test]
+fn tpiu_swo_baud_tolerance() {
+    use crate::peripheral::tpiu::{swo_baud, SwoBaud, TpiuError, DEFAULT_SWO_TOLERANCE_PPM};
+
+    assert_eq!(
+        swo_baud(72_000_000, 2_000_000, DEFAULT_SWO_TOLERANCE_PPM),
+        Ok(SwoBaud {
+            prescaler: 35,
+            achievable: 2_000_000
+        })
+    );
+
+    // 64 MHz / 3 = 21.33 Mbaud, 3% off 22 Mbaud
+    assert_eq!(
+        swo_baud(64_000_000, 22_000_000, DEFAULT_SWO_TOLERANCE_PPM),
+        Err(TpiuError::BaudOutOfTolerance {
+            requested: 22_000_000,
+            achievable: 21_333_333
+        })
+    );
+    assert_eq!(
+        swo_baud(64_000_000, 22_000_000, 50_000),
+        Ok(SwoBaud {
+            prescaler: 2,
+            achievable: 21_333_333
+        })
+    );
+
+    // just inside and just outside 1%
+    assert!(swo_baud(101_000_000, 100_000_000, DEFAULT_SWO_TOLERANCE_PPM).is_ok());
+    assert!(swo_baud(101_000_001, 100_000_000, DEFAULT_SWO_TOLERANCE_PPM).is_err());
+
+    assert_eq!(
+        swo_baud(1_000_000, 0, DEFAULT_SWO_TOLERANCE_PPM),
+        Err(TpiuError::BaudOutOfRange)
+    );
+}
+
+#[test]
+fn tpiu_poll_until() {
+    use crate::peripheral::tpiu::poll_until;
+
+    let mut polls = 0;
+    assert!(poll_until(0, || {
+        polls += 1;
+        true
+    }));
+    assert_eq!(polls, 1);
+
+    // the condition is polled once more when the timeout is reached
+    polls = 0;
+    assert!(!poll_until(640, || {
+        polls += 1;
+        false
+    }));
+    assert_eq!(polls, 11);
+
+    polls = 0;
+    assert!(poll_until(u32::MAX, || {
+        polls += 1;
+        polls == 3
+    }));
+    assert_eq!(polls, 3);
+}
+
+#[test]
+fn tpiu_port_width_selection() {
+    use crate::peripheral::tpiu::{port_width_selection, TpiuError};
+
+    // 1, 2 and 4 bit wide ports
+    const SSPSR: u32 = 0b1011;
+    assert_eq!(port_width_selection(SSPSR, 1), Ok(0b0001));
+    assert_eq!(port_width_selection(SSPSR, 4), Ok(0b1000));
+    assert_eq!(
+        port_width_selection(SSPSR, 3),
+        Err(TpiuError::PortWidthUnsupported)
+    );
+    assert_eq!(
+        port_width_selection(SSPSR, 0),
+        Err(TpiuError::PortWidthUnsupported)
+    );
+    assert_eq!(
+        port_width_selection(u32::MAX, 33),
+        Err(TpiuError::PortWidthUnsupported)
+    );
+    assert_eq!(port_width_selection(u32::MAX, 32), Ok(1 << 31));
+}
+
+#[test]
+fn mpu_type() {
+    use crate::peripheral::mpu::Type;
+
+    let t = Type(0x0000_0800);
+    assert!(!t.separate());
+    assert_eq!(t.dregion(), 8);
+    assert_eq!(t.iregion(), 0);
+}
+
 #[test]
 fn nvic() {
     let nvic = unsafe { &*crate::peripheral::NVIC::PTR };
@@ -121,6 +1976,17 @@ fn nvic() {
     assert_eq!(address(&nvic.stir), 0xE000EF00);
 }
 
+#[test]
+fn scb_vector_table_alignment() {
+    use crate::peripheral::scb::vector_table_alignment;
+
+    assert_eq!(vector_table_alignment(16), 128);
+    assert_eq!(vector_table_alignment(32), 128);
+    assert_eq!(vector_table_alignment(33), 256);
+    assert_eq!(vector_table_alignment(16 + 68), 512);
+    assert_eq!(vector_table_alignment(512), 2048);
+}
+
 #[test]
 fn scb() {
     let scb = unsafe { &*crate::peripheral::SCB::PTR };
@@ -159,6 +2025,7 @@ fn tpiu() {
     assert_eq!(address(&tpiu.cspsr), 0xE004_0004);
     assert_eq!(address(&tpiu.acpr), 0xE004_0010);
     assert_eq!(address(&tpiu.sppr), 0xE004_00F0);
+    assert_eq!(address(&tpiu.ffsr), 0xE004_0300);
     assert_eq!(address(&tpiu.ffcr), 0xE004_0304);
     assert_eq!(address(&tpiu.lar), 0xE004_0FB0);
     assert_eq!(address(&tpiu.lsr), 0xE004_0FB4);
@@ -1,6 +1,15 @@
 //! Trace Port Interface Unit;
 //!
 //! *NOTE* Not available on Armv6-M.
+//!
+//! # Formatter
+//!
+//! The formatter wraps the trace data of each source in frames tagged with its trace ID, so that
+//! several sources can share the output. It must be on for the parallel trace port, and whenever
+//! ETM trace is mixed with ITM and DWT trace. For raw ITM and DWT trace over SWO it must be off
+//! (bypass mode): SWO decoders such as `itmdump` expect bare ITM packets and print garbage when
+//! fed formatter frames. The reset state varies between vendors, so set it explicitly with
+//! [`TPIU::enable_continuous_formatting`].
 
 use volatile_register::{RO, RW, WO};
 
@@ -20,7 +29,9 @@ pub struct RegisterBlock {
     reserved1: [u32; 55],
     /// Selected Pin Control
     pub sppr: RW<Sppr>,
-    reserved2: [u32; 132],
+    reserved2: [u32; 131],
+    /// Formatter and Flush Status
+    pub ffsr: RO<Ffsr>,
     /// Formatter and Flush Control
     pub ffcr: RW<Ffcr>,
     reserved3: [u32; 810],
@@ -39,6 +50,18 @@ bitfield! {
     #[derive(Clone, Copy)]
     pub struct Ffcr(u32);
     enfcont, set_enfcont: 1;
+    fonman, set_fonman: 6;
+}
+
+bitfield! {
+    /// Formatter and flush status register.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Ffsr(u32);
+    flinprog, _: 0;
+    ftstopped, _: 1;
+    tcpresent, _: 2;
+    ftnonstop, _: 3;
 }
 
 bitfield! {
@@ -101,10 +124,172 @@ pub struct SWOSupports {
     pub min_queue_size: u8,
 }
 
+/// The state of the formatter, see [`TPIU::formatter_status`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct FormatterStatus {
+    /// A flush is in progress.
+    pub flush_in_progress: bool,
+    /// The formatter has stopped, after a flush or a trigger.
+    pub stopped: bool,
+    /// The formatter can't be stopped.
+    pub non_stop: bool,
+}
+
+/// The asynchronous Serial Wire Output (SWO) encodings.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SwoProtocol {
+    /// Manchester encoding
+    Manchester,
+    /// NRZ (UART) encoding
+    Nrz,
+}
+
+impl From<SwoProtocol> for TraceProtocol {
+    #[inline]
+    fn from(protocol: SwoProtocol) -> Self {
+        match protocol {
+            SwoProtocol::Manchester => TraceProtocol::AsyncSWOManchester,
+            SwoProtocol::Nrz => TraceProtocol::AsyncSWONRZ,
+        }
+    }
+}
+
+/// Possible error values returned by the [`TPIU`] SWO configuration functions.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[non_exhaustive]
+pub enum TpiuError {
+    /// The TPIU doesn't support the requested SWO encoding.
+    ProtocolUnsupported,
+    /// The TPIU supports no asynchronous SWO encoding at all.
+    SwoUnsupported,
+    /// The baud rate can't be derived from the trace clock with the prescaler.
+    BaudOutOfRange,
+    /// The TPIU has no parallel trace port, only SWO.
+    ParallelUnsupported,
+    /// The parallel trace port doesn't support the requested width.
+    PortWidthUnsupported,
+    /// The closest baud rate the prescaler can produce is further from the requested one than
+    /// the tolerance allows.
+    BaudOutOfTolerance {
+        /// The requested baud rate.
+        requested: u32,
+        /// The closest achievable baud rate.
+        achievable: u32,
+    },
+    /// The trace data wasn't drained before the timeout.
+    FlushTimeout,
+}
+
+/// The default tolerance of [`swo_baud`] and [`TPIU::configure_swo`], 1% in parts per million.
+///
+/// An asynchronous receiver resynchronizes on every start bit, so a few percent of error is
+/// tolerated in principle; in practice receivers with their own clock error start dropping bytes
+/// well before that, under sustained load.
+pub const DEFAULT_SWO_TOLERANCE_PPM: u32 = 10_000;
+
+/// The SWO prescaler setting for a baud rate, see [`swo_baud`].
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct SwoBaud {
+    /// The value of the ACPR prescaler.
+    pub prescaler: u16,
+    /// The baud rate the prescaler produces.
+    pub achievable: u32,
+}
+
+/// The number of cycles between two polls of a bounded wait, see [`poll_until`].
+const POLL_INTERVAL_CYCLES: u32 = 64;
+
+/// Polls `done` until it returns `true`, giving up after at least `timeout_cycles` cycles.
+///
+/// Returns `false` on timeout. On the host, the time between polls isn't waited.
+pub(crate) fn poll_until(timeout_cycles: u32, mut done: impl FnMut() -> bool) -> bool {
+    let mut waited: u32 = 0;
+    loop {
+        if done() {
+            return true;
+        }
+        if waited >= timeout_cycles {
+            return false;
+        }
+        #[cfg(cortex_m)]
+        crate::asm::delay(POLL_INTERVAL_CYCLES);
+        waited = waited.saturating_add(POLL_INTERVAL_CYCLES);
+    }
+}
+
+/// Computes the SWO prescaler for `baud` from the trace clock `trace_clk_hz`, checking that the
+/// achievable baud rate is within `tolerance_ppm` parts per million of `baud`.
+///
+/// Being a `const fn`, this lets board crates check their clock tree at compile time:
+///
+/// ```
+/// use cortex_m::peripheral::tpiu::{swo_baud, DEFAULT_SWO_TOLERANCE_PPM};
+///
+/// const TRACE_CLK_HZ: u32 = 72_000_000;
+/// const SWO_BAUD: u32 = 2_000_000;
+/// const _: () = assert!(swo_baud(TRACE_CLK_HZ, SWO_BAUD, DEFAULT_SWO_TOLERANCE_PPM).is_ok());
+/// ```
+///
+/// Returns [`TpiuError::BaudOutOfRange`] if no 16-bit prescaler gets close to `baud`, and
+/// [`TpiuError::BaudOutOfTolerance`] if the closest one isn't close enough. Some TPIUs implement
+/// fewer prescaler bits, which [`TPIU::configure_swo`] checks.
+#[inline]
+pub const fn swo_baud(
+    trace_clk_hz: u32,
+    baud: u32,
+    tolerance_ppm: u32,
+) -> Result<SwoBaud, TpiuError> {
+    let prescaler = match swo_prescaler(trace_clk_hz, baud) {
+        Ok(prescaler) => prescaler,
+        Err(e) => return Err(e),
+    };
+    let achievable = trace_clk_hz / (prescaler as u32 + 1);
+    let error = achievable.abs_diff(baud);
+    if error as u64 * 1_000_000 > tolerance_ppm as u64 * baud as u64 {
+        return Err(TpiuError::BaudOutOfTolerance {
+            requested: baud,
+            achievable,
+        });
+    }
+    Ok(SwoBaud {
+        prescaler,
+        achievable,
+    })
+}
+
+/// Returns the CSPSR value selecting a `bits` wide port, if the SSPSR value `sspsr` lists it.
+pub(crate) const fn port_width_selection(sspsr: u32, bits: u8) -> Result<u32, TpiuError> {
+    if bits == 0 || bits > 32 {
+        return Err(TpiuError::PortWidthUnsupported);
+    }
+    // bit `n - 1` stands for a width of `n` bits, in both registers
+    let selection = 1 << (bits - 1);
+    if sspsr & selection == 0 {
+        return Err(TpiuError::PortWidthUnsupported);
+    }
+    Ok(selection)
+}
+
+/// Computes the prescaler dividing `trace_clk_hz` to the closest rate to `baud`.
+pub(crate) const fn swo_prescaler(trace_clk_hz: u32, baud: u32) -> Result<u16, TpiuError> {
+    if baud == 0 {
+        return Err(TpiuError::BaudOutOfRange);
+    }
+    // the baud rate is `trace_clk_hz / (prescaler + 1)`, rounded to the nearest divider
+    let divider = (trace_clk_hz as u64 + baud as u64 / 2) / baud as u64;
+    if divider == 0 || divider > 1 << 16 {
+        return Err(TpiuError::BaudOutOfRange);
+    }
+    Ok((divider - 1) as u16)
+}
+
 impl TPIU {
     /// Sets the prescaler value for a wanted baud rate of the Serial
     /// Wire Output (SWO) in relation to a given asynchronous refernce
     /// clock rate.
+    ///
+    /// See [`TPIU::configure_swo`] for a version that rounds to the
+    /// closest rate and checks the result.
     #[inline]
     pub fn set_swo_baud_rate(&mut self, ref_clk_rate: u32, baud_rate: u32) {
         unsafe {
@@ -135,6 +320,10 @@ impl TPIU {
     /// Whether to enable the formatter. If disabled, only ITM and DWT
     /// trace sources are passed through. Data from the ETM is
     /// discarded.
+    ///
+    /// Enable it for the parallel trace port and to mix ETM trace in;
+    /// disable it for raw ITM and DWT trace over SWO. See the
+    /// [module documentation](crate::peripheral::tpiu#formatter).
     #[inline]
     pub fn enable_continuous_formatting(&mut self, bit: bool) {
         unsafe {
@@ -145,6 +334,64 @@ impl TPIU {
         }
     }
 
+    /// Starts a manual flush of the trace data buffered in the TPIU
+    /// (FFCR.FOnMan).
+    ///
+    /// The flush completes asynchronously. FFSR.FlInProg may still read
+    /// as clear right after this returns, so use
+    /// [`flush_and_wait`](TPIU::flush_and_wait) rather than polling
+    /// [`FormatterStatus::flush_in_progress`] alone.
+    #[inline]
+    pub fn trigger_flush(&mut self) {
+        unsafe {
+            self.ffcr.modify(|mut r| {
+                r.set_fonman(true);
+                r
+            });
+        }
+    }
+
+    /// Flushes the trace data buffered in the TPIU, and waits for the
+    /// flush to complete.
+    ///
+    /// Call this before entering deep sleep or resetting, when the
+    /// trace clock stops and buffered data would be lost. Data still in
+    /// the ITM isn't covered, see [`itm::flush`](crate::itm::flush)
+    /// for the whole path.
+    ///
+    /// The flush is complete once FFCR.FOnMan has cleared and FFSR.FlInProg
+    /// is clear, or once the formatter has stopped.
+    ///
+    /// Returns [`TpiuError::FlushTimeout`] if the flush hasn't completed
+    /// after at least `timeout_cycles` processor cycles, for example
+    /// because the trace clock is stopped.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn flush_and_wait(&mut self, timeout_cycles: u32) -> Result<(), TpiuError> {
+        self.trigger_flush();
+        // FlInProg may not be set yet right after the trigger, but FOnMan reads as set until the
+        // flush has completed
+        let drained = poll_until(timeout_cycles, || {
+            let status = self.formatter_status();
+            (!self.ffcr.read().fonman() && !status.flush_in_progress) || status.stopped
+        });
+        if drained {
+            Ok(())
+        } else {
+            Err(TpiuError::FlushTimeout)
+        }
+    }
+
+    /// Reads the formatter and flush status (FFSR).
+    #[inline]
+    pub fn formatter_status(&self) -> FormatterStatus {
+        let ffsr = self.ffsr.read();
+        FormatterStatus {
+            flush_in_progress: ffsr.flinprog(),
+            stopped: ffsr.ftstopped(),
+            non_stop: ffsr.ftnonstop(),
+        }
+    }
+
     /// Reads the supported trace output modes and the minimum size of
     /// the TPIU FIFO queue for trace data.
     #[inline]
@@ -157,4 +404,126 @@ impl TPIU {
             min_queue_size: _type.fifosz(),
         }
     }
+
+    /// Selects an asynchronous SWO encoding.
+    ///
+    /// Returns an error if the TPIU doesn't support `protocol`, see
+    /// [`TPIU::swo_supports`].
+    #[inline]
+    pub fn set_swo_protocol(&mut self, protocol: SwoProtocol) -> Result<(), TpiuError> {
+        let supports = Self::swo_supports();
+        let supported = match protocol {
+            SwoProtocol::Manchester => supports.manchester_encoding,
+            SwoProtocol::Nrz => supports.nrz_encoding,
+        };
+        if !supported {
+            return Err(TpiuError::ProtocolUnsupported);
+        }
+        self.set_trace_output_protocol(protocol.into());
+        Ok(())
+    }
+
+    /// Sets the SWO prescaler: the SWO baud rate is the trace clock
+    /// divided by `div + 1`.
+    ///
+    /// Some TPIUs, such as the ones of the Cortex-M3 and Cortex-M4,
+    /// only implement the low 13 bits.
+    #[inline]
+    pub fn set_prescaler(&mut self, div: u16) {
+        unsafe { self.acpr.write(u32::from(div)) }
+    }
+
+    /// Configures SWO output at the closest rate to `baud` the trace
+    /// clock `trace_clk_hz` can be divided to, and returns that rate.
+    ///
+    /// NRZ encoding is used if the TPIU supports it, Manchester
+    /// encoding otherwise. The achievable rate must be within
+    /// [`DEFAULT_SWO_TOLERANCE_PPM`] of `baud`, see
+    /// [`configure_swo_with_tolerance`](TPIU::configure_swo_with_tolerance).
+    ///
+    /// Returns an error if the TPIU doesn't support asynchronous SWO, or
+    /// if no prescaler the TPIU implements gives a rate close to `baud`.
+    #[inline]
+    pub fn configure_swo(&mut self, trace_clk_hz: u32, baud: u32) -> Result<u32, TpiuError> {
+        self.configure_swo_with_tolerance(trace_clk_hz, baud, DEFAULT_SWO_TOLERANCE_PPM)
+    }
+
+    /// Like [`configure_swo`](TPIU::configure_swo), with a tolerance
+    /// of `tolerance_ppm` parts per million of `baud`, see
+    /// [`swo_baud`].
+    ///
+    /// Nothing is changed if an error is returned. Whether the TPIU
+    /// implements the prescaler is found by writing it and reading it
+    /// back, so ACPR is restored to its previous value if it doesn't.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn configure_swo_with_tolerance(
+        &mut self,
+        trace_clk_hz: u32,
+        baud: u32,
+        tolerance_ppm: u32,
+    ) -> Result<u32, TpiuError> {
+        let supports = Self::swo_supports();
+        let protocol = if supports.nrz_encoding {
+            SwoProtocol::Nrz
+        } else if supports.manchester_encoding {
+            SwoProtocol::Manchester
+        } else {
+            return Err(TpiuError::SwoUnsupported);
+        };
+
+        let SwoBaud {
+            prescaler,
+            achievable,
+        } = swo_baud(trace_clk_hz, baud, tolerance_ppm)?;
+        let previous = self.acpr.read();
+        self.set_prescaler(prescaler);
+        // the unimplemented high bits of the prescaler read as zero
+        if self.acpr.read() != u32::from(prescaler) {
+            unsafe { self.acpr.write(previous) }
+            return Err(TpiuError::BaudOutOfRange);
+        }
+        // the protocol was checked against `swo_supports` above
+        self.set_trace_output_protocol(protocol.into());
+
+        Ok(achievable)
+    }
+
+    /// Returns the widths, in bits, that the parallel trace port
+    /// supports, from SSPSR.
+    ///
+    /// SWO-only implementations support none.
+    #[inline]
+    pub fn supported_port_widths(&self) -> impl Iterator<Item = u8> {
+        let sspsr = self.sspsr.read();
+        (1..=32).filter(move |&bits| port_width_selection(sspsr, bits).is_ok())
+    }
+
+    /// Selects the width, in bits, of the parallel trace port, which
+    /// should match the trace pins routed on the board.
+    ///
+    /// Returns [`TpiuError::ParallelUnsupported`] on SWO-only
+    /// implementations, so that callers can fall back to SWO, and
+    /// [`TpiuError::PortWidthUnsupported`] if `bits` isn't one of the
+    /// [`supported_port_widths`](TPIU::supported_port_widths).
+    #[inline]
+    pub fn set_port_width(&mut self, bits: u8) -> Result<(), TpiuError> {
+        if !Self::swo_supports().parallel_operation {
+            return Err(TpiuError::ParallelUnsupported);
+        }
+        let selection = port_width_selection(self.sspsr.read(), bits)?;
+        unsafe { self.cspsr.write(selection) }
+        Ok(())
+    }
+
+    /// Returns the selected width, in bits, of the parallel trace port,
+    /// from CSPSR, or `None` if no single width is selected.
+    #[inline]
+    pub fn current_port_width(&self) -> Option<u8> {
+        let cspsr = self.cspsr.read();
+        if cspsr.count_ones() == 1 {
+            Some(cspsr.trailing_zeros() as u8 + 1)
+        } else {
+            None
+        }
+    }
 }
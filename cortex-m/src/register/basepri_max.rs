@@ -9,32 +9,17 @@ use core::arch::asm;
 /// - `basepri != 0` AND `basepri < basepri::read()`
 ///
 /// **IMPORTANT** If you are using a Cortex-M7 device with revision r0p1 you MUST enable the
-/// `cm7-r0p1` Cargo feature or this function WILL misbehave.
+/// `cm7-r0p1` Cargo feature (or `cm7-r0p1-detect`) or this function WILL misbehave (erratum
+/// 837070).
 #[cfg(cortex_m)]
 #[inline]
 pub fn write(basepri: u8) {
-    #[cfg(feature = "cm7-r0p1")]
-    {
-        unsafe {
-            asm!(
-                "mrs {1}, PRIMASK",
-                "cpsid i",
-                "tst.w {1}, #1",
-                "msr BASEPRI_MAX, {0}",
-                "it ne",
-                "bxne lr",
-                "cpsie i",
-                in(reg) basepri,
-                out(reg) _,
-                options(nomem, nostack, preserves_flags),
-            );
-        }
-    }
-
-    #[cfg(not(feature = "cm7-r0p1"))]
-    {
-        unsafe {
-            asm!("msr BASEPRI_MAX, {}", in(reg) basepri, options(nomem, nostack, preserves_flags));
-        }
+    let msr = || unsafe {
+        asm!("msr BASEPRI_MAX, {}", in(reg) basepri, options(nomem, nostack, preserves_flags));
+    };
+    if crate::errata::basepri_workaround_active() {
+        unsafe { super::write_837070(msr) };
+    } else {
+        msr();
     }
 }
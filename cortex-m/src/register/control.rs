@@ -5,6 +5,8 @@ use core::arch::asm;
 #[cfg(cortex_m)]
 use core::sync::atomic::{compiler_fence, Ordering};
 
+use crate::register::frame::EXC_RETURN_FTYPE;
+
 /// Control register
 #[derive(Clone, Copy, Debug)]
 pub struct Control {
@@ -64,6 +66,20 @@ impl Control {
         }
     }
 
+    /// The stack pointer in use while executing exception number `exception_number`, `0` being
+    /// Thread mode
+    ///
+    /// SPSEL only selects the stack pointer of Thread mode: Handler mode always uses MSP, whatever
+    /// SPSEL says. See [`active_stack`] for the stack pointer in use right now.
+    #[inline]
+    pub fn active_stack(self, exception_number: u16) -> Spsel {
+        if exception_number == 0 {
+            self.spsel()
+        } else {
+            Spsel::Msp
+        }
+    }
+
     /// Whether context floating-point is currently active
     #[inline]
     pub fn fpca(self) -> Fpca {
@@ -83,6 +99,44 @@ impl Control {
             Fpca::NotActive => self.bits &= !mask,
         }
     }
+
+    /// Whether FPCA agrees with the frame type of `exc_return`: active for an extended frame
+    /// (`EXC_RETURN.FType` clear), not active for a basic frame
+    #[inline]
+    pub fn matches_frame_type(self, exc_return: u32) -> bool {
+        let extended = exc_return & EXC_RETURN_FTYPE == 0;
+        self.fpca().is_active() == extended
+    }
+
+    /// Whether the floating-point context belongs to the Secure state (SFPA)
+    ///
+    /// This bit only exists in the Secure CONTROL register of Armv8-M processors with the Security
+    /// Extension and an FPU.
+    #[cfg(all(armv8m, has_fpu))]
+    #[inline]
+    pub fn sfpa(self) -> Sfpa {
+        if self.bits & (1 << 3) == (1 << 3) {
+            Sfpa::Active
+        } else {
+            Sfpa::NotActive
+        }
+    }
+
+    /// Sets the SFPA value.
+    ///
+    /// **Security**: SFPA tells the processor whether the live FP registers hold Secure data that
+    /// must be preserved and cleared before Non-secure code can see them. Clearing it while the
+    /// registers hold Secure data lets that data leak to Non-secure code, so only clear it after
+    /// the Secure FP context has been saved and scrubbed.
+    #[cfg(all(armv8m, has_fpu))]
+    #[inline]
+    pub fn set_sfpa(&mut self, sfpa: Sfpa) {
+        let mask = 1 << 3;
+        match sfpa {
+            Sfpa::Active => self.bits |= mask,
+            Sfpa::NotActive => self.bits &= !mask,
+        }
+    }
 }
 
 /// Thread mode privilege level
@@ -154,6 +208,38 @@ impl Fpca {
     }
 }
 
+/// Whether the floating-point context belongs to the Secure state
+///
+/// The processor sets SFPA when Secure code executes a floating-point instruction, and clears it
+/// automatically on exception entry and on a function call to Non-secure state (`BLXNS`), after
+/// the Secure FP context has been saved or scheduled for lazy preservation. Lazy state
+/// preservation relies on it to decide which FP context must be protected on a security state
+/// transition, so hand-written context switches must keep it consistent with the FP registers,
+/// see [`Control::set_sfpa`].
+#[cfg(all(armv8m, has_fpu))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Sfpa {
+    /// The FP registers hold Secure floating-point context.
+    Active,
+    /// The FP registers don't hold Secure floating-point context.
+    NotActive,
+}
+
+#[cfg(all(armv8m, has_fpu))]
+impl Sfpa {
+    /// Do the FP registers hold Secure floating-point context?
+    #[inline]
+    pub fn is_active(self) -> bool {
+        self == Sfpa::Active
+    }
+
+    /// Do the FP registers not hold Secure floating-point context?
+    #[inline]
+    pub fn is_not_active(self) -> bool {
+        self == Sfpa::NotActive
+    }
+}
+
 /// Reads the CPU register
 #[cfg(cortex_m)]
 #[inline]
@@ -163,6 +249,43 @@ pub fn read() -> Control {
     Control { bits }
 }
 
+/// Returns the stack pointer currently in use
+///
+/// This is MSP in Handler mode (IPSR non-zero), and the stack pointer selected by CONTROL.SPSEL in
+/// Thread mode.
+#[cfg(cortex_m)]
+#[inline]
+pub fn active_stack() -> Spsel {
+    read().active_stack(crate::register::xpsr::read().exception_number())
+}
+
+/// Checks that a task's saved CONTROL value agrees with the frame type of its saved `exc_return`,
+/// see [`Control::matches_frame_type`]
+///
+/// A scheduler that builds task contexts by hand has to keep the two in step: a task resumed with
+/// an extended frame but FPCA clear, or the other way around, runs fine until the first context
+/// switch after it uses the FPU, and then faults or corrupts its FP registers. Call this in the
+/// context switch with the CONTROL and EXC_RETURN values saved for the next task, before
+/// restoring them.
+///
+/// Don't check the live CONTROL register instead: inside the switching handler, FPCA reflects
+/// the handler's own FP use, e.g. the `vstmdb`/`vldmia` that save and restore `s16`-`s31` set it,
+/// so it says nothing about the task being resumed.
+///
+/// This is a debug-only check: it panics on mismatch if `debug_assertions` are enabled, and does
+/// nothing otherwise.
+#[inline]
+pub fn assert_fp_consistency(saved_control: Control, exc_return: u32) {
+    if cfg!(debug_assertions) {
+        assert!(
+            saved_control.matches_frame_type(exc_return),
+            "saved CONTROL.FPCA ({:?}) doesn't match the frame type of EXC_RETURN {:#010x}",
+            saved_control.fpca(),
+            exc_return
+        );
+    }
+}
+
 /// Writes to the CPU register.
 #[cfg(cortex_m)]
 #[inline]
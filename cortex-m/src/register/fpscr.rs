@@ -1,6 +1,7 @@
 //! Floating-point Status Control Register
 
 use core::arch::asm;
+use core::ops::{BitOr, BitOrAssign};
 
 /// Floating-point Status Control Register
 #[derive(Clone, Copy, Debug)]
@@ -251,6 +252,205 @@ impl Fpscr {
             false => self.bits &= !mask,
         }
     }
+
+    /// Has an invalid operation occurred since the flag was last cleared?
+    ///
+    /// Alias of [`ioc`](Self::ioc).
+    #[inline]
+    pub fn invalid_operation(self) -> bool {
+        self.ioc()
+    }
+
+    /// Has a division by zero occurred since the flag was last cleared?
+    ///
+    /// Alias of [`dzc`](Self::dzc).
+    #[inline]
+    pub fn division_by_zero(self) -> bool {
+        self.dzc()
+    }
+
+    /// Has an overflow occurred since the flag was last cleared?
+    ///
+    /// Alias of [`ofc`](Self::ofc).
+    #[inline]
+    pub fn overflow(self) -> bool {
+        self.ofc()
+    }
+
+    /// Has an underflow occurred since the flag was last cleared?
+    ///
+    /// Alias of [`ufc`](Self::ufc).
+    #[inline]
+    pub fn underflow(self) -> bool {
+        self.ufc()
+    }
+
+    /// Has an inexact result occurred since the flag was last cleared?
+    ///
+    /// Alias of [`ixc`](Self::ixc).
+    #[inline]
+    pub fn inexact(self) -> bool {
+        self.ixc()
+    }
+
+    /// Has a denormal input been flushed to zero since the flag was last cleared?
+    ///
+    /// Alias of [`idc`](Self::idc).
+    #[inline]
+    pub fn input_denormal(self) -> bool {
+        self.idc()
+    }
+
+    /// Is flush-to-zero mode enabled? Alias of [`fz`](Self::fz).
+    #[inline]
+    pub fn flush_to_zero(self) -> bool {
+        self.fz()
+    }
+
+    /// Enables or disables flush-to-zero mode (`FZ`)
+    ///
+    /// **Numerical effect**: in flush-to-zero mode subnormal inputs and results are silently
+    /// replaced by a zero of the same sign, so tiny values lose all precision instead of degrading
+    /// gradually, `x - y == 0.0` no longer implies `x == y`, and the `IDC` and `UFC` flags report
+    /// the flushing. Results are no longer IEEE 754 compliant.
+    ///
+    /// The compiler assumes IEEE 754 behavior, i.e. `FZ` and `DN` cleared: it evaluates constant
+    /// expressions and optimizes floating-point code (and the soft-float routines used for `f64`
+    /// on single-precision FPUs ignore FPSCR entirely) as if subnormals were kept. Code running
+    /// with this mode can therefore see different results depending on what got constant folded
+    /// or inlined. Confine it to the `#[inline(never)]` functions that need it, and don't rely on
+    /// exact results for subnormal values.
+    #[inline]
+    pub fn set_flush_to_zero(&mut self, enabled: bool) {
+        self.set_fz(enabled)
+    }
+
+    /// Is default NaN mode enabled? Alias of [`dn`](Self::dn).
+    #[inline]
+    pub fn default_nan(self) -> bool {
+        self.dn()
+    }
+
+    /// Enables or disables default NaN mode (`DN`)
+    ///
+    /// In default NaN mode any operation producing a NaN returns the default quiet NaN
+    /// (`0x7FC0_0000`) instead of propagating the payload of an input NaN, which makes results
+    /// deterministic but loses NaN payloads. See [`set_flush_to_zero`](Self::set_flush_to_zero)
+    /// for the compiler's assumptions, which apply here too.
+    #[inline]
+    pub fn set_default_nan(&mut self, enabled: bool) {
+        self.set_dn(enabled)
+    }
+
+    /// Is the alternative half-precision format selected? Alias of [`ahp`](Self::ahp).
+    #[inline]
+    pub fn alternative_half_precision(self) -> bool {
+        self.ahp()
+    }
+
+    /// Selects the alternative half-precision format (`AHP`) instead of IEEE 754 half-precision
+    ///
+    /// This only affects the half-precision conversion instructions (`VCVTB`, `VCVTT`). The
+    /// alternative format has no infinities or NaNs and uses the maximum exponent for normal
+    /// numbers instead. It has no effect if the FPU doesn't implement half-precision conversions,
+    /// see `MVFR1.FPHP`.
+    #[inline]
+    pub fn set_alternative_half_precision(&mut self, enabled: bool) {
+        self.set_ahp(enabled)
+    }
+
+    /// Returns the set cumulative exception flags
+    #[inline]
+    pub fn flags(self) -> FlagSet {
+        FlagSet::from_bits(self.bits)
+    }
+
+    /// Clears the cumulative exception flags in `flags`, leaving all other bits untouched
+    #[inline]
+    pub fn clear_flags(&mut self, flags: FlagSet) {
+        self.bits &= !flags.bits;
+    }
+}
+
+/// A set of FPSCR cumulative exception flags
+///
+/// Sets are combined with `|`, e.g. `FlagSet::OVERFLOW | FlagSet::UNDERFLOW`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FlagSet {
+    bits: u32,
+}
+
+impl FlagSet {
+    /// Invalid Operation (`IOC`)
+    pub const INVALID_OPERATION: Self = Self { bits: 1 << 0 };
+    /// Division by Zero (`DZC`)
+    pub const DIVISION_BY_ZERO: Self = Self { bits: 1 << 1 };
+    /// Overflow (`OFC`)
+    pub const OVERFLOW: Self = Self { bits: 1 << 2 };
+    /// Underflow (`UFC`)
+    pub const UNDERFLOW: Self = Self { bits: 1 << 3 };
+    /// Inexact (`IXC`)
+    pub const INEXACT: Self = Self { bits: 1 << 4 };
+    /// Input Denormal (`IDC`)
+    pub const INPUT_DENORMAL: Self = Self { bits: 1 << 7 };
+    /// All cumulative exception flags
+    pub const ALL: Self = Self { bits: 0b1001_1111 };
+
+    /// The empty set
+    #[inline]
+    pub const fn empty() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// Creates a set from FPSCR bits, ignoring all bits other than the cumulative exception flags
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Self {
+            bits: bits & Self::ALL.bits,
+        }
+    }
+
+    /// Returns the flags as FPSCR bits
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.bits
+    }
+
+    /// Is no flag set?
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Are all flags of `other` set in `self`?
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Is any flag of `other` set in `self`?
+    #[inline]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.bits & other.bits != 0
+    }
+}
+
+impl BitOr for FlagSet {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self {
+        Self {
+            bits: self.bits | rhs.bits,
+        }
+    }
+}
+
+impl BitOrAssign for FlagSet {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.bits |= rhs.bits;
+    }
 }
 
 /// Rounding mode
@@ -266,6 +466,9 @@ pub enum RMode {
     Zero,
 }
 
+/// Rounding mode, see [`rounding_mode`] and [`set_rounding_mode`]
+pub type RoundingMode = RMode;
+
 impl RMode {
     /// Is Nearest the current rounding mode?
     #[inline]
@@ -306,3 +509,93 @@ pub unsafe fn write(fpscr: Fpscr) {
     let fpscr = fpscr.bits();
     asm!("vmsr fpscr, {}", in(reg) fpscr, options(nomem, nostack));
 }
+
+/// Clears the cumulative exception flags in `flags`
+///
+/// The rounding mode, the condition flags and the other control bits are preserved. With automatic
+/// FP state preservation enabled (`FPCCR.ASPEN`, the reset default), FPSCR is part of the
+/// floating-point context that exception entry saves (or, with `FPCCR.LSPEN`, reserves space for
+/// and saves lazily) and exception return restores, so an interrupt can't change it between the
+/// read and the write. With `ASPEN` cleared, handlers that use the FPU must save and restore FPSCR
+/// themselves, or this read-modify-write can lose their changes.
+#[inline]
+pub fn clear_flags(flags: FlagSet) {
+    let mut fpscr = read();
+    fpscr.clear_flags(flags);
+    unsafe { write(fpscr) };
+}
+
+/// Returns the cumulative exception flags and clears them
+///
+/// Use it to check whether a block of floating-point code raised any exception:
+///
+/// ```no_run
+/// use cortex_m::register::fpscr::{self, FlagSet};
+///
+/// # fn compute() {}
+/// fpscr::take_flags();
+/// compute();
+/// if fpscr::take_flags().intersects(FlagSet::INVALID_OPERATION | FlagSet::OVERFLOW) {
+///     // the result can't be trusted
+/// }
+/// ```
+///
+/// See [`clear_flags`] for what is preserved.
+#[inline]
+pub fn take_flags() -> FlagSet {
+    let mut fpscr = read();
+    let flags = fpscr.flags();
+    fpscr.clear_flags(FlagSet::ALL);
+    unsafe { write(fpscr) };
+    flags
+}
+
+/// Returns the current rounding mode
+#[inline]
+pub fn rounding_mode() -> RoundingMode {
+    read().rmode()
+}
+
+/// Sets the rounding mode, preserving all other FPSCR bits
+///
+/// # Safety
+///
+/// The compiler assumes the default round-to-nearest mode: floating-point operations it evaluates
+/// at compile time, or moves across the mode change, are rounded to nearest regardless. Keep the
+/// code relying on another mode in `#[inline(never)]` functions and restore round-to-nearest
+/// before returning to code that expects it; [`with_rounding_mode`] does the latter.
+#[inline]
+pub unsafe fn set_rounding_mode(mode: RoundingMode) {
+    let mut fpscr = read();
+    fpscr.set_rmode(mode);
+    write(fpscr);
+}
+
+/// Runs `f` with the rounding mode set to `mode`, then restores the previous rounding mode
+///
+/// The previous mode is restored however `f` returns, so an early `return` from the code in the
+/// closure can't leave the mode changed.
+///
+/// ```no_run
+/// use core::hint::black_box;
+/// use cortex_m::register::fpscr::{self, RoundingMode};
+///
+/// let third = unsafe {
+///     fpscr::with_rounding_mode(RoundingMode::Zero, || black_box(1.0f32) / 3.0)
+/// };
+/// ```
+///
+/// # Safety
+///
+/// See [`set_rounding_mode`].
+#[inline]
+pub unsafe fn with_rounding_mode<F, R>(mode: RoundingMode, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let previous = rounding_mode();
+    set_rounding_mode(mode);
+    let r = f();
+    set_rounding_mode(previous);
+    r
+}
@@ -0,0 +1,150 @@
+//! Register snapshots for fault handlers
+//!
+//! On exception entry the processor stacks R0-R3, R12, LR, PC and xPSR. The callee-saved registers
+//! R4-R11 are not stacked and are only preserved as long as the handler doesn't use them, so they
+//! have to be captured with [`capture_registers!`](crate::capture_registers) before any other code
+//! runs.
+
+#[cfg(cortex_m)]
+use core::arch::asm;
+
+/// The basic frame the processor stacks on exception entry
+///
+/// This has the same layout as the `ExceptionFrame` of `cortex-m-rt`, so a reference to one can be
+/// cast to a reference to the other.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(C)]
+pub struct ExceptionFrame {
+    /// (General purpose) Register 0
+    pub r0: u32,
+    /// (General purpose) Register 1
+    pub r1: u32,
+    /// (General purpose) Register 2
+    pub r2: u32,
+    /// (General purpose) Register 3
+    pub r3: u32,
+    /// (General purpose) Register 12
+    pub r12: u32,
+    /// Linking Register
+    pub lr: u32,
+    /// Program Counter
+    pub pc: u32,
+    /// Program Status Register
+    pub xpsr: u32,
+}
+
+/// The callee-saved core registers R4-R11
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[repr(C)]
+pub struct CoreRegisters {
+    /// (General purpose) Register 4
+    pub r4: u32,
+    /// (General purpose) Register 5
+    pub r5: u32,
+    /// (General purpose) Register 6
+    pub r6: u32,
+    /// (General purpose) Register 7
+    pub r7: u32,
+    /// (General purpose) Register 8
+    pub r8: u32,
+    /// (General purpose) Register 9
+    pub r9: u32,
+    /// (General purpose) Register 10
+    pub r10: u32,
+    /// (General purpose) Register 11
+    pub r11: u32,
+}
+
+/// Implementation detail of `capture_registers!`. Do not use directly.
+#[cfg(cortex_m)]
+#[doc(hidden)]
+#[inline(always)]
+pub fn __capture(regs: &mut CoreRegisters) {
+    let ptr = regs as *mut CoreRegisters;
+
+    // NOTE only caller-saved registers are used as scratch registers so that R4-R11 are still
+    // untouched when they're stored
+    #[cfg(not(any(armv6m, armv8m_base)))]
+    unsafe {
+        asm!(
+            "stm {0}, {{r4-r11}}",
+            in(reg) ptr,
+            options(nostack, preserves_flags),
+        );
+    }
+
+    // ARMv6-M and ARMv8-M Baseline can only store the low registers with `stm`
+    #[cfg(any(armv6m, armv8m_base))]
+    unsafe {
+        asm!(
+            "stmia r0!, {{r4-r7}}",
+            "mov r1, r8",
+            "mov r2, r9",
+            "mov r3, r10",
+            "stmia r0!, {{r1-r3}}",
+            "mov r1, r11",
+            "str r1, [r0]",
+            inout("r0") ptr => _,
+            out("r1") _,
+            out("r2") _,
+            out("r3") _,
+            options(nostack, preserves_flags),
+        );
+    }
+}
+
+pub(crate) const EXC_RETURN_FTYPE: u32 = 1 << 4;
+const EXC_RETURN_DCRS: u32 = 1 << 5;
+const XPSR_STACK_ALIGN: u32 = 1 << 9;
+
+/// Size of the additional state context (integrity signature, reserved word and R4-R11) that
+/// Armv8-M stacks below the basic frame when `EXC_RETURN.DCRS` is clear
+const ADDITIONAL_STATE_SIZE: usize = 40;
+
+/// Returns the offset in bytes of the stacked PC from the stack pointer `exc_return` selects,
+/// i.e. the address of the stacked frame
+///
+/// The stacked PC follows R0-R3, R12 and LR, 24 bytes into the basic frame. On Armv8-M, when
+/// `EXC_RETURN.DCRS` is clear, the additional state context is stacked first and the PC is 64
+/// bytes into the frame. The FP registers and the alignment padding are stacked above the
+/// stacked xPSR, so they don't move the PC, but they do change where the frame starts relative
+/// to the stack pointer before the exception, see [`stacked_frame_size`].
+#[inline]
+pub const fn stacked_pc_offset(exc_return: u32) -> usize {
+    let mut offset = 24;
+    if exc_return & EXC_RETURN_DCRS == 0 {
+        offset += ADDITIONAL_STATE_SIZE;
+    }
+    offset
+}
+
+/// Returns the offset in bytes of the stacked xPSR from the stack pointer `exc_return` selects
+#[inline]
+pub const fn stacked_xpsr_offset(exc_return: u32) -> usize {
+    stacked_pc_offset(exc_return) + 4
+}
+
+/// Returns the size in bytes of the stacked frame, including the alignment padding
+///
+/// `stacked_xpsr` is the xPSR value read from the frame, at
+/// [`stacked_xpsr_offset`]. Its bit 9 tells whether a padding word was inserted above the frame
+/// to align it to 8 bytes. `EXC_RETURN.FType` clear adds the 18-word FP extension (S0-S15,
+/// FPSCR and a reserved word), and `EXC_RETURN.DCRS` clear the 10-word additional state context.
+/// The stack pointer before the exception is the frame address plus this size.
+///
+/// The additional FP context (S16-S31) that Armv8-M stacks when `FPCCR.TS` is set isn't
+/// indicated by `EXC_RETURN` and isn't included.
+#[inline]
+pub const fn stacked_frame_size(stacked_xpsr: u32, exc_return: u32) -> usize {
+    let mut size = 32;
+    if exc_return & EXC_RETURN_FTYPE == 0 {
+        size += 72;
+    }
+    if exc_return & EXC_RETURN_DCRS == 0 {
+        size += ADDITIONAL_STATE_SIZE;
+    }
+    if stacked_xpsr & XPSR_STACK_ALIGN != 0 {
+        size += 4;
+    }
+    size
+}
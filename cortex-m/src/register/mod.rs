@@ -15,6 +15,7 @@
 //! - LR
 //! - PC
 //! - PSP
+//! - xPSR
 //!
 //! The following registers are NOT available on ARMv6-M devices
 //! (`thumbv6m-none-eabi`):
@@ -44,6 +45,8 @@ pub mod faultmask;
 #[cfg(has_fpu)]
 pub mod fpscr;
 
+pub mod frame;
+
 pub mod msp;
 
 pub mod primask;
@@ -61,3 +64,27 @@ pub mod apsr;
 pub mod lr;
 
 pub mod pc;
+
+pub mod xpsr;
+
+#[cfg(test)]
+mod test;
+
+/// Erratum 837070: on Cortex-M7 r0p1 a write that raises BASEPRI may not take effect until a few
+/// instructions later, so an interrupt can still be taken after it. Perform the write, done by
+/// `msr`, with interrupts disabled and followed by barriers, then re-enable interrupts only if
+/// they were enabled before.
+///
+/// Shared by the `BASEPRI` and `BASEPRI_MAX` writes.
+#[cfg(all(cortex_m, not(armv6m), not(armv8m_base)))]
+#[inline]
+unsafe fn write_837070(msr: impl FnOnce()) {
+    let primask = primask::read();
+    crate::interrupt::disable();
+    msr();
+    crate::asm::isb();
+    crate::asm::dsb();
+    if primask.is_active() {
+        crate::interrupt::enable();
+    }
+}
@@ -24,6 +24,32 @@ pub unsafe fn write(bits: u32) {
     asm!("msr MSP, {}", in(reg) bits, options(nomem, nostack, preserves_flags));
 }
 
+/// Writes `bits` to the CPU register, which must not be the stack pointer currently in use
+///
+/// MSP is only inactive in Thread mode with CONTROL.SPSEL set, i.e. while running on the process
+/// stack; Handler mode always uses MSP (see
+/// [`control::active_stack`](crate::register::control::active_stack)). Unlike [`write`], this
+/// doesn't pull the stack from under the running code, e.g. when resetting the main stack from a
+/// thread running on PSP.
+///
+/// # Panics
+///
+/// With debug assertions enabled, panics if MSP is the stack pointer in use.
+///
+/// # Safety
+///
+/// `bits` must point to the top of a valid stack before MSP is used again, which happens on the
+/// next exception entry.
+#[cfg(cortex_m)]
+#[inline]
+pub unsafe fn write_inactive(bits: u32) {
+    debug_assert!(
+        crate::register::control::active_stack().is_psp(),
+        "MSP is the active stack pointer"
+    );
+    asm!("msr MSP, {}", in(reg) bits, options(nomem, nostack, preserves_flags));
+}
+
 /// Reads the Non-Secure CPU register from Secure state.
 ///
 /// Executing this function in Non-Secure state will return zeroes.
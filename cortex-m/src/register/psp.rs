@@ -13,6 +13,8 @@ pub fn read() -> u32 {
 }
 
 /// Writes `bits` to the CPU register
+///
+/// Prefer [`write_inactive`], which checks that PSP isn't the stack pointer in use.
 #[cfg(cortex_m)]
 #[inline]
 pub unsafe fn write(bits: u32) {
@@ -20,3 +22,30 @@ pub unsafe fn write(bits: u32) {
     // if MSP is currently being used as the stack pointer.
     asm!("msr PSP, {}", in(reg) bits, options(nomem, nostack, preserves_flags));
 }
+
+/// Writes `bits` to the CPU register, which must not be the stack pointer currently in use
+///
+/// This is the recommended way to set up PSP, e.g. from the main stack before switching Thread
+/// mode to the process stack, or from an exception handler to switch tasks. PSP is in use in
+/// Thread mode with CONTROL.SPSEL set; Handler mode always uses MSP (see
+/// [`control::active_stack`](crate::register::control::active_stack)).
+///
+/// # Panics
+///
+/// With debug assertions enabled, panics if PSP is the stack pointer in use: overwriting it would
+/// move the stack under the running code and crash at some later, unrelated instruction. Use
+/// [`write`] only for the rare case of changing the active stack on purpose.
+///
+/// # Safety
+///
+/// `bits` must point to the top of a valid stack before PSP is used again, e.g. by an exception
+/// return to Thread mode on the process stack.
+#[cfg(cortex_m)]
+#[inline]
+pub unsafe fn write_inactive(bits: u32) {
+    debug_assert!(
+        crate::register::control::active_stack().is_msp(),
+        "PSP is the active stack pointer"
+    );
+    write(bits);
+}
@@ -0,0 +1,79 @@
+#[test]
+fn frame_layout() {
+    use crate::register::frame::{stacked_frame_size, stacked_pc_offset, stacked_xpsr_offset};
+
+    // return to Thread mode, PSP, basic frame
+    const BASIC: u32 = 0xFFFF_FFFD;
+    // return to Thread mode, PSP, extended (FP) frame
+    const EXTENDED: u32 = 0xFFFF_FFED;
+    // Armv8-M Secure, return to Thread mode, PSP, additional state context stacked
+    const ADDITIONAL: u32 = 0xFFFF_FFDD;
+
+    const PADDED: u32 = 0x0100_0200;
+    const UNPADDED: u32 = 0x0100_0000;
+
+    assert_eq!(stacked_pc_offset(BASIC), 24);
+    assert_eq!(stacked_xpsr_offset(BASIC), 28);
+    assert_eq!(stacked_frame_size(UNPADDED, BASIC), 32);
+    assert_eq!(stacked_frame_size(PADDED, BASIC), 36);
+
+    // the FP registers are stacked above xPSR and don't move the PC
+    assert_eq!(stacked_pc_offset(EXTENDED), 24);
+    assert_eq!(stacked_frame_size(UNPADDED, EXTENDED), 104);
+    assert_eq!(stacked_frame_size(PADDED, EXTENDED), 108);
+
+    assert_eq!(stacked_pc_offset(ADDITIONAL), 64);
+    assert_eq!(stacked_xpsr_offset(ADDITIONAL), 68);
+    assert_eq!(stacked_frame_size(UNPADDED, ADDITIONAL), 72);
+    assert_eq!(stacked_frame_size(PADDED, ADDITIONAL), 76);
+
+    // a frame stacked from a stack pointer that wasn't 8-byte aligned is padded, and locating the
+    // PC relative to the original stack pointer without the padding is off by one word
+    let pre_exception_sp = 0x2000_0FFC;
+    let frame = pre_exception_sp - stacked_frame_size(PADDED, BASIC);
+    assert_eq!(frame % 8, 0);
+    assert_eq!(frame + stacked_pc_offset(BASIC), 0x2000_0FF0);
+}
+
+#[test]
+fn control_active_stack() {
+    use crate::register::control::{Control, Spsel};
+
+    let msp = Control::from_bits(0);
+    let psp = Control::from_bits(0b10);
+
+    // Thread mode follows SPSEL
+    assert_eq!(msp.active_stack(0), Spsel::Msp);
+    assert_eq!(psp.active_stack(0), Spsel::Psp);
+
+    // Handler mode always runs on MSP
+    assert_eq!(msp.active_stack(11), Spsel::Msp);
+    assert_eq!(psp.active_stack(11), Spsel::Msp);
+    assert_eq!(psp.active_stack(16 + 42), Spsel::Msp);
+}
+
+#[test]
+fn control_matches_frame_type() {
+    use crate::register::control::{Control, Fpca};
+
+    const BASIC: u32 = 0xFFFF_FFFD;
+    const EXTENDED: u32 = 0xFFFF_FFED;
+
+    let mut control = Control::from_bits(0b10);
+    assert!(control.matches_frame_type(BASIC));
+    assert!(!control.matches_frame_type(EXTENDED));
+
+    control.set_fpca(Fpca::Active);
+    assert!(control.matches_frame_type(EXTENDED));
+    assert!(!control.matches_frame_type(BASIC));
+    crate::register::control::assert_fp_consistency(control, EXTENDED);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic]
+fn control_fp_consistency_mismatch() {
+    use crate::register::control::{assert_fp_consistency, Control};
+
+    assert_fp_consistency(Control::from_bits(0b10), 0xFFFF_FFED);
+}
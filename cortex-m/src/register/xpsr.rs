@@ -0,0 +1,112 @@
+//! Combined Program Status Register
+//!
+//! The xPSR is the combination of the APSR, IPSR and EPSR. It is the value that gets stacked on
+//! exception entry.
+//!
+//! The EPSR bits (Thumb bit and ICI/IT bits) always read as zero through `MRS`, so
+//! [`read`] only returns meaningful APSR and IPSR fields. The decoder is most useful when applied
+//! to a stacked xPSR word taken from an exception frame, using [`Xpsr::from_bits`].
+
+#[cfg(cortex_m)]
+use core::arch::asm;
+
+/// Combined Program Status Register
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Xpsr {
+    bits: u32,
+}
+
+impl Xpsr {
+    /// Creates a decoder for a raw xPSR value, e.g. one read from a stacked exception frame
+    #[inline]
+    pub const fn from_bits(bits: u32) -> Self {
+        Xpsr { bits }
+    }
+
+    /// Returns the contents of the register as raw bits
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.bits
+    }
+
+    /// Negative flag
+    #[inline]
+    pub const fn n(self) -> bool {
+        self.bits & (1 << 31) != 0
+    }
+
+    /// Zero flag
+    #[inline]
+    pub const fn z(self) -> bool {
+        self.bits & (1 << 30) != 0
+    }
+
+    /// Carry or borrow flag
+    #[inline]
+    pub const fn c(self) -> bool {
+        self.bits & (1 << 29) != 0
+    }
+
+    /// Overflow flag
+    #[inline]
+    pub const fn v(self) -> bool {
+        self.bits & (1 << 28) != 0
+    }
+
+    /// DSP overflow and saturation flag
+    #[inline]
+    pub const fn q(self) -> bool {
+        self.bits & (1 << 27) != 0
+    }
+
+    /// Greater than or equal flags, set by the SIMD instructions of the DSP extension
+    #[inline]
+    pub const fn ge(self) -> u8 {
+        ((self.bits >> 16) & 0xF) as u8
+    }
+
+    /// Thumb state bit
+    ///
+    /// Always set in a valid stacked xPSR. A clear bit means an attempt to execute in ARM state,
+    /// which raises an INVSTATE UsageFault.
+    #[inline]
+    pub const fn t(self) -> bool {
+        self.bits & (1 << 24) != 0
+    }
+
+    /// The ICI/IT bits, reassembled from bits `[26:25]` and `[15:10]`
+    ///
+    /// Inside an IT block these are the IT state bits `IT[7:0]`. After an interrupted
+    /// multi-register load or store they hold the continuation state (ICI) instead. Zero when
+    /// neither applies.
+    #[inline]
+    pub const fn ici_it(self) -> u8 {
+        (((self.bits >> 8) & 0xFC) | ((self.bits >> 25) & 0b11)) as u8
+    }
+
+    /// Stack alignment bit
+    ///
+    /// Only meaningful in a stacked xPSR: set when the processor inserted a padding word to align
+    /// the exception frame to 8 bytes.
+    #[inline]
+    pub const fn stack_realigned(self) -> bool {
+        self.bits & (1 << 9) != 0
+    }
+
+    /// The exception number of the active exception (ISR number), or zero in Thread mode
+    #[inline]
+    pub const fn exception_number(self) -> u16 {
+        (self.bits & 0x1FF) as u16
+    }
+}
+
+/// Reads the CPU register
+///
+/// The EPSR bits read as zero.
+#[cfg(cortex_m)]
+#[inline]
+pub fn read() -> Xpsr {
+    let bits;
+    unsafe { asm!("mrs {}, XPSR", out(reg) bits, options(nomem, nostack, preserves_flags)) };
+    Xpsr { bits }
+}
@@ -0,0 +1,90 @@
+//! Stack usage measurement
+//!
+//! The maximum stack usage of a program is measured by painting the stack with a known pattern
+//! early on, and later scanning for the lowest word that no longer holds the pattern: the stack
+//! grows downwards, so everything above it has been used at some point.
+//!
+//! # Ordering
+//!
+//! Painting overwrites `region`, so it must happen before the stack grows into it, and `region`
+//! must not include the stack frames that are live while painting. Paint as early as possible,
+//! typically at the start of `main` (or in the reset handler), over the part of the stack below
+//! the current stack pointer, keeping a margin for the frame of [`paint`] itself.
+//!
+//! # Stack bounds
+//!
+//! With `cortex-m-rt`, the stack grows down from `_stack_start` towards the end of the static
+//! data, which is where `__sheap` points. Neither is checked by default: the stack silently
+//! overwrites the static data when it overflows; see `MSPLIM`/`PSPLIM` on Armv8-M to catch
+//! that.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use core::ptr::addr_of_mut;
+//! use core::slice;
+//! use cortex_m::stack;
+//!
+//! extern "C" {
+//!     static mut __sheap: u32;
+//! }
+//!
+//! const PATTERN: u32 = 0xCCCC_CCCC;
+//! // leave room for the frame of `paint`
+//! const MARGIN: usize = 256;
+//!
+//! // the address of a local is close to the stack pointer (`register::msp::read`)
+//! let marker = 0u32;
+//! let sp = &marker as *const u32 as usize;
+//! let bottom = unsafe { addr_of_mut!(__sheap) };
+//! let len = (sp - MARGIN - bottom as usize) / 4;
+//! // NOTE(unsafe) the region between the static data and the live stack frames is unused
+//! stack::paint(unsafe { slice::from_raw_parts_mut(bottom, len) }, PATTERN);
+//!
+//! // .. run the program ..
+//!
+//! // the stack has written to the region since, so don't hold on to a reference to it
+//! let used = stack::high_water_mark(unsafe { slice::from_raw_parts(bottom, len) }, PATTERN);
+//! ```
+
+/// Fills `region` with `pattern`.
+///
+/// See the [module documentation](self) for when and where to paint.
+#[inline]
+pub fn paint(region: &mut [u32], pattern: u32) {
+    for word in region.iter_mut() {
+        // volatile, so that the writes to memory that is otherwise unused aren't optimized out
+        unsafe { core::ptr::write_volatile(word, pattern) };
+    }
+}
+
+/// Returns the number of bytes of `region` that have been used by the stack since it was
+/// [painted](paint) with `pattern`.
+///
+/// `region` is scanned from its lowest address, and everything from the first word that doesn't
+/// hold `pattern` up to the end of `region` is counted as used. A word that happens to be written
+/// with the pattern itself is not told apart, so pick a pattern unlikely to be stored on the
+/// stack. If the result is the size of `region`, the stack may have grown past it.
+///
+/// # Example
+///
+/// ```
+/// use cortex_m::stack;
+///
+/// let mut region = [0; 16];
+/// stack::paint(&mut region, 0xCCCC_CCCC);
+/// assert_eq!(stack::high_water_mark(&region, 0xCCCC_CCCC), 0);
+///
+/// // the stack grows down from the end of the region
+/// region[13] = 0;
+/// assert_eq!(stack::high_water_mark(&region, 0xCCCC_CCCC), 12);
+/// ```
+#[inline]
+pub fn high_water_mark(region: &[u32], pattern: u32) -> usize {
+    let untouched = region
+        .iter()
+        // volatile, as the stack may have been written behind the compiler's back
+        .take_while(|word| unsafe { core::ptr::read_volatile(*word) } == pattern)
+        .count();
+    (region.len() - untouched) * 4
+}
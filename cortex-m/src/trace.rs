@@ -0,0 +1,245 @@
+//! Coordinated trace session setup
+//!
+//! Getting trace output that host tools can decode and align requires the DCB, DWT, ITM and
+//! (optionally) TPIU to be configured together, in a specific order:
+//!
+//! 1. `DEMCR.TRCENA` is set first. Until it is, writes to the DWT and ITM registers may be
+//!    ignored.
+//! 2. The DWT and ITM software locks are removed.
+//! 3. The TPIU output (protocol, baud rate prescaler and formatter) is configured, before any
+//!    trace source starts emitting packets.
+//! 4. The DWT cycle counter is reset and started. It clocks the synchronization packets and the PC
+//!    sampling.
+//! 5. The ITM is enabled last, together with its timestamps and synchronization packets, and only
+//!    then are the stimulus ports opened.
+//!
+//! [`Session::builder`] applies a known-good configuration for a [`Preset`] in that order and
+//! returns a [`Session`] which restores the previous configuration when it is [stopped].
+//!
+//! [stopped]: Session::stop
+//!
+//! *NOTE* Not available on Armv6-M and Armv8-M Baseline.
+//!
+//! # Example
+//!
+//! ``` no_run
+//! use cortex_m::peripheral::tpiu::TraceProtocol;
+//! use cortex_m::trace::{Preset, Session, SwoConfig};
+//!
+//! let mut p = cortex_m::Peripherals::take().unwrap();
+//!
+//! let session = Session::builder(&mut p.DCB, &mut p.DWT, &mut p.ITM)
+//!     .swo(
+//!         &mut p.TPIU,
+//!         SwoConfig {
+//!             ref_clk_rate: 64_000_000,
+//!             baud_rate: 2_000_000,
+//!             protocol: TraceProtocol::AsyncSWONRZ,
+//!         },
+//!     )
+//!     .preset(Preset::TimestampedSoftwareTrace)
+//!     .start();
+//!
+//! // .. trace
+//!
+//! session.stop(100_000).ok();
+//! ```
+
+use crate::peripheral::dcb::DCB_DEMCR_TRCENA;
+use crate::peripheral::dwt::{Ctrl, PostCntTap, SyncTap};
+use crate::peripheral::itm::{
+    GlobalTimestampOptions, ITMSettings, LocalTimestampOptions, Tcr, TimestampClkSrc,
+};
+use crate::peripheral::tpiu::{poll_until, TpiuError, TraceProtocol};
+use crate::peripheral::{DCB, DWT, ITM, TPIU};
+
+/// A known-good trace configuration
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Preset {
+    /// Software trace through the ITM stimulus ports, with local timestamps clocked by the
+    /// processor clock and periodic synchronization packets.
+    TimestampedSoftwareTrace,
+    /// Periodic PC sampling by the DWT, forwarded through the ITM, with local timestamps and
+    /// periodic synchronization packets. A PC sample is generated every 1024 processor cycles.
+    PcSampling,
+}
+
+/// SWO output configuration of the TPIU
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct SwoConfig {
+    /// Frequency of the asynchronous TPIU reference clock, in Hz.
+    pub ref_clk_rate: u32,
+    /// Wanted SWO baud rate.
+    pub baud_rate: u32,
+    /// Trace output protocol.
+    pub protocol: TraceProtocol,
+}
+
+/// Builder for a trace [`Session`]
+pub struct SessionBuilder<'a> {
+    dcb: &'a mut DCB,
+    dwt: &'a mut DWT,
+    itm: &'a mut ITM,
+    swo: Option<(&'a mut TPIU, SwoConfig)>,
+    preset: Preset,
+}
+
+impl<'a> SessionBuilder<'a> {
+    /// Also configures the TPIU for SWO output.
+    ///
+    /// Leave this out if the debugger configures the TPIU itself.
+    #[inline]
+    pub fn swo(mut self, tpiu: &'a mut TPIU, config: SwoConfig) -> Self {
+        self.swo = Some((tpiu, config));
+        self
+    }
+
+    /// Selects the configuration to apply. Defaults to [`Preset::TimestampedSoftwareTrace`].
+    #[inline]
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.preset = preset;
+        self
+    }
+
+    /// Applies the configuration and starts tracing.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn start(self) -> Session<'a> {
+        let SessionBuilder {
+            dcb,
+            dwt,
+            itm,
+            swo,
+            preset,
+        } = self;
+
+        let saved = Saved {
+            demcr: dcb.demcr.read(),
+            dwt_ctrl: dwt.ctrl.read(),
+            itm_tcr: itm.tcr.read(),
+            itm_ter: itm.ter[0].read(),
+        };
+
+        // 1. Global trace enable
+        dcb.enable_trace();
+
+        // 2. Software locks
+        DWT::unlock();
+        itm.unlock();
+
+        // 3. Trace output
+        if let Some((tpiu, config)) = swo {
+            tpiu.set_trace_output_protocol(config.protocol);
+            tpiu.set_swo_baud_rate(config.ref_clk_rate, config.baud_rate);
+            // the formatter is only needed to multiplex the ETM into the output, and is not
+            // understood by SWO decoders
+            tpiu.enable_continuous_formatting(false);
+        }
+
+        // 4. Cycle counter, synchronization and PC sampling
+        dwt.set_cycle_count(0);
+        dwt.set_sync_tap(SyncTap::Cycles2Pow24);
+        if preset == Preset::PcSampling {
+            // (15 + 1) * 64 = 1024 cycles
+            dwt.set_sample_period(PostCntTap::Cycles64, 15);
+        }
+        dwt.enable_cycle_counter(dcb);
+        if preset == Preset::PcSampling {
+            dwt.enable_pc_samples(true);
+        }
+
+        // 5. ITM
+        itm.configure(
+            dcb,
+            ITMSettings {
+                enable: true,
+                forward_dwt: preset == Preset::PcSampling,
+                local_timestamps: LocalTimestampOptions::Enabled,
+                global_timestamps: GlobalTimestampOptions::Disabled,
+                bus_id: None,
+                timestamp_clk_src: TimestampClkSrc::SystemClock,
+            },
+        );
+        itm.enable_sync_packets(true);
+        if preset == Preset::TimestampedSoftwareTrace {
+            unsafe { itm.ter[0].write(0xFFFF_FFFF) }
+        }
+
+        Session {
+            dcb,
+            dwt,
+            itm,
+            saved,
+        }
+    }
+}
+
+struct Saved {
+    demcr: u32,
+    dwt_ctrl: Ctrl,
+    itm_tcr: Tcr,
+    itm_ter: u32,
+}
+
+/// A running trace session
+///
+/// Created with [`Session::builder`]. The borrowed peripherals stay reserved for the session until
+/// it is [stopped](Session::stop).
+pub struct Session<'a> {
+    dcb: &'a mut DCB,
+    dwt: &'a mut DWT,
+    itm: &'a mut ITM,
+    saved: Saved,
+}
+
+impl<'a> Session<'a> {
+    /// Creates a builder for a trace session.
+    #[inline]
+    pub fn builder(dcb: &'a mut DCB, dwt: &'a mut DWT, itm: &'a mut ITM) -> SessionBuilder<'a> {
+        SessionBuilder {
+            dcb,
+            dwt,
+            itm,
+            swo: None,
+            preset: Preset::TimestampedSoftwareTrace,
+        }
+    }
+
+    /// Stops tracing and restores the DCB, DWT and ITM configuration from before the session was
+    /// started.
+    ///
+    /// Tear-down happens in the reverse order of the set-up: the trace sources are stopped and the
+    /// ITM is drained first, and `DEMCR.TRCENA` is restored last. The TPIU configuration is left
+    /// untouched.
+    ///
+    /// Draining the ITM gives up after at least `timeout_cycles` processor cycles, e.g. when the
+    /// trace clock is stopped. The configuration is restored either way, but then
+    /// [`TpiuError::FlushTimeout`] is returned: packets still held by the ITM may be lost.
+    #[allow(clippy::missing_inline_in_public_items)]
+    pub fn stop(self, timeout_cycles: u32) -> Result<(), TpiuError> {
+        let Session {
+            dcb,
+            dwt,
+            itm,
+            saved,
+        } = self;
+
+        // stop the sources first, then wait until all pending packets are emitted
+        unsafe {
+            itm.ter[0].write(saved.itm_ter);
+            dwt.ctrl.write(saved.dwt_ctrl);
+        }
+        let drained = poll_until(timeout_cycles, || !itm.is_busy());
+        unsafe {
+            itm.tcr.write(saved.itm_tcr);
+
+            dcb.demcr
+                .modify(|w| (w & !DCB_DEMCR_TRCENA) | (saved.demcr & DCB_DEMCR_TRCENA));
+        }
+
+        if drained {
+            Ok(())
+        } else {
+            Err(TpiuError::FlushTimeout)
+        }
+    }
+}
@@ -1,6 +1,14 @@
 fn main() {
     let target = std::env::var("TARGET").unwrap();
 
+    println!("cargo:rustc-check-cfg=cfg(armv6m)");
+    println!("cargo:rustc-check-cfg=cfg(armv7em)");
+    println!("cargo:rustc-check-cfg=cfg(armv7m)");
+    println!("cargo:rustc-check-cfg=cfg(armv8m)");
+    println!("cargo:rustc-check-cfg=cfg(armv8m_base)");
+    println!("cargo:rustc-check-cfg=cfg(armv8m_main)");
+    println!("cargo:rustc-check-cfg=cfg(has_fpu)");
+
     if target.starts_with("thumbv6m-") {
         println!("cargo:rustc-cfg=armv6m");
     } else if target.starts_with("thumbv7m-") {
@@ -15,4 +23,8 @@ fn main() {
         println!("cargo:rustc-cfg=armv8m");
         println!("cargo:rustc-cfg=armv8m_main");
     }
+
+    if target.ends_with("-eabihf") {
+        println!("cargo:rustc-cfg=has_fpu");
+    }
 }
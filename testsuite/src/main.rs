@@ -42,7 +42,7 @@ mod tests {
             p.DWT.set_cycle_count(TEST_COUNT);
             assert_eq!(DWT::cycle_count(), TEST_COUNT);
 
-            p.DWT.enable_cycle_counter();
+            p.DWT.enable_cycle_counter(&mut p.DCB);
             assert!(DWT::cycle_count() > TEST_COUNT);
         }
 
@@ -51,4 +51,135 @@ mod tests {
             assert!(!p.DWT.has_cycle_counter());
         }
     }
+
+    #[test]
+    fn critical_section_single_core() {
+        use cortex_m::{interrupt, register::primask};
+
+        for enabled in [true, false] {
+            if enabled {
+                unsafe { interrupt::enable() };
+            } else {
+                interrupt::disable();
+            }
+
+            critical_section::with(|_| {
+                assert!(primask::read().is_inactive());
+                critical_section::with(|_| assert!(primask::read().is_inactive()));
+                // leaving the inner section must not unmask interrupts
+                assert!(primask::read().is_inactive());
+            });
+
+            assert_eq!(primask::read().is_active(), enabled);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "rtt", not(armv6m)))] // QEMU does not model the DWT counters
+    fn dwt_take_count(p: &mut cortex_m::Peripherals) {
+        use cortex_m::peripheral::DWT;
+
+        if p.DWT.enable_sleep_counting(&mut p.DCB).is_err() {
+            log!("no profiling counters, skipped");
+            return;
+        }
+        p.DWT.set_sleep_count(5);
+        assert_eq!(p.DWT.take_sleep_count(), 5);
+        assert_eq!(DWT::sleep_count(), 0);
+    }
+
+    #[test]
+    fn priority_bits() {
+        use cortex_m::peripheral::{scb::SystemHandler, SCB};
+
+        let pendsv = SCB::get_priority(SystemHandler::PendSV);
+        let bits = SCB::priority_bits();
+        assert!((2..=8).contains(&bits));
+        assert_eq!(SCB::get_priority(SystemHandler::PendSV), pendsv);
+    }
+
+    #[test]
+    #[cfg(all(feature = "rtt", not(armv6m)))] // QEMU does not model the FPB
+    fn fpb_remap_literal(p: &mut cortex_m::Peripherals) {
+        use core::ptr;
+        use cortex_m::peripheral::fpb::{FpbError, RemapTable};
+
+        static ANSWER: u32 = 41;
+        static PATCH: RemapTable = RemapTable::new([42; 8]);
+
+        let slot = p.FPB.capabilities().code_comparators as usize;
+        let addr = &ANSWER as *const u32 as u32;
+        match p.FPB.configure_remap(&PATCH, &[(slot, addr)]) {
+            Err(FpbError::RemapUnsupported) => {
+                log!("FPB remap not supported, skipping");
+                return;
+            }
+            result => result.unwrap(),
+        }
+
+        p.FPB.enable();
+        assert_eq!(unsafe { ptr::read_volatile(&ANSWER) }, 42);
+        p.FPB.disable();
+        assert_eq!(unsafe { ptr::read_volatile(&ANSWER) }, 41);
+    }
+
+    #[test]
+    #[cfg(all(feature = "rtt", not(armv6m)))] // QEMU does not model the FPB
+    fn fpb_snapshot_restore(p: &mut cortex_m::Peripherals) {
+        use cortex_m::peripheral::FPB;
+
+        let before = p.FPB.snapshot();
+        let hits = p.FPB.with_breakpoint(0, 0x0000_0100, || {
+            let fpb = unsafe { &*FPB::PTR };
+            assert_eq!(fpb.ctrl.read() & 1, 1);
+            assert_ne!(fpb.comp[0].read(), before.comparators()[0]);
+            1
+        });
+        assert_eq!(hits, Ok(1));
+
+        let after = p.FPB.snapshot();
+        assert_eq!(after.is_enabled(), before.is_enabled());
+        assert_eq!(after.comparators(), before.comparators());
+    }
+
+    #[test]
+    #[cfg(has_fpu)]
+    fn fpscr_flags() {
+        use core::hint::black_box;
+        use cortex_m::register::fpscr::{self, FlagSet};
+
+        let rmode = fpscr::read().rmode();
+        fpscr::take_flags();
+        assert!(!fpscr::read().division_by_zero());
+
+        let quotient = black_box(1.0f32) / black_box(0.0f32);
+        assert!(quotient.is_infinite());
+        assert!(fpscr::read().division_by_zero());
+
+        let flags = fpscr::take_flags();
+        assert!(flags.contains(FlagSet::DIVISION_BY_ZERO));
+        assert!(fpscr::read().flags().is_empty());
+        assert_eq!(fpscr::read().rmode(), rmode);
+    }
+
+    #[test]
+    #[cfg(has_fpu)]
+    fn fpscr_rounding_mode() {
+        use core::hint::black_box;
+        use cortex_m::register::fpscr::{self, RoundingMode};
+
+        assert_eq!(fpscr::rounding_mode(), RoundingMode::Nearest);
+
+        // 1/3 = 0x3EAAAAAA_AAA..., which rounds up to nearest but is truncated towards zero
+        let nearest = black_box(black_box(1.0f32) / black_box(3.0f32));
+        let zero = unsafe {
+            fpscr::with_rounding_mode(RoundingMode::Zero, || {
+                assert_eq!(fpscr::rounding_mode(), RoundingMode::Zero);
+                black_box(black_box(1.0f32) / black_box(3.0f32))
+            })
+        };
+        assert_eq!(nearest.to_bits(), 0x3EAA_AAAB);
+        assert_eq!(zero.to_bits(), 0x3EAA_AAAA);
+        assert_eq!(fpscr::rounding_mode(), RoundingMode::Nearest);
+    }
 }
@@ -51,7 +51,7 @@ fn build(package: &str, target: &str, features: &[&str]) {
 
 #[rustfmt::skip]
 static PACKAGE_FEATURES: &[(&str, &[&str], &[&str])] = &[
-    ("cortex-m", ALL_TARGETS, &["cm7-r0p1"]),
+    ("cortex-m", ALL_TARGETS, &["cm7-r0p1", "cm7-r0p1-detect"]),
     ("cortex-m-semihosting", ALL_TARGETS, &["no-semihosting", "jlink-quirks"]),
     ("panic-semihosting", ALL_TARGETS, &["exit", "jlink-quirks"]),
     ("panic-itm", NON_BASE_TARGETS, &[]),
@@ -66,7 +66,7 @@ fn check_crates_build(_is_nightly: bool) {
             let should_use_feature = |feat: &str| {
                 match feat {
                     // This only affects thumbv7em targets.
-                    "cm7-r0p1" => target.starts_with("thumbv7em"),
+                    "cm7-r0p1" | "cm7-r0p1-detect" => target.starts_with("thumbv7em"),
                     _ => true,
                 }
             };